@@ -1,22 +1,37 @@
+mod watchdog;
+
 use std::{
     fs::File,
     io::{self, BufRead, BufReader, BufWriter, Write},
     path::{Path, PathBuf},
     process::{Child, ChildStdin, ChildStdout, Command, Stdio},
-    time::Duration,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 use wazir_drop::{
     AnyMove, CliCommand, Color, Player, PlayerFactory, Position, ShortMove,
-    clock::Timer,
-    movegen,
+    clock::{TimeControl, Timer},
+    log, movegen,
     parser::{self, ParserExt},
 };
 
+use watchdog::Watchdog;
+
+/// How long past its clock an external player may think before it is killed.
+pub const WATCHDOG_GRACE: Duration = Duration::from_millis(500);
+
+/// With a time control, the subprocess is killed if it is still thinking `WATCHDOG_GRACE` after its
+/// clock or move cap runs out, so a hung engine loses on time instead of stalling the match.
+///
+/// The engine is told the base time and the move cap. The protocol has no increment, so it doesn't
+/// know about that.
 #[derive(Debug)]
 pub struct ExternalPlayer {
-    subprocess: Child,
+    subprocess: Arc<Mutex<Child>>,
     stdin: BufWriter<ChildStdin>,
     stdout: BufReader<ChildStdout>,
+    watchdog: Option<Watchdog>,
+    move_cap: Option<Duration>,
 }
 
 impl ExternalPlayer {
@@ -24,8 +39,9 @@ impl ExternalPlayer {
         path: &Path,
         log_path: &Path,
         color: Color,
+        start: &Position,
         opening: &[AnyMove],
-        time_limit: Option<Duration>,
+        time_control: Option<TimeControl>,
     ) -> io::Result<Self> {
         let log_file = File::create(log_path)?;
         let mut subprocess = Command::new(path)
@@ -35,64 +51,127 @@ impl ExternalPlayer {
             .spawn()?;
         let stdin = BufWriter::new(subprocess.stdin.take().unwrap());
         let stdout = BufReader::new(subprocess.stdout.take().unwrap());
+        let subprocess = Arc::new(Mutex::new(subprocess));
+        let watchdog = time_control.map(|_| Watchdog::new(&subprocess));
         let mut this = Self {
             subprocess,
             stdin,
             stdout,
+            watchdog,
+            move_cap: time_control.and_then(|time_control| time_control.move_cap),
         };
-        if let Some(time_limit) = time_limit {
-            this.send_command(CliCommand::TimeLimit(time_limit));
+        if let Some(time_control) = time_control {
+            this.try_send_command(CliCommand::TimeLimit(time_control.base))?;
+            if let Some(move_cap) = time_control.move_cap {
+                this.try_send_command(CliCommand::MoveCap(move_cap))?;
+            }
+        }
+        if start.ply() != 0 {
+            this.try_send_command(CliCommand::Position(*start))?;
         }
         if !opening.is_empty() {
-            this.send_command(CliCommand::Opening(opening.to_vec()));
+            this.try_send_command(CliCommand::Opening(opening.to_vec()))?;
         }
-        if color.index() == opening.len() % Color::COUNT {
-            this.send_command(CliCommand::Start);
+        if color.index() == (usize::from(start.ply()) + opening.len()) % Color::COUNT {
+            this.try_send_command(CliCommand::Start)?;
         }
         Ok(this)
     }
 
+    /// Like `Player::opponent_move`, but returns errors instead of panicking.
+    pub fn try_opponent_move(&mut self, mov: AnyMove) -> io::Result<()> {
+        self.try_send_command(CliCommand::OpponentMove(mov.into()))
+    }
+
+    /// Like `Player::make_move`, but returns errors instead of panicking. `None` if the watchdog
+    /// killed the subprocess.
+    pub fn try_make_move(
+        &mut self,
+        position: &Position,
+        timer: &Timer,
+    ) -> io::Result<Option<AnyMove>> {
+        if let Some(watchdog) = &self.watchdog {
+            let mut out_of_time = timer.instant_at(Duration::ZERO);
+            if let Some(move_cap) = self.move_cap {
+                out_of_time = out_of_time.min(Instant::now() + move_cap);
+            }
+            watchdog.arm(out_of_time + WATCHDOG_GRACE);
+        }
+        let short_move = self.read_move();
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.disarm();
+        }
+        let Some(short_move) = short_move? else {
+            log::info!("external player killed by watchdog");
+            return Ok(None);
+        };
+        let mov = movegen::any_move_from_short_move(position, short_move).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid move: {short_move}"),
+            )
+        })?;
+        Ok(Some(mov))
+    }
+
     fn try_send_command(&mut self, command: CliCommand) -> Result<(), io::Error> {
         writeln!(self.stdin, "{command}")?;
         self.stdin.flush()?;
         Ok(())
     }
 
-    fn send_command(&mut self, command: CliCommand) {
-        self.try_send_command(command)
-            .unwrap_or_else(|e| panic!("Failed to send command: {e}"));
-    }
-
-    fn read_move(&mut self) -> ShortMove {
+    /// `None` if the watchdog killed the subprocess.
+    fn read_move(&mut self) -> io::Result<Option<ShortMove>> {
         let mut line = Vec::new();
-        _ = self
-            .stdout
-            .read_until(b'\n', &mut line)
-            .unwrap_or_else(|e| panic!("Failed to read line: {e}"));
-        ShortMove::parser()
+        let result = self.stdout.read_until(b'\n', &mut line);
+        if self.watchdog.as_ref().is_some_and(Watchdog::killed) {
+            return Ok(None);
+        }
+        if result? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let mov = ShortMove::parser()
             .then_ignore(parser::endl())
             .parse_all(&line)
-            .unwrap_or_else(|_| panic!("Can't parse move: {}", String::from_utf8_lossy(&line)))
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Can't parse move: {}", String::from_utf8_lossy(&line)),
+                )
+            })?;
+        Ok(Some(mov))
     }
 }
 
 impl Player for ExternalPlayer {
     fn opponent_move(&mut self, _position: &Position, mov: AnyMove, _timer: &Timer) {
-        self.send_command(CliCommand::OpponentMove(mov.into()));
+        self.try_opponent_move(mov)
+            .unwrap_or_else(|e| panic!("Failed to send command: {e}"));
     }
 
-    fn make_move(&mut self, position: &Position, _timer: &Timer) -> AnyMove {
-        let short_move = self.read_move();
-        movegen::any_move_from_short_move(position, short_move)
-            .unwrap_or_else(|_| panic!("Invalid move: {short_move}"))
+    fn make_move(&mut self, position: &Position, timer: &Timer) -> AnyMove {
+        match self.try_make_move(position, timer) {
+            Ok(Some(mov)) => mov,
+            // Out of time, so the move is never played. Any legal move will do, or a suicide if
+            // there is none.
+            Ok(None) => movegen::legal_moves(position)
+                .chain(movegen::any_pseudomoves(position))
+                .next()
+                .expect("No moves"),
+            Err(e) => panic!("External player failed: {e}"),
+        }
     }
 }
 
 impl Drop for ExternalPlayer {
     fn drop(&mut self) {
         _ = self.try_send_command(CliCommand::Quit);
+        // Join the watchdog thread before waiting for the subprocess.
+        self.watchdog = None;
         _ = self
             .subprocess
+            .lock()
+            .unwrap()
             .wait()
             .unwrap_or_else(|e| panic!("Failed to wait for external player to quit: {e}"));
     }
@@ -120,16 +199,18 @@ impl PlayerFactory for ExternalPlayerFactory {
         &self,
         game_id: &str,
         color: Color,
+        start: &Position,
         opening: &[AnyMove],
-        time_limit: std::option::Option<Duration>,
+        time_control: Option<TimeControl>,
     ) -> Box<dyn Player> {
         let log_path = self
             .log_dir
             .join(format!("{name}-{game_id}-{color}.log", name = self.name));
-        let player = match ExternalPlayer::new(&self.path, &log_path, color, opening, time_limit) {
-            Ok(player) => player,
-            Err(e) => panic!("Failed to run external player: {e}"),
-        };
+        let player =
+            match ExternalPlayer::new(&self.path, &log_path, color, start, opening, time_control) {
+                Ok(player) => player,
+                Err(e) => panic!("Failed to run external player: {e}"),
+            };
         Box::new(player)
     }
 }
@@ -0,0 +1,98 @@
+use std::{
+    process::Child,
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+/// Kills a subprocess that is still thinking when its deadline passes.
+#[derive(Debug)]
+pub struct Watchdog {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    condvar: Condvar,
+    subprocess: Arc<Mutex<Child>>,
+}
+
+#[derive(Debug)]
+struct State {
+    deadline: Option<Instant>,
+    killed: bool,
+    quit: bool,
+}
+
+impl Watchdog {
+    pub fn new(subprocess: &Arc<Mutex<Child>>) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                deadline: None,
+                killed: false,
+                quit: false,
+            }),
+            condvar: Condvar::new(),
+            subprocess: Arc::clone(subprocess),
+        });
+        let thread = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || shared.run())
+        };
+        Self {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Kill the subprocess at `deadline` unless `disarm` is called first.
+    pub fn arm(&self, deadline: Instant) {
+        self.shared.state.lock().unwrap().deadline = Some(deadline);
+        self.shared.condvar.notify_one();
+    }
+
+    pub fn disarm(&self) {
+        self.shared.state.lock().unwrap().deadline = None;
+        self.shared.condvar.notify_one();
+    }
+
+    /// Whether the subprocess has been killed.
+    pub fn killed(&self) -> bool {
+        self.shared.state.lock().unwrap().killed
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().quit = true;
+        self.shared.condvar.notify_one();
+        if let Some(thread) = self.thread.take() {
+            thread.join().expect("Watchdog thread panicked");
+        }
+    }
+}
+
+impl Shared {
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        while !state.quit {
+            match state.deadline {
+                None => {
+                    state = self.condvar.wait(state).unwrap();
+                }
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        _ = self.subprocess.lock().unwrap().kill();
+                        state.killed = true;
+                        state.deadline = None;
+                    } else {
+                        state = self.condvar.wait_timeout(state, deadline - now).unwrap().0;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+#![cfg(unix)]
+
+use external_player::{ExternalPlayer, WATCHDOG_GRACE};
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use wazir_drop::{
+    Color, Player, Position,
+    clock::{TimeControl, Timer},
+};
+
+fn engine_script(name: &str, script: &str) -> PathBuf {
+    // A directory per engine, so tests running in parallel can each remove their own.
+    let dir = env::temp_dir().join(format!("wazir-drop-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, script).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+fn hanging_engine(name: &str) -> PathBuf {
+    engine_script(name, "#!/bin/sh\nexec sleep 60\n")
+}
+
+#[test]
+fn test_watchdog_kills_hanging_engine() {
+    let path = hanging_engine("hang.sh");
+    let log_path = path.with_extension("log");
+    let time_limit = Duration::from_millis(100);
+    let mut player = ExternalPlayer::new(
+        &path,
+        &log_path,
+        Color::Red,
+        &Position::initial(),
+        &[],
+        Some(TimeControl::fixed(time_limit)),
+    )
+    .unwrap();
+
+    let mut timer = Timer::new(time_limit);
+    let start = Instant::now();
+    timer.start();
+    let mov = player.make_move(&Position::initial(), &timer);
+    timer.stop();
+
+    assert!(Position::initial().make_any_move(mov).is_ok());
+    assert_eq!(timer.get(), Duration::ZERO);
+    assert!(start.elapsed() >= time_limit + WATCHDOG_GRACE);
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    let start = Instant::now();
+    drop(player);
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_try_make_move_errors() {
+    let garbage = engine_script(
+        "garbage.sh",
+        "#!/bin/sh\necho garbage\nwhile read line; do [ \"$line\" = Quit ] && exit 0; done\n",
+    );
+    let quits = engine_script("quits.sh", "#!/bin/sh\nexit 0\n");
+    let time_limit = Duration::from_secs(10);
+    for path in [garbage, quits] {
+        let mut timer = Timer::new(time_limit);
+        timer.start();
+        // An engine that quits may already fail on the first command.
+        if let Ok(mut player) = ExternalPlayer::new(
+            &path,
+            &path.with_extension("log"),
+            Color::Red,
+            &Position::initial(),
+            &[],
+            Some(TimeControl::fixed(time_limit)),
+        ) {
+            assert!(player.try_make_move(&Position::initial(), &timer).is_err());
+        }
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
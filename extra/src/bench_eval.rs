@@ -0,0 +1,177 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use wazir_drop::{
+    Color, EvalDetail, Evaluator, Features, Move, Position, SetupMove, constants::Eval,
+    enums::EnumMap,
+};
+
+/// Counts how often accumulator updates take the incremental path.
+#[derive(Debug)]
+pub struct DiffStats {
+    incremental: AtomicU64,
+    full: AtomicU64,
+}
+
+impl DiffStats {
+    pub const fn new() -> Self {
+        Self {
+            incremental: AtomicU64::new(0),
+            full: AtomicU64::new(0),
+        }
+    }
+
+    /// Updates applied as a diff.
+    pub fn incremental(&self) -> u64 {
+        self.incremental.load(Ordering::Relaxed)
+    }
+
+    /// Updates that recomputed all features.
+    pub fn full(&self) -> u64 {
+        self.full.load(Ordering::Relaxed)
+    }
+
+    /// Percentage of updates applied as a diff.
+    pub fn incremental_percent(&self) -> f64 {
+        let incremental = self.incremental();
+        let total = incremental + self.full();
+        if total == 0 {
+            0.0
+        } else {
+            100.0 * incremental as f64 / total as f64
+        }
+    }
+
+    fn record<T>(&self, diff: &Option<T>) {
+        let counter = if diff.is_some() {
+            &self.incremental
+        } else {
+            &self.full
+        };
+        _ = counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Default for DiffStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Features that record diff statistics and can disable incremental updates.
+#[derive(Debug, Clone, Copy)]
+pub struct BenchFeatures<F> {
+    features: F,
+    incremental: bool,
+    stats: &'static DiffStats,
+}
+
+impl<F: Features> Features for BenchFeatures<F> {
+    fn name(self) -> &'static str {
+        self.features.name()
+    }
+
+    fn count(self) -> usize {
+        self.features.count()
+    }
+
+    fn approximate_avg_set(self) -> f64 {
+        self.features.approximate_avg_set()
+    }
+
+    fn all(self, position: &Position, color: Color) -> impl Iterator<Item = usize> {
+        self.features.all(position, color)
+    }
+
+    fn diff_setup(
+        self,
+        mov: SetupMove,
+        new_position: &Position,
+        color: Color,
+    ) -> Option<(impl Iterator<Item = usize>, impl Iterator<Item = usize>)> {
+        let diff = if self.incremental {
+            self.features.diff_setup(mov, new_position, color)
+        } else {
+            None
+        };
+        self.stats.record(&diff);
+        diff
+    }
+
+    fn diff(
+        self,
+        mov: Move,
+        new_position: &Position,
+        color: Color,
+    ) -> Option<(impl Iterator<Item = usize>, impl Iterator<Item = usize>)> {
+        let diff = if self.incremental {
+            self.features.diff(mov, new_position, color)
+        } else {
+            None
+        };
+        self.stats.record(&diff);
+        diff
+    }
+}
+
+/// Wraps an evaluator for benchmarking incremental vs full evaluation.
+///
+/// With `incremental == false`, accumulators are recomputed from scratch after every move.
+#[derive(Debug)]
+pub struct BenchEvaluator<E: Evaluator> {
+    evaluator: E,
+    features: BenchFeatures<E::Features>,
+}
+
+impl<E: Evaluator> BenchEvaluator<E> {
+    pub fn new(evaluator: E, incremental: bool, stats: &'static DiffStats) -> Self {
+        let features = BenchFeatures {
+            features: evaluator.features(),
+            incremental,
+            stats,
+        };
+        Self {
+            evaluator,
+            features,
+        }
+    }
+}
+
+impl<E: Evaluator> Evaluator for BenchEvaluator<E> {
+    type Accumulator = E::Accumulator;
+    type Features = BenchFeatures<E::Features>;
+
+    fn features(&self) -> Self::Features {
+        self.features
+    }
+
+    fn new_accumulator(&self) -> Self::Accumulator {
+        self.evaluator.new_accumulator()
+    }
+
+    fn add_feature(&self, accumulator: &mut Self::Accumulator, feature: usize) {
+        self.evaluator.add_feature(accumulator, feature);
+    }
+
+    fn remove_feature(&self, accumulator: &mut Self::Accumulator, feature: usize) {
+        self.evaluator.remove_feature(accumulator, feature);
+    }
+
+    fn evaluate(&self, accumulators: &EnumMap<Color, Self::Accumulator>, to_move: Color) -> Eval {
+        self.evaluator.evaluate(accumulators, to_move)
+    }
+
+    fn scale(&self) -> f64 {
+        self.evaluator.scale()
+    }
+
+    fn evaluate_detailed(&self, position: &Position, color: Color) -> EvalDetail {
+        self.evaluator.evaluate_detailed(position, color)
+    }
+
+    fn name(&self) -> &str {
+        self.evaluator.name()
+    }
+
+    fn version(&self) -> u64 {
+        self.evaluator.version()
+    }
+}
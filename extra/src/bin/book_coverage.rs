@@ -0,0 +1,67 @@
+use clap::Parser;
+use extra::BookCoverage;
+use std::{error::Error, fs, path::PathBuf, process::ExitCode, str::FromStr};
+use wazir_drop::{AnyMove, SetupMove, book, constants::PLY_AFTER_SETUP};
+
+/// Reports how games used the compiled opening book.
+///
+/// Each game file has one move per line, as saved by the GUI.
+#[derive(Parser, Debug)]
+struct Args {
+    games: Vec<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    if let Err(e) = run() {
+        eprintln!("Error: {e}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let openings = book::openings();
+    let num_openings = openings.len();
+    let mut coverage = BookCoverage::new(openings);
+    for path in &args.games {
+        let (red, blue) =
+            read_setups(path).map_err(|e| format!("{path}: {e}", path = path.display()))?;
+        coverage.add_game(red, blue);
+    }
+
+    let num_games = coverage.num_games();
+    let percent = |count: u64| 100.0 * count as f64 / num_games.max(1) as f64;
+    println!("games: {num_games}");
+    for ply in 0..usize::from(PLY_AFTER_SETUP) {
+        let count = coverage.left_book_at(ply);
+        println!("left book at ply {ply}: {count} ({:.1}%)", percent(count));
+    }
+    let count = coverage.left_book_at(PLY_AFTER_SETUP.into());
+    println!("stayed in book: {count} ({:.1}%)", percent(count));
+    let unused: Vec<usize> = coverage.unused().collect();
+    println!(
+        "openings reached: {reached}/{num_openings}",
+        reached = num_openings - unused.len()
+    );
+    let unused: Vec<String> = unused.iter().map(usize::to_string).collect();
+    println!("unused: {}", unused.join(" "));
+    Ok(())
+}
+
+/// The two setup moves at the start of a game file.
+fn read_setups(path: &PathBuf) -> Result<(SetupMove, SetupMove), Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let mut moves = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| AnyMove::from_str(line).map_err(|_| format!("invalid move {line}")));
+    let mut next_setup = || match moves.next().transpose()? {
+        Some(AnyMove::Setup(mov)) => Ok(mov),
+        _ => Err("game doesn't start with two setup moves".to_string()),
+    };
+    let red = next_setup()?;
+    let blue = next_setup()?;
+    Ok((red, blue))
+}
@@ -0,0 +1,96 @@
+use extra::{BenchEvaluator, DiffStats, moverand};
+use rand::{SeedableRng, rngs::StdRng};
+use std::{
+    env,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use wazir_drop::{
+    EvalCacheStats, History, Nnue, Position, Search, Stage,
+    constants::{Depth, Hyperparameters, ONE_PLY, PLY_AFTER_SETUP},
+};
+
+const NUM_POSITIONS: usize = 20;
+const RANDOM_PLIES: usize = 12;
+const DEPTH: Depth = 8 * ONE_PLY;
+
+static INCREMENTAL_STATS: DiffStats = DiffStats::new();
+static FULL_STATS: DiffStats = DiffStats::new();
+
+/// Compares search speed with incremental accumulator updates vs full recomputation.
+///
+/// Build with `--features make-unmake` to measure the make/unmake quiescence search. Optional
+/// arguments set `eval_cache_size` in bytes and `search_threads`; the time is time to depth.
+fn main() {
+    println!(
+        "quiescence: {}",
+        if cfg!(feature = "make-unmake") {
+            "make-unmake"
+        } else {
+            "copy"
+        }
+    );
+    let eval_cache_size = match env::args().nth(1) {
+        Some(arg) => arg.parse().expect("Invalid eval cache size"),
+        None => 0,
+    };
+    let search_threads = match env::args().nth(2) {
+        Some(arg) => arg.parse().expect("Invalid number of threads"),
+        None => 1,
+    };
+    println!("eval cache: {eval_cache_size} bytes, threads: {search_threads}");
+    let positions = bench_positions();
+    let hyperparameters = Hyperparameters {
+        ttable_size: 16 << 20,
+        eval_cache_size,
+        search_threads,
+        ..Hyperparameters::default()
+    };
+    for (incremental, stats) in [(true, &INCREMENTAL_STATS), (false, &FULL_STATS)] {
+        let evaluator = Arc::new(BenchEvaluator::new(Nnue::default(), incremental, stats));
+        let mut nodes = 0;
+        let mut eval_cache_stats = EvalCacheStats::default();
+        let mut elapsed = Duration::ZERO;
+        for (position, history) in &positions {
+            // Fresh search for every position so the hash table doesn't carry over.
+            let mut search = Search::new(&hyperparameters, &evaluator);
+            let start = Instant::now();
+            let result = search.search(position, Some(DEPTH), None, None, false, history);
+            elapsed += start.elapsed();
+            nodes += result.nodes;
+            eval_cache_stats.hits += result.eval_cache_stats.hits;
+            eval_cache_stats.misses += result.eval_cache_stats.misses;
+        }
+        println!(
+            "{mode}: nodes={nodes} t={t}ms kns={knps:.0} diff={diff:.1}% ({incremental_updates} diff, {full_updates} full) cache_hits={hits:.1}%",
+            mode = if incremental { "incremental" } else { "full" },
+            t = elapsed.as_millis(),
+            knps = nodes as f64 / elapsed.as_secs_f64() / 1000.0,
+            diff = stats.incremental_percent(),
+            incremental_updates = stats.incremental(),
+            full_updates = stats.full(),
+            hits = 100.0 * eval_cache_stats.hit_rate(),
+        );
+    }
+}
+
+/// Positions a few random moves after setup, with their histories.
+fn bench_positions() -> Vec<(Position, History)> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut positions = Vec::with_capacity(NUM_POSITIONS);
+    'games: while positions.len() < NUM_POSITIONS {
+        let mut position = Position::initial();
+        let mut history = History::new_from_position(&position);
+        for _ in 0..usize::from(PLY_AFTER_SETUP) + RANDOM_PLIES {
+            let mov = moverand::random_move(&position, &mut rng);
+            let effect;
+            (position, effect) = position.make_any_move_with_effect(mov).unwrap();
+            history.push_position_after(&position, &effect);
+            if position.stage() != Stage::Regular && position.ply() >= PLY_AFTER_SETUP {
+                continue 'games;
+            }
+        }
+        positions.push((position, history));
+    }
+    positions
+}
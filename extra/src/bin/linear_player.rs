@@ -1,6 +1,7 @@
-use extra::LinearEvaluator;
 use std::{process::ExitCode, sync::Arc};
-use wazir_drop::{MainPlayerFactory, WPSFeatures, constants::Hyperparameters, run_cli};
+use wazir_drop::{
+    LinearEvaluator, MainPlayerFactory, WPSFeatures, constants::Hyperparameters, run_cli,
+};
 
 fn main() -> ExitCode {
     let player_factory = MainPlayerFactory::new(
@@ -0,0 +1,65 @@
+use wazir_drop::{SetupMove, Symmetry, constants::PLY_AFTER_SETUP};
+
+/// How a set of games used an opening book.
+#[derive(Debug, Clone)]
+pub struct BookCoverage {
+    /// (red, blue) with red normalized, as returned by `book::openings`.
+    openings: Vec<(SetupMove, SetupMove)>,
+    /// Number of games that played each opening in full.
+    hits: Vec<u64>,
+    /// Number of games that left the book at each ply. `PLY_AFTER_SETUP` means never.
+    left_book_at: [u64; PLY_AFTER_SETUP as usize + 1],
+}
+
+impl BookCoverage {
+    pub fn new(openings: Vec<(SetupMove, SetupMove)>) -> Self {
+        let hits = vec![0; openings.len()];
+        Self {
+            openings,
+            hits,
+            left_book_at: [0; PLY_AFTER_SETUP as usize + 1],
+        }
+    }
+
+    /// Records a game that started with the setups `red` and `blue`.
+    pub fn add_game(&mut self, red: SetupMove, blue: SetupMove) {
+        let (symmetry, red) = Symmetry::normalize_red_setup(red);
+        let Some(index) = self
+            .openings
+            .iter()
+            .position(|&(book_red, _)| book_red == red)
+        else {
+            self.left_book_at[0] += 1;
+            return;
+        };
+        if symmetry.inverse().apply_to_setup(self.openings[index].1) == blue {
+            self.hits[index] += 1;
+            self.left_book_at[usize::from(PLY_AFTER_SETUP)] += 1;
+        } else {
+            self.left_book_at[1] += 1;
+        }
+    }
+
+    pub fn num_games(&self) -> u64 {
+        self.left_book_at.iter().sum()
+    }
+
+    /// Number of games that left the book at `ply`. `PLY_AFTER_SETUP` counts games that didn't.
+    pub fn left_book_at(&self, ply: usize) -> u64 {
+        self.left_book_at[ply]
+    }
+
+    /// Number of games that played the opening with this index in full.
+    pub fn hits(&self, index: usize) -> u64 {
+        self.hits[index]
+    }
+
+    /// Indexes of openings no game played in full.
+    pub fn unused(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hits
+            .iter()
+            .enumerate()
+            .filter(|&(_, &hits)| hits == 0)
+            .map(|(index, _)| index)
+    }
+}
@@ -1,8 +1,14 @@
+mod bench_eval;
+mod book_coverage;
 mod linear_eval;
 mod linear_ps_weights;
-mod linear_wps_weights;
+pub mod logging;
 pub mod moverand;
 mod ps_features;
+mod wazir_safety_features;
 
-pub use linear_eval::LinearEvaluator;
+pub use bench_eval::{BenchEvaluator, BenchFeatures, DiffStats};
+pub use book_coverage::BookCoverage;
+pub use linear_eval::linear_ps_evaluator;
 pub use ps_features::PSFeatures;
+pub use wazir_safety_features::WazirSafetyFeatures;
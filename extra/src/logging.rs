@@ -0,0 +1,51 @@
+use clap::Args;
+use log::{LevelFilter, SetLoggerError};
+use simplelog::{ColorChoice, CombinedLogger, TermLogger, TerminalMode, WriteLogger};
+use std::fs::File;
+use wazir_drop::log::Verbosity;
+
+/// `--quiet` and `--verbose` flags, to be flattened into a binary's arguments.
+#[derive(Args, Debug)]
+pub struct VerbosityArgs {
+    /// Only log warnings and errors to the terminal.
+    #[arg(long, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Also log debug messages.
+    #[arg(long)]
+    pub verbose: bool,
+}
+
+impl VerbosityArgs {
+    /// Falls back to the environment if neither flag is given.
+    pub fn verbosity(&self) -> Verbosity {
+        Verbosity::new(self.quiet, self.verbose)
+    }
+}
+
+pub fn level_filter(verbosity: Verbosity) -> LevelFilter {
+    match verbosity {
+        Verbosity::Quiet => LevelFilter::Warn,
+        Verbosity::Normal => LevelFilter::Info,
+        Verbosity::Verbose => LevelFilter::Debug,
+    }
+}
+
+/// Logs to the terminal and to `log_file`.
+///
+/// The log file always gets at least info messages, so `--quiet` only affects the terminal.
+pub fn init_logger(verbosity: Verbosity, log_file: File) -> Result<(), SetLoggerError> {
+    let level = level_filter(verbosity);
+    CombinedLogger::init(vec![
+        WriteLogger::new(
+            level.max(LevelFilter::Info),
+            simplelog::Config::default(),
+            log_file,
+        ),
+        TermLogger::new(
+            level,
+            simplelog::Config::default(),
+            TerminalMode::Stderr,
+            ColorChoice::Auto,
+        ),
+    ])
+}
@@ -23,3 +23,14 @@ pub fn random_move<RNG: rand::Rng>(position: &Position, rng: &mut RNG) -> AnyMov
         Stage::End(_) => panic!("End of game"),
     }
 }
+
+/// Like `random_move`, but never a suicide unless every move is one.
+pub fn random_legal_move<RNG: Rng>(position: &Position, rng: &mut RNG) -> AnyMove {
+    match position.stage() {
+        // Every setup move is legal, and shuffling is much faster than picking among all of them.
+        Stage::Setup => AnyMove::Setup(random_setup(position.to_move(), rng)),
+        _ => movegen::legal_moves(position)
+            .choose(rng)
+            .unwrap_or_else(|| random_move(position, rng)),
+    }
+}
@@ -26,6 +26,10 @@ impl PSFeatures {
 }
 
 impl Features for PSFeatures {
+    fn name(self) -> &'static str {
+        "ps"
+    }
+
     fn count(self) -> usize {
         Self::CAPTURED_OFFSET + NUM_CAPTURED_INDEXES - 2
     }
@@ -0,0 +1,121 @@
+use wazir_drop::{
+    Bitboard, Color, Features, Move, NormalizedSquare, Position, SetupMove, Square, Symmetry,
+    smallvec::SmallVec,
+};
+
+/// Features of `F` plus how many enemy pieces are near each wazir.
+///
+/// For each wazir there is one feature indexed by its normalized square and the number of enemy
+/// pieces at most 2 squares away in any direction, capped at `MAX_NEAR`. They come after the
+/// features of `F`.
+#[derive(Debug, Clone, Copy)]
+pub struct WazirSafetyFeatures<F> {
+    features: F,
+}
+
+impl<F: Features> WazirSafetyFeatures<F> {
+    pub const MAX_NEAR: usize = 8;
+    const COUNT_PER_WAZIR: usize = NormalizedSquare::COUNT * (Self::MAX_NEAR + 1);
+
+    pub fn new(features: F) -> Self {
+        Self { features }
+    }
+
+    pub fn safety_feature(
+        self,
+        is_other_color: bool,
+        wazir_square: NormalizedSquare,
+        near: usize,
+    ) -> usize {
+        self.features.count()
+            + usize::from(is_other_color) * Self::COUNT_PER_WAZIR
+            + wazir_square.index() * (Self::MAX_NEAR + 1)
+            + near.min(Self::MAX_NEAR)
+    }
+
+    fn safety_features(self, position: &Position, color: Color) -> SmallVec<usize, 2> {
+        [(false, color), (true, color.opposite())]
+            .into_iter()
+            .filter_map(|(is_other_color, wazir_color)| {
+                let wazir_square = position.wazir_square(wazir_color)?;
+                let (_, wazir_nsquare) = Symmetry::normalize(wazir_square);
+                let near = (near_squares(wazir_square)
+                    & position.occupied_by(wazir_color.opposite()))
+                .count();
+                Some(self.safety_feature(is_other_color, wazir_nsquare, near))
+            })
+            .collect()
+    }
+
+    /// (added, removed) safety features going from `old_position` to `new_position`.
+    fn safety_diff(
+        self,
+        old_position: &Position,
+        new_position: &Position,
+        color: Color,
+    ) -> (SmallVec<usize, 2>, SmallVec<usize, 2>) {
+        let old = self.safety_features(old_position, color);
+        let new = self.safety_features(new_position, color);
+        let added = new.iter().copied().filter(|f| !old.contains(f)).collect();
+        let removed = old.iter().copied().filter(|f| !new.contains(f)).collect();
+        (added, removed)
+    }
+}
+
+/// Squares at most 2 squares away from `square` in any direction, not including `square`.
+fn near_squares(square: Square) -> Bitboard {
+    Bitboard::from_squares(
+        (-2..=2)
+            .flat_map(|dy| (-2..=2).map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0))
+            .filter_map(|(dx, dy)| square.offset(dx, dy)),
+    )
+}
+
+impl<F: Features> Features for WazirSafetyFeatures<F> {
+    fn name(self) -> &'static str {
+        match self.features.name() {
+            "ps" => "ps-safety",
+            "wps" => "wps-safety",
+            _ => "safety",
+        }
+    }
+
+    fn count(self) -> usize {
+        self.features.count() + Color::COUNT * Self::COUNT_PER_WAZIR
+    }
+
+    fn approximate_avg_set(self) -> f64 {
+        self.features.approximate_avg_set() + Color::COUNT as f64
+    }
+
+    fn all(self, position: &Position, color: Color) -> impl Iterator<Item = usize> {
+        self.features
+            .all(position, color)
+            .chain(self.safety_features(position, color))
+    }
+
+    fn diff_setup(
+        self,
+        mov: SetupMove,
+        new_position: &Position,
+        color: Color,
+    ) -> Option<(impl Iterator<Item = usize>, impl Iterator<Item = usize>)> {
+        let (added, removed) = self.features.diff_setup(mov, new_position, color)?;
+        let old_position = new_position.unmake_setup_move(mov).ok()?;
+        let (safety_added, safety_removed) = self.safety_diff(&old_position, new_position, color);
+        Some((added.chain(safety_added), removed.chain(safety_removed)))
+    }
+
+    fn diff(
+        self,
+        mov: Move,
+        new_position: &Position,
+        color: Color,
+    ) -> Option<(impl Iterator<Item = usize>, impl Iterator<Item = usize>)> {
+        let (added, removed) = self.features.diff(mov, new_position, color)?;
+        let old_position = new_position.unmake_move(mov).ok()?;
+        let (safety_added, safety_removed) = self.safety_diff(&old_position, new_position, color);
+        Some((added.chain(safety_added), removed.chain(safety_removed)))
+    }
+}
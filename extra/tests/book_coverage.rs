@@ -0,0 +1,34 @@
+use extra::BookCoverage;
+use std::str::FromStr;
+use wazir_drop::{SetupMove, Symmetry, constants::PLY_AFTER_SETUP};
+
+#[test]
+fn test_book_coverage() {
+    let red = SetupMove::from_str("AWAAAAAADFFDDDAN").unwrap();
+    let blue = SetupMove::from_str("addadffdnaaaaawa").unwrap();
+    let other_blue = SetupMove::from_str("wnaadadaffaaddaa").unwrap();
+    let (symmetry, normalized_red) = Symmetry::normalize_red_setup(red);
+    let book_blue = symmetry.apply_to_setup(blue);
+    let mut coverage = BookCoverage::new(vec![
+        (normalized_red, book_blue),
+        (
+            Symmetry::normalize_red_setup(SetupMove::from_str("WNAADADAFFAADDAA").unwrap()).1,
+            other_blue,
+        ),
+    ]);
+
+    coverage.add_game(red, blue);
+    coverage.add_game(
+        Symmetry::FlipX.apply_to_setup(red),
+        Symmetry::FlipX.apply_to_setup(blue),
+    );
+    coverage.add_game(red, other_blue);
+    coverage.add_game(SetupMove::from_str("NWAADADAFFAADDAA").unwrap(), blue);
+
+    assert_eq!(coverage.num_games(), 4);
+    assert_eq!(coverage.left_book_at(0), 1);
+    assert_eq!(coverage.left_book_at(1), 1);
+    assert_eq!(coverage.left_book_at(PLY_AFTER_SETUP.into()), 2);
+    assert_eq!(coverage.hits(0), 2);
+    assert_eq!(coverage.unused().collect::<Vec<_>>(), [1]);
+}
@@ -1,14 +1,24 @@
-use extra::{LinearEvaluator, PSFeatures, moverand};
+use extra::{BenchEvaluator, DiffStats, linear_ps_evaluator, moverand};
 use rand::{SeedableRng, rngs::StdRng};
-use wazir_drop::{EvaluatedPosition, Evaluator, Nnue, Position, Stage, WPSFeatures};
+use std::sync::Arc;
+use wazir_drop::{
+    Color, EvaluatedPosition, Evaluator, History, LinearEvaluator, Move, Nnue, Position,
+    ScoreExpanded, Search, Stage, WPSFeatures,
+    constants::{Depth, Eval, Hyperparameters},
+    enums::EnumMap,
+};
 
 #[test]
 fn test_evaluators() {
     test_evaluator(&LinearEvaluator::<WPSFeatures>::default());
-    test_evaluator(&LinearEvaluator::<PSFeatures>::default());
+    test_evaluator(&linear_ps_evaluator());
     test_evaluator(&Nnue::default());
+    test_evaluator(&BenchEvaluator::new(Nnue::default(), false, &FULL_STATS));
 }
 
+static INCREMENTAL_STATS: DiffStats = DiffStats::new();
+static FULL_STATS: DiffStats = DiffStats::new();
+
 fn test_evaluator<E: Evaluator>(evaluator: &E) {
     let mut rng = StdRng::from_os_rng();
     for _ in 0..100 {
@@ -19,6 +29,186 @@ fn test_evaluator<E: Evaluator>(evaluator: &E) {
             let value = position.evaluate();
             let fresh_value = EvaluatedPosition::new(evaluator, *position.position()).evaluate();
             assert_eq!(value, fresh_value);
+            let to_move = position.position().to_move();
+            let detail = evaluator.evaluate_detailed(position.position(), to_move);
+            assert_eq!(detail.eval, value);
+        }
+    }
+}
+
+#[test]
+fn test_evaluator_names() {
+    let linear_wps = LinearEvaluator::<WPSFeatures>::default();
+    let linear_ps = linear_ps_evaluator();
+    let nnue = Nnue::default();
+    assert_eq!(linear_wps.name(), "linear-wps");
+    assert_eq!(linear_ps.name(), "linear-ps");
+    assert_eq!(nnue.name(), "nnue");
+    assert_ne!(linear_wps.version(), linear_ps.version());
+    assert_eq!(nnue.version(), Nnue::default().version());
+}
+
+#[test]
+fn test_bench_evaluator() {
+    let mut rng = StdRng::from_os_rng();
+    let incremental = BenchEvaluator::new(Nnue::default(), true, &INCREMENTAL_STATS);
+    let mut position = EvaluatedPosition::new(&incremental, Position::initial());
+    while !matches!(position.position().stage(), Stage::End(_)) {
+        let mov = moverand::random_move(position.position(), &mut rng);
+        position = position.make_any_move(mov).unwrap();
+        let nnue_value = EvaluatedPosition::new(&Nnue::default(), *position.position()).evaluate();
+        assert_eq!(position.evaluate(), nnue_value);
+    }
+    assert!(INCREMENTAL_STATS.incremental() > 0);
+    assert!(INCREMENTAL_STATS.incremental_percent() > 50.0);
+    assert_eq!(FULL_STATS.incremental(), 0);
+}
+
+#[test]
+fn test_evaluate_detailed_terms() {
+    let linear = LinearEvaluator::<WPSFeatures>::default();
+    let nnue = Nnue::default();
+    let mut rng = StdRng::seed_from_u64(440);
+    let mut position = Position::initial();
+    while !matches!(position.stage(), Stage::End(_)) {
+        for color in [Color::Red, Color::Blue] {
+            let detail = linear.evaluate_detailed(&position, color);
+            let names: Vec<&str> = detail.terms.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, ["own", "opponent", "to_move"]);
+            let sum: Eval = detail.terms.iter().map(|&(_, term)| term).sum();
+            assert_eq!(sum, detail.eval);
+
+            assert!(nnue.evaluate_detailed(&position, color).terms.is_empty());
+        }
+        let mov = moverand::random_move(&position, &mut rng);
+        position = position.make_any_move(mov).unwrap();
+    }
+}
+
+const AGREEMENT_DEPTH: Depth = 200;
+
+/// Fraction of decided positions where the best moves must match.
+const MIN_AGREEMENT: f64 = 0.75;
+
+#[test]
+fn test_linear_nnue_agreement() {
+    let positions = decided_positions();
+    assert!(positions.len() >= 30, "only {} positions", positions.len());
+
+    let nnue = Arc::new(Nnue::default());
+    let nnue_agreement = agreement(&positions, &nnue);
+    assert!(
+        nnue_agreement >= MIN_AGREEMENT,
+        "agreement {nnue_agreement}"
+    );
+
+    // A sign-flipped evaluator fails.
+    let negated = Arc::new(Negated(Nnue::default()));
+    let negated_agreement = agreement(&positions, &negated);
+    assert!(
+        negated_agreement < MIN_AGREEMENT,
+        "negated agreement {negated_agreement}"
+    );
+}
+
+/// Positions from random games where a shallow linear search finds a single move better than all
+/// others by at least half a unit, without a forced win or loss.
+fn decided_positions() -> Vec<(Position, History, Move)> {
+    let linear = Arc::new(LinearEvaluator::<WPSFeatures>::default());
+    let mut search = Search::new(&small_tables(), &linear);
+    let threshold = (0.5 * linear.scale()) as i32;
+    let mut rng = StdRng::seed_from_u64(441);
+    let mut decided = Vec::new();
+    for _ in 0..30 {
+        let mut position = Position::initial();
+        let mut history = History::new_from_position(&position);
+        while position.stage() == Stage::Setup {
+            position = position
+                .make_any_move(moverand::random_move(&position, &mut rng))
+                .unwrap();
+            history.push_position_irreversible(&position);
         }
+        for _ in 0..20 {
+            if position.stage() != Stage::Regular {
+                break;
+            }
+            let result = search.search(
+                &position,
+                Some(AGREEMENT_DEPTH),
+                None,
+                Some(threshold),
+                true,
+                &history,
+            );
+            if result.top_moves.len() == 1
+                && matches!(ScoreExpanded::from(result.score), ScoreExpanded::Eval(_))
+            {
+                decided.push((position, history.clone(), result.top_moves[0].mov));
+            }
+            position = position
+                .make_any_move(moverand::random_move(&position, &mut rng))
+                .unwrap();
+            history.push_position(&position);
+        }
+    }
+    decided
+}
+
+fn agreement<E: Evaluator>(positions: &[(Position, History, Move)], evaluator: &Arc<E>) -> f64 {
+    let mut search = Search::new(&small_tables(), evaluator);
+    let num_agree = positions
+        .iter()
+        .filter(|(position, history, mov)| {
+            let result = search.search(position, Some(AGREEMENT_DEPTH), None, None, false, history);
+            result.pv.first() == Some(mov)
+        })
+        .count();
+    num_agree as f64 / positions.len() as f64
+}
+
+fn small_tables() -> Hyperparameters {
+    Hyperparameters {
+        ttable_size: 1 << 20,
+        pvtable_size: 1 << 16,
+        ..Hyperparameters::default()
+    }
+}
+
+struct Negated<E>(E);
+
+impl<E: Evaluator> Evaluator for Negated<E> {
+    type Accumulator = E::Accumulator;
+    type Features = E::Features;
+
+    fn features(&self) -> Self::Features {
+        self.0.features()
+    }
+
+    fn new_accumulator(&self) -> Self::Accumulator {
+        self.0.new_accumulator()
+    }
+
+    fn add_feature(&self, accumulator: &mut Self::Accumulator, feature: usize) {
+        self.0.add_feature(accumulator, feature);
+    }
+
+    fn remove_feature(&self, accumulator: &mut Self::Accumulator, feature: usize) {
+        self.0.remove_feature(accumulator, feature);
+    }
+
+    fn evaluate(&self, accumulators: &EnumMap<Color, Self::Accumulator>, to_move: Color) -> Eval {
+        -self.0.evaluate(accumulators, to_move)
+    }
+
+    fn scale(&self) -> f64 {
+        self.0.scale()
+    }
+
+    fn name(&self) -> &str {
+        "negated"
+    }
+
+    fn version(&self) -> u64 {
+        !self.0.version()
     }
 }
@@ -1,7 +1,8 @@
-use extra::{PSFeatures, moverand};
+use extra::{PSFeatures, WazirSafetyFeatures, moverand};
 use rand::{SeedableRng, rngs::StdRng};
+use std::str::FromStr;
 use wazir_drop::{
-    Color, Features, Position, Stage, WPSFeatures,
+    AnyMove, Color, Features, Position, Stage, WPSFeatures,
     enums::{EnumMap, SimpleEnumExt},
 };
 
@@ -17,6 +18,24 @@ fn test_wps_features() {
     test_features_random_games(&WPSFeatures);
 }
 
+#[test]
+fn test_wazir_safety_features() {
+    let features = WazirSafetyFeatures::new(WPSFeatures);
+    assert_eq!(features.count(), 6360 + 180);
+    assert_eq!(features.name(), "wps-safety");
+    // One safety feature for each wazir.
+    let position = Position::replay(&[
+        AnyMove::from_str("WNAADADAFFAADDAA").unwrap(),
+        AnyMove::from_str("wnaadadaffaaddaa").unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(
+        features.all(&position, Color::Red).count(),
+        WPSFeatures.all(&position, Color::Red).count() + 2
+    );
+    test_features_random_games(&features);
+}
+
 fn gen_feature_vecs<F: Features>(features: &F, position: &Position) -> EnumMap<Color, Vec<i32>> {
     EnumMap::from_fn(|color| {
         let mut v = vec![0; features.count()];
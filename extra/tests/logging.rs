@@ -0,0 +1,26 @@
+use clap::Parser;
+use extra::logging::{VerbosityArgs, level_filter};
+use log::LevelFilter;
+use wazir_drop::log::Verbosity;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[command(flatten)]
+    verbosity: VerbosityArgs,
+}
+
+#[test]
+fn test_verbosity_args() {
+    let args = Args::try_parse_from(["bin", "--quiet"]).unwrap();
+    assert_eq!(args.verbosity.verbosity(), Verbosity::Quiet);
+    let args = Args::try_parse_from(["bin", "--verbose"]).unwrap();
+    assert_eq!(args.verbosity.verbosity(), Verbosity::Verbose);
+    assert!(Args::try_parse_from(["bin", "--quiet", "--verbose"]).is_err());
+}
+
+#[test]
+fn test_level_filter() {
+    assert_eq!(level_filter(Verbosity::Quiet), LevelFilter::Warn);
+    assert_eq!(level_filter(Verbosity::Normal), LevelFilter::Info);
+    assert_eq!(level_filter(Verbosity::Verbose), LevelFilter::Debug);
+}
@@ -0,0 +1,26 @@
+//! `cargo fuzz run cli_command`
+//!
+//! Parses raw protocol lines the way `run_cli` does. Malformed input must be an error, not a
+//! panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wazir_drop::{
+    CliCommand,
+    parser::{self, ParserExt},
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(command) = CliCommand::parser()
+        .then_ignore(parser::endl_or_end())
+        .parse_all(data)
+    {
+        let line = format!("{command}\n");
+        let reparsed = CliCommand::parser()
+            .then_ignore(parser::endl_or_end())
+            .parse_all(line.as_bytes())
+            .unwrap_or_else(|_| panic!("{line:?} doesn't parse"));
+        assert_eq!(reparsed.to_string(), command.to_string());
+    }
+});
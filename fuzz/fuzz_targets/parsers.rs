@@ -0,0 +1,18 @@
+//! `cargo fuzz run parsers`
+//!
+//! Parsing never panics, and anything parsed displays as a string that parses back to the same
+//! display.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../tests/common/round_trip.rs"]
+mod round_trip;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = str::from_utf8(data) else {
+        return;
+    };
+    round_trip::check_all(input);
+});
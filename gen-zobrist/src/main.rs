@@ -8,14 +8,14 @@ fn main() {
     let mut rng = StdRng::from_os_rng();
 
     println!("#[rustfmt::skip]");
-    println!("pub static TO_MOVE: EnumMap<Color, u64> = EnumMap::from_array([");
+    println!("static TO_MOVE: EnumMap<Color, u64> = EnumMap::from_array([");
     generate("    ", Color::COUNT, &mut rng);
     println!("]);");
     println!();
 
     println!("#[rustfmt::skip]");
     println!(
-        "pub static COLORED_PIECE_SQUARE: EnumMap<ColoredPiece, EnumMap<Square, u64>> = EnumMap::from_array(["
+        "static COLORED_PIECE_SQUARE: EnumMap<ColoredPiece, EnumMap<Square, u64>> = EnumMap::from_array(["
     );
     for _ in 0..ColoredPiece::COUNT {
         println!("    EnumMap::from_array([");
@@ -38,7 +38,7 @@ fn main() {
     println!();
 
     println!("#[rustfmt::skip]");
-    println!("pub static NULL_MOVE_COUNTER: [u64; (PLY_DRAW - PLY_AFTER_SETUP + 1) as usize] = [");
+    println!("static NULL_MOVE_COUNTER: [u64; (PLY_DRAW - PLY_AFTER_SETUP + 1) as usize] = [");
     generate(
         "    ",
         usize::from(PLY_DRAW - PLY_AFTER_SETUP + 1),
@@ -48,7 +48,7 @@ fn main() {
     println!();
 
     println!("#[rustfmt::skip]");
-    println!("pub static PLY: [u64; (PLY_DRAW + 1) as usize] = [");
+    println!("static PLY: [u64; (PLY_DRAW + 1) as usize] = [");
     generate("    ", usize::from(PLY_DRAW + 1), &mut rng);
     println!("];");
     println!();
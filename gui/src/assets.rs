@@ -0,0 +1,53 @@
+use eframe::egui::{Image, ImageSource, load::Bytes};
+use wazir_drop::{ColoredPiece, enums::EnumMap};
+
+/// Defines the piece asset tables from one list, which must be in `ColoredPiece` index order.
+///
+/// The tables are arrays of `ColoredPiece::COUNT` elements, so a missing piece doesn't compile,
+/// and the order is checked by a const assert below.
+macro_rules! piece_assets {
+    ($($cpiece:ident => $path:literal,)*) => {
+        const PIECE_ASSET_ORDER: [ColoredPiece; ColoredPiece::COUNT] =
+            [$(ColoredPiece::$cpiece),*];
+
+        /// Image file of each piece, relative to the `gui` crate.
+        pub const PIECE_ASSET_PATHS: EnumMap<ColoredPiece, &str> =
+            EnumMap::from_array([$($path),*]);
+
+        const PIECE_ASSET_BYTES: EnumMap<ColoredPiece, &[u8]> =
+            EnumMap::from_array([$(include_bytes!(concat!("../", $path)) as &[u8]),*]);
+    };
+}
+
+piece_assets! {
+    RedAlfil => "assets/white_bishop.svg",
+    BlueAlfil => "assets/black_bishop.svg",
+    RedDabbaba => "assets/white_rook.svg",
+    BlueDabbaba => "assets/black_rook.svg",
+    RedFerz => "assets/white_pawn.svg",
+    BlueFerz => "assets/black_pawn.svg",
+    RedKnight => "assets/white_knight.svg",
+    BlueKnight => "assets/black_knight.svg",
+    RedWazir => "assets/white_king.svg",
+    BlueWazir => "assets/black_king.svg",
+}
+
+const _: () = {
+    let mut index = 0;
+    while index < ColoredPiece::COUNT {
+        assert!(
+            PIECE_ASSET_ORDER[index].index() == index,
+            "piece assets must be listed in ColoredPiece order"
+        );
+        index += 1;
+    }
+};
+
+pub fn piece_images() -> EnumMap<ColoredPiece, Image<'static>> {
+    EnumMap::from_fn(|cpiece| {
+        Image::new(ImageSource::Bytes {
+            uri: format!("bytes://{}", PIECE_ASSET_PATHS[cpiece]).into(),
+            bytes: Bytes::Static(PIECE_ASSET_BYTES[cpiece]),
+        })
+    })
+}
@@ -0,0 +1,98 @@
+use wazir_drop::{
+    Deadlines, Evaluator, History, LinearEvaluator, Move, Nnue, Position, Search, SetupMove,
+    WPSFeatures, book, constants::Hyperparameters,
+};
+
+use std::sync::Arc;
+
+/// What the computer player needs from `Search`.
+///
+/// `Search` is generic over the evaluator. Holding a `Box<dyn ComputerSearch>` instead lets the
+/// GUI switch evaluators at runtime.
+pub trait ComputerSearch: Send {
+    /// Blue's reply to the red setup `red`, from the setups in the book.
+    fn blue_setup(&mut self, red: SetupMove, deadlines: Deadlines) -> SetupMove;
+
+    fn regular_move(
+        &mut self,
+        position: &Position,
+        history: &History,
+        deadlines: Deadlines,
+    ) -> Move;
+}
+
+impl<E: Evaluator> ComputerSearch for Search<E> {
+    fn blue_setup(&mut self, red: SetupMove, deadlines: Deadlines) -> SetupMove {
+        let result = self.search_blue_setup(red, None, Some(deadlines), &book::blue_setup_moves());
+        log::info!(
+            "depth {depth} score {score} \
+                root {root_moves_considered}/{root_all_moves} \
+                nodes {nodes} pv {mov} {pv}",
+            depth = result.depth,
+            score = result.score.to_relative(1),
+            root_moves_considered = result.root_moves_considered,
+            root_all_moves = result.num_root_moves,
+            nodes = result.nodes,
+            mov = result.mov,
+            pv = result.pv,
+        );
+        result.mov
+    }
+
+    fn regular_move(
+        &mut self,
+        position: &Position,
+        history: &History,
+        deadlines: Deadlines,
+    ) -> Move {
+        let result = self.search(
+            position,
+            None, /* max_depth */
+            Some(deadlines),
+            None,  /* multi_move_threshold */
+            false, /* is_score_important */
+            history,
+        );
+        log::info!(
+            "depth {depth} score {score} \
+                root {root_moves_considered}/{root_all_moves} \
+                nodes {nodes} pv {pv}",
+            depth = result.depth,
+            score = result.score.to_relative(position.ply()),
+            root_moves_considered = result.root_moves_considered,
+            root_all_moves = result.num_root_moves,
+            nodes = result.nodes,
+            pv = result.pv,
+        );
+        result.pv.moves[0]
+    }
+}
+
+/// Evaluator used by the built-in computer player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvaluatorChoice {
+    Nnue,
+    LinearWps,
+}
+
+impl EvaluatorChoice {
+    pub const ALL: [Self; 2] = [Self::Nnue, Self::LinearWps];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nnue => "NNUE",
+            Self::LinearWps => "Linear (WPS)",
+        }
+    }
+
+    pub fn new_search(self) -> Box<dyn ComputerSearch> {
+        let hyperparameters = Hyperparameters::default();
+        match self {
+            Self::Nnue => Box::new(Search::new(&hyperparameters, &Arc::new(Nnue::default()))),
+            Self::LinearWps => Box::new(Search::new(
+                &hyperparameters,
+                &Arc::new(LinearEvaluator::<WPSFeatures>::default()),
+            )),
+        }
+    }
+}
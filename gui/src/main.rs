@@ -1,22 +1,32 @@
+mod assets;
+mod computer;
+
+use computer::{ComputerSearch, EvaluatorChoice};
 use eframe::{
     App,
     egui::{
-        self, Align2, CentralPanel, Color32, FontId, Image, Pos2, Rect, ScrollArea, Sense,
-        SidePanel, Theme, Ui, Vec2, ViewportBuilder, include_image,
+        self, Align2, CentralPanel, Checkbox, Color32, FontId, Image, Key, MenuBar, Modifiers,
+        Pos2, Rect, ScrollArea, Sense, SidePanel, Theme, TopBottomPanel, Ui, Vec2, ViewportBuilder,
     },
 };
+use external_player::ExternalPlayer;
 use simplelog::{ColorChoice, LevelFilter, TermLogger, TerminalMode};
 use std::{
+    env,
     error::Error,
+    fs, io,
+    path::{Path, PathBuf},
     process::ExitCode,
+    str::FromStr,
     sync::{Arc, Mutex},
     thread,
     time::{Duration, Instant},
 };
 use wazir_drop::{
-    AnyMove, Color, ColoredPiece, Coord, Deadlines, DefaultEvaluator, History, Piece, Position,
-    Search, SetupMove, ShortMove, ShortMoveFrom, Square, Stage, Symmetry, book,
-    constants::Hyperparameters,
+    AnyMove, Bitboard, Color, ColoredPiece, Coord, Deadlines, History, Piece, Position, SetupMove,
+    ShortMove, ShortMoveFrom, Square, Stage, Symmetry, book,
+    clock::{TimeControl, Timer},
+    constants::PLY_DRAW,
     enums::{EnumMap, SimpleEnumExt},
     movegen,
 };
@@ -54,17 +64,37 @@ fn run() -> Result<(), Box<dyn Error>> {
 
 struct WazirDropApp {
     reverse: bool,
+    auto_orient: bool,
+    auto_orient_follow_to_move: bool,
+    /// Mark drops that give check when a captured piece is selected.
+    show_check_hints: bool,
     is_computer_player: EnumMap<Color, bool>,
     time_limit_str: String,
+    /// Path of an engine binary to use as the computer player. Empty means the built-in search.
+    engine_path_str: String,
     piece_images: EnumMap<ColoredPiece, Image<'static>>,
     tile_size: f32,
     position: Position,
     next_move_state: NextMoveState,
     history_entries: Vec<HistoryEntry>,
-    search: Arc<Mutex<Search<DefaultEvaluator>>>,
+    // Number of moves played in the position being viewed. None means the current position.
+    viewed_ply: Option<usize>,
+    evaluator_choice: EvaluatorChoice,
+    search: Arc<Mutex<Box<dyn ComputerSearch>>>,
+    /// Running external engines, kept between moves of a game.
+    engines: Arc<Mutex<EnumMap<Color, Option<EngineSession>>>>,
     history: History,
 }
 
+/// An external engine process playing one color.
+struct EngineSession {
+    path: PathBuf,
+    time_limit: Duration,
+    player: ExternalPlayer,
+    /// The game up to and including the engine's last move.
+    moves: Vec<AnyMove>,
+}
+
 impl WazirDropApp {
     fn new(ctx: &eframe::CreationContext) -> Self {
         egui_extras::install_image_loaders(&ctx.egui_ctx);
@@ -72,41 +102,27 @@ impl WazirDropApp {
         let history = History::new_from_position(&position);
         let mut app = Self {
             reverse: false,
+            auto_orient: false,
+            auto_orient_follow_to_move: false,
+            show_check_hints: true,
             is_computer_player: EnumMap::from_fn(|_| false),
             time_limit_str: "1000".to_string(),
-            piece_images: Self::piece_images(),
+            engine_path_str: String::new(),
+            piece_images: assets::piece_images(),
             tile_size: 0.0,
             position,
             next_move_state: NextMoveState::EndOfGame, // temporary
             history_entries: Vec::new(),
-            search: Arc::new(Mutex::new(Search::new(
-                &Hyperparameters::default(),
-                &Arc::new(DefaultEvaluator::default()),
-            ))),
+            viewed_ply: None,
+            evaluator_choice: EvaluatorChoice::Nnue,
+            search: Arc::new(Mutex::new(EvaluatorChoice::Nnue.new_search())),
+            engines: Arc::new(Mutex::new(EnumMap::from_fn(|_| None))),
             history,
         };
         app.start_next_move(&ctx.egui_ctx);
         app
     }
 
-    fn piece_images() -> EnumMap<ColoredPiece, Image<'static>> {
-        EnumMap::from_fn(|cpiece| {
-            let image = match cpiece {
-                ColoredPiece::RedAlfil => include_image!("../assets/white_bishop.svg"),
-                ColoredPiece::BlueAlfil => include_image!("../assets/black_bishop.svg"),
-                ColoredPiece::RedDabbaba => include_image!("../assets/white_rook.svg"),
-                ColoredPiece::BlueDabbaba => include_image!("../assets/black_rook.svg"),
-                ColoredPiece::RedFerz => include_image!("../assets/white_pawn.svg"),
-                ColoredPiece::BlueFerz => include_image!("../assets/black_pawn.svg"),
-                ColoredPiece::RedKnight => include_image!("../assets/white_knight.svg"),
-                ColoredPiece::BlueKnight => include_image!("../assets/black_knight.svg"),
-                ColoredPiece::RedWazir => include_image!("../assets/white_king.svg"),
-                ColoredPiece::BlueWazir => include_image!("../assets/black_king.svg"),
-            };
-            Image::new(image)
-        })
-    }
-
     fn is_dark_square(square: Square) -> bool {
         let coord = Coord::from(square);
         !(coord.x() + coord.y()).is_multiple_of(2)
@@ -136,6 +152,14 @@ impl WazirDropApp {
         }
     }
 
+    fn check_hint_square_color(square: Square) -> Color32 {
+        if Self::is_dark_square(square) {
+            Color32::from_rgb(176, 82, 66)
+        } else {
+            Color32::from_rgb(214, 118, 98)
+        }
+    }
+
     fn update_chessboard(&mut self, ui: &mut Ui) {
         let size = ui.available_size();
         self.tile_size = (size.x / (Coord::WIDTH + Piece::COUNT + 3) as f32)
@@ -215,20 +239,27 @@ impl WazirDropApp {
     }
 
     fn update_board(&mut self, ui: &mut Ui) {
+        let is_live = self.viewed_ply.is_none();
         let position = match self.next_move_state {
-            NextMoveState::HumanSetup { setup, .. } => self
+            NextMoveState::HumanSetup { setup, .. } if is_live => self
                 .position
                 .make_setup_move(setup)
                 .expect("Invalid setup move"),
-            _ => self.position,
+            _ => self.viewed_position(),
         };
+        let last_move = self.viewed_last_move();
+        let (drop_targets, drop_checks) = self.drop_hints();
 
         for square in Square::all() {
             let rect = self.square_rect(square);
-            if ui.allocate_rect(rect, Sense::click()).clicked() {
+            if ui.allocate_rect(rect, Sense::click()).clicked() && is_live {
                 self.click_square(square, ui.ctx());
             }
             let is_selected = match self.next_move_state {
+                _ if !is_live => false,
+                NextMoveState::HumanRegular {
+                    from: Some(ShortMoveFrom::Piece(_)),
+                } => drop_targets.contains(square),
                 NextMoveState::HumanRegular { from: Some(from) } => {
                     let short_move = ShortMove::Regular { from, to: square };
                     from == ShortMoveFrom::Square(square)
@@ -240,14 +271,13 @@ impl WazirDropApp {
                 } => swap_from == square,
                 _ => false,
             };
-            let is_last_move = match self.history_entries.last() {
-                Some(HistoryEntry {
-                    mov: AnyMove::Regular(mov),
-                    ..
-                }) => mov.from == Some(square) || mov.to == square,
+            let is_last_move = match last_move {
+                Some(AnyMove::Regular(mov)) => mov.from == Some(square) || mov.to == square,
                 _ => false,
             };
-            let color = if is_selected {
+            let color = if is_selected && self.show_check_hints && drop_checks.contains(square) {
+                Self::check_hint_square_color(square)
+            } else if is_selected {
                 Self::selected_square_color(square)
             } else if is_last_move {
                 Self::last_move_square_color(square)
@@ -261,13 +291,35 @@ impl WazirDropApp {
         }
     }
 
+    /// Squares where the selected captured piece can be dropped, and those of them that give check.
+    fn drop_hints(&self) -> (Bitboard, Bitboard) {
+        let mut targets = Bitboard::EMPTY;
+        let mut checks = Bitboard::EMPTY;
+        if let NextMoveState::HumanRegular {
+            from: Some(ShortMoveFrom::Piece(cpiece)),
+        } = self.next_move_state
+            && self.viewed_ply.is_none()
+        {
+            for mov in movegen::drops(&self.position).filter(|mov| mov.colored_piece == cpiece) {
+                targets.add(mov.to);
+                if movegen::gives_check(&self.position, mov) {
+                    checks.add(mov.to);
+                }
+            }
+        }
+        (targets, checks)
+    }
+
     fn update_captured(&mut self, ui: &mut Ui) {
+        let is_live = self.viewed_ply.is_none();
+        let position = self.viewed_position();
         for cpiece in ColoredPiece::all() {
             let rect = self.captured_rect(cpiece);
-            if ui.allocate_rect(rect, Sense::click()).clicked() {
+            if ui.allocate_rect(rect, Sense::click()).clicked() && is_live {
                 self.click_captured(cpiece);
             }
             let selected = match self.next_move_state {
+                _ if !is_live => false,
                 NextMoveState::HumanRegular {
                     from: Some(ShortMoveFrom::Piece(from_cpiece)),
                 } => cpiece == from_cpiece,
@@ -280,7 +332,7 @@ impl WazirDropApp {
                 Self::square_color(square)
             };
             _ = ui.painter().rect_filled(rect, 0.0, color);
-            let num = self.position.num_captured(cpiece);
+            let num = position.num_captured(cpiece);
             if num > 0 {
                 self.draw_captured_piece(ui, cpiece, num);
             }
@@ -311,13 +363,44 @@ impl WazirDropApp {
     fn launch_computer_thread(&mut self, ctx: &egui::Context, result: Arc<Mutex<Option<AnyMove>>>) {
         let position = self.position;
         let search = self.search.clone();
+        let engines = self.engines.clone();
         let ctx = ctx.clone();
         let time_limit_ms = self.time_limit_str.parse::<u32>().unwrap_or(1000);
         let time_limit = Duration::from_millis(time_limit_ms.into());
         let history_first = self.history_entries.first().cloned();
         let history = self.history.clone();
+        let engine_path = Some(self.engine_path_str.trim())
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+        let moves_played: Vec<AnyMove> = self.history_entries.iter().map(|e| e.mov).collect();
 
         _ = thread::spawn(move || {
+            if let Some(engine_path) = engine_path {
+                let mut engines = engines.lock().unwrap();
+                let session = &mut engines[position.to_move()];
+                match Self::external_engine_move(
+                    session,
+                    &engine_path,
+                    &position,
+                    &moves_played,
+                    time_limit,
+                ) {
+                    Ok(Some(mov)) => {
+                        *result.lock().unwrap() = Some(mov);
+                        ctx.request_repaint();
+                        return;
+                    }
+                    Ok(None) => log::error!(
+                        "External engine {path} out of time, using the built-in search",
+                        path = engine_path.display()
+                    ),
+                    Err(e) => log::error!(
+                        "External engine {path}: {e}, using the built-in search",
+                        path = engine_path.display()
+                    ),
+                }
+                *session = None;
+            }
             let now = Instant::now();
             let deadlines = Deadlines {
                 hard: now + time_limit,
@@ -336,52 +419,15 @@ impl WazirDropApp {
 
                         match book::blue_setup(red) {
                             Some(mov) => mov.into(),
-                            None => {
-                                let result = search.lock().unwrap().search_blue_setup(
-                                    red,
-                                    None,
-                                    Some(deadlines),
-                                    &book::blue_setup_moves(),
-                                );
-                                log::info!(
-                                    "depth {depth} score {score} \
-                                        root {root_moves_considered}/{root_all_moves} \
-                                        nodes {nodes} pv {mov} {pv}",
-                                    depth = result.depth,
-                                    score = result.score.to_relative(position.ply()),
-                                    root_moves_considered = result.root_moves_considered,
-                                    root_all_moves = result.num_root_moves,
-                                    nodes = result.nodes,
-                                    mov = result.mov,
-                                    pv = result.pv,
-                                );
-                                result.mov.into()
-                            }
+                            None => search.lock().unwrap().blue_setup(red, deadlines).into(),
                         }
                     }
                 },
-                Stage::Regular => {
-                    let result = search.lock().unwrap().search(
-                        &position,
-                        None, /* max_depth */
-                        Some(deadlines),
-                        None,  /* multi_move_threshold */
-                        false, /* is_score_important */
-                        &history,
-                    );
-                    log::info!(
-                        "depth {depth} score {score} \
-                            root {root_moves_considered}/{root_all_moves} \
-                            nodes {nodes} pv {pv}",
-                        depth = result.depth,
-                        score = result.score.to_relative(position.ply()),
-                        root_moves_considered = result.root_moves_considered,
-                        root_all_moves = result.num_root_moves,
-                        nodes = result.nodes,
-                        pv = result.pv,
-                    );
-                    result.pv.moves[0].into()
-                }
+                Stage::Regular => search
+                    .lock()
+                    .unwrap()
+                    .regular_move(&position, &history, deadlines)
+                    .into(),
                 Stage::End(_) => panic!("Game is over"),
             };
             *result.lock().unwrap() = Some(mov);
@@ -389,6 +435,66 @@ impl WazirDropApp {
         });
     }
 
+    /// A move from the engine at `path`. `None` if it ran out of time.
+    ///
+    /// The engine in `session` is reused if the game has only gained the opponent's move since
+    /// its last move. Otherwise, e.g. after undo or new game, a new engine is started with the
+    /// game so far as its opening. Its clock is large enough for `time_limit` to be the limit
+    /// for every move.
+    fn external_engine_move(
+        session: &mut Option<EngineSession>,
+        path: &Path,
+        position: &Position,
+        moves_played: &[AnyMove],
+        time_limit: Duration,
+    ) -> io::Result<Option<AnyMove>> {
+        let reusable = session.as_ref().is_some_and(|session| {
+            session.path == path
+                && session.time_limit == time_limit
+                && moves_played.len() == session.moves.len() + 1
+                && moves_played.starts_with(&session.moves)
+        });
+        let session = match session {
+            Some(session) if reusable => {
+                session
+                    .player
+                    .try_opponent_move(moves_played[session.moves.len()])?;
+                session
+            }
+            _ => {
+                // Quit the old engine first.
+                *session = None;
+                let log_path = env::temp_dir().join("wazir-drop-gui-engine.log");
+                let player = ExternalPlayer::new(
+                    path,
+                    &log_path,
+                    position.to_move(),
+                    &Position::initial(),
+                    moves_played,
+                    Some(TimeControl {
+                        base: time_limit * u32::from(PLY_DRAW),
+                        increment: Duration::ZERO,
+                        move_cap: Some(time_limit),
+                    }),
+                )?;
+                session.insert(EngineSession {
+                    path: path.to_path_buf(),
+                    time_limit,
+                    player,
+                    moves: Vec::new(),
+                })
+            }
+        };
+        let mut timer = Timer::new(time_limit);
+        timer.start();
+        let mov = session.player.try_make_move(position, &timer)?;
+        if let Some(mov) = mov {
+            session.moves = moves_played.to_vec();
+            session.moves.push(mov);
+        }
+        Ok(mov)
+    }
+
     fn draw_piece(&self, ui: &mut Ui, square: Square, piece: ColoredPiece) {
         self.piece_images[piece].paint_at(ui, self.square_rect(square));
     }
@@ -413,14 +519,15 @@ impl WazirDropApp {
     }
 
     fn draw_to_move(&self, ui: &mut Ui) {
-        if !matches!(self.position.stage(), Stage::End(_)) {
+        let position = self.viewed_position();
+        if !matches!(position.stage(), Stage::End(_)) {
             let x = 1.1 * self.tile_size;
-            let y = if (self.position.to_move() == Color::Red) != self.reverse {
+            let y = if (position.to_move() == Color::Red) != self.reverse {
                 0.8 * self.tile_size
             } else {
                 ((Coord::HEIGHT + 1) as f32 + 0.2) * self.tile_size
             };
-            let color = match self.position.to_move() {
+            let color = match position.to_move() {
                 Color::Red => Color32::WHITE,
                 Color::Blue => Color32::BLACK,
             };
@@ -432,15 +539,74 @@ impl WazirDropApp {
         }
     }
 
-    fn draw_history(&self, ui: &mut Ui) {
+    fn draw_history(&mut self, ui: &mut Ui) {
         _ = ui.heading("Moves");
         _ = ScrollArea::vertical().show(ui, |ui| {
+            let num_moves = self.history_entries.len();
+            let viewed_ply = self.viewed_ply.unwrap_or(num_moves);
             for (index, entry) in self.history_entries.iter().enumerate() {
-                _ = ui.label(format!("{}. {}", index + 1, entry.mov));
+                let ply = index + 1;
+                let notation = match entry.mov {
+                    AnyMove::Setup(mov) => mov.to_string(),
+                    AnyMove::Regular(mov) => mov.to_notation(&entry.position),
+                };
+                if ui
+                    .selectable_label(ply == viewed_ply, format!("{ply}. {notation}"))
+                    .clicked()
+                {
+                    self.viewed_ply = if ply == num_moves { None } else { Some(ply) };
+                }
             }
         });
     }
 
+    fn viewed_position(&self) -> Position {
+        match self.viewed_ply {
+            Some(ply) => self.history_entries[ply].position,
+            None => self.position,
+        }
+    }
+
+    fn viewed_last_move(&self) -> Option<AnyMove> {
+        let ply = self.viewed_ply.unwrap_or(self.history_entries.len());
+        ply.checked_sub(1)
+            .map(|index| self.history_entries[index].mov)
+    }
+
+    fn view_back(&mut self) {
+        let ply = self.viewed_ply.unwrap_or(self.history_entries.len());
+        if ply > 0 {
+            self.viewed_ply = Some(ply - 1);
+        }
+    }
+
+    fn view_forward(&mut self) {
+        if let Some(ply) = self.viewed_ply {
+            self.viewed_ply = if ply + 1 < self.history_entries.len() {
+                Some(ply + 1)
+            } else {
+                None
+            };
+        }
+    }
+
+    /// Shows the board from the point of view of the human player.
+    fn auto_orient(&mut self) {
+        let human = match (
+            self.is_computer_player[Color::Red],
+            self.is_computer_player[Color::Blue],
+        ) {
+            (false, true) => Some(Color::Red),
+            (true, false) => Some(Color::Blue),
+            _ if self.auto_orient_follow_to_move => Some(self.position.to_move()),
+            _ => None,
+        };
+        if let Some(human) = human {
+            // Opponent on top.
+            self.reverse = Symmetry::pov(human.opposite()) == Symmetry::Rotate180;
+        }
+    }
+
     fn symmetry(&self) -> Symmetry {
         if self.reverse {
             Symmetry::Rotate180
@@ -509,36 +675,116 @@ impl WazirDropApp {
     }
 
     fn make_move(&mut self, mov: AnyMove, ctx: &egui::Context) {
+        self.push_move(mov);
+        self.start_next_move(ctx);
+    }
+
+    fn push_move(&mut self, mov: AnyMove) {
+        self.viewed_ply = None;
         self.history_entries.push(HistoryEntry {
             position: self.position,
             mov,
         });
-        self.position = self.position.make_any_move(mov).expect("Invalid move");
-        match mov {
-            AnyMove::Setup(_) => {
-                self.history.push_position_irreversible(&self.position);
-            }
-            AnyMove::Regular(_) => {
-                self.history.push_position(&self.position);
-            }
+        let (position, effect) = self
+            .position
+            .make_any_move_with_effect(mov)
+            .expect("Invalid move");
+        self.position = position;
+        self.history.push_position_after(&self.position, &effect);
+    }
+
+    /// Switches the built-in computer player to another evaluator and starts a new game.
+    fn set_evaluator(&mut self, choice: EvaluatorChoice, ctx: &egui::Context) {
+        if !matches!(self.next_move_state, NextMoveState::Computer { .. }) {
+            log::info!("Evaluator {}", choice.name());
+            self.evaluator_choice = choice;
+            self.search = Arc::new(Mutex::new(choice.new_search()));
+            self.new_game(ctx);
         }
-        self.start_next_move(ctx);
     }
 
     fn new_game(&mut self, ctx: &egui::Context) {
         if !matches!(self.next_move_state, NextMoveState::Computer { .. }) {
             self.position = Position::initial();
             self.history_entries.clear();
-            self.history = History::new_from_position(&self.position);
+            self.viewed_ply = None;
+            self.history.clear();
             self.start_next_move(ctx);
         }
     }
 
+    fn save_game(&self, path: &Path) {
+        let mut text = String::new();
+        for entry in &self.history_entries {
+            text.push_str(&format!("{}\n", entry.mov));
+        }
+        if let Err(e) = fs::write(path, text) {
+            log::error!("Failed to save {path}: {e}", path = path.display());
+        }
+    }
+
+    fn open_game(&mut self, path: &Path, ctx: &egui::Context) {
+        if matches!(self.next_move_state, NextMoveState::Computer { .. }) {
+            return;
+        }
+        let moves = match Self::read_game(path) {
+            Ok(moves) => moves,
+            Err(e) => {
+                log::error!("Failed to open {path}: {e}", path = path.display());
+                return;
+            }
+        };
+        self.position = Position::initial();
+        self.history_entries.clear();
+        self.viewed_ply = None;
+        self.history.clear();
+        for mov in moves {
+            self.push_move(mov);
+        }
+        self.start_next_move(ctx);
+    }
+
+    /// Reads a game transcript: one move per line.
+    fn read_game(path: &Path) -> Result<Vec<AnyMove>, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let mut moves = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let mov = AnyMove::from_str(line).map_err(|_| format!("Invalid move: {line}"))?;
+            moves.push(mov);
+        }
+        _ = Position::replay(&moves).map_err(|_| "Illegal move sequence")?;
+        Ok(moves)
+    }
+
+    fn handle_keyboard(&mut self, ctx: &egui::Context) {
+        let (back, forward, undo, new_game) = ctx.input_mut(|input| {
+            (
+                input.consume_key(Modifiers::NONE, Key::ArrowLeft),
+                input.consume_key(Modifiers::NONE, Key::ArrowRight),
+                input.consume_key(Modifiers::COMMAND, Key::Z),
+                input.consume_key(Modifiers::COMMAND, Key::N),
+            )
+        });
+        if back {
+            self.view_back();
+        }
+        if forward {
+            self.view_forward();
+        }
+        if undo {
+            self.undo(ctx);
+        }
+        if new_game {
+            self.new_game(ctx);
+        }
+    }
+
     fn undo(&mut self, ctx: &egui::Context) {
         if !matches!(self.next_move_state, NextMoveState::Computer { .. })
             && let Some(entry) = self.history_entries.pop()
         {
             self.position = entry.position;
+            self.viewed_ply = None;
             self.history.pop();
             self.start_next_move(ctx);
         }
@@ -558,8 +804,70 @@ impl App for WazirDropApp {
             self.make_move(mov, ctx);
         }
 
+        _ = TopBottomPanel::top("menu bar").show(ctx, |ui| {
+            _ = MenuBar::new().ui(ui, |ui| {
+                _ = ui.menu_button("File", |ui| {
+                    let is_computer_thinking =
+                        matches!(self.next_move_state, NextMoveState::Computer { .. });
+                    if ui
+                        .add_enabled(!is_computer_thinking, egui::Button::new("Open..."))
+                        .clicked()
+                    {
+                        ui.close();
+                        if let Some(path) = game_file_dialog().pick_file() {
+                            self.open_game(&path, ctx);
+                        }
+                    }
+                    if ui.button("Save...").clicked() {
+                        ui.close();
+                        if let Some(path) = game_file_dialog().set_file_name("game.txt").save_file()
+                        {
+                            self.save_game(&path);
+                        }
+                    }
+                });
+                _ = ui.menu_button("Engine", |ui| {
+                    _ = ui.label("External engine (empty for built-in):");
+                    _ = ui.text_edit_singleline(&mut self.engine_path_str);
+                    _ = ui.label("Time limit (ms):");
+                    _ = ui.text_edit_singleline(&mut self.time_limit_str);
+                    let is_computer_thinking =
+                        matches!(self.next_move_state, NextMoveState::Computer { .. });
+                    let mut choice = self.evaluator_choice;
+                    _ = ui.add_enabled_ui(!is_computer_thinking, |ui| {
+                        egui::ComboBox::from_label("Evaluator")
+                            .selected_text(choice.name())
+                            .show_ui(ui, |ui| {
+                                for option in EvaluatorChoice::ALL {
+                                    _ = ui.selectable_value(&mut choice, option, option.name());
+                                }
+                            })
+                    });
+                    if choice != self.evaluator_choice {
+                        self.set_evaluator(choice, ctx);
+                    }
+                });
+            });
+        });
+
+        if !ctx.wants_keyboard_input() {
+            self.handle_keyboard(ctx);
+        }
+
+        if self.auto_orient {
+            self.auto_orient();
+        }
+
         _ = SidePanel::right("side panel").show(ctx, |ui| {
-            _ = ui.checkbox(&mut self.reverse, "Reverse view");
+            _ = ui.add_enabled(
+                !self.auto_orient,
+                Checkbox::new(&mut self.reverse, "Reverse view"),
+            );
+            _ = ui.checkbox(&mut self.auto_orient, "Auto-orient");
+            if self.auto_orient {
+                _ = ui.checkbox(&mut self.auto_orient_follow_to_move, "Follow side to move");
+            }
+            _ = ui.checkbox(&mut self.show_check_hints, "Highlight checking drops");
 
             for color in Color::all() {
                 if ui
@@ -575,9 +883,6 @@ impl App for WazirDropApp {
                 }
             }
 
-            _ = ui.label("Time limit (ms):");
-            _ = ui.text_edit_singleline(&mut self.time_limit_str);
-
             if let NextMoveState::Computer { .. } = self.next_move_state {
                 _ = ui.label("Thinking...");
             } else {
@@ -607,6 +912,11 @@ impl App for WazirDropApp {
     }
 }
 
+/// A file dialog for game transcripts.
+fn game_file_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new().add_filter("Game transcript", &["txt"])
+}
+
 #[derive(Debug)]
 enum NextMoveState {
     HumanSetup {
@@ -1,9 +1,8 @@
 use clap::Parser;
-use log::LevelFilter;
+use extra::logging::{self, VerbosityArgs};
 use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
 use rayon::iter::{IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator};
 use serde::Deserialize;
-use simplelog::{ColorChoice, CombinedLogger, TermLogger, TerminalMode, WriteLogger};
 use std::{
     cmp::Reverse,
     collections::{BTreeSet, HashMap, HashSet},
@@ -17,7 +16,7 @@ use std::{
 };
 use wazir_drop::{
     Color, DefaultEvaluator, EvaluatedPosition, Position, Score, ScoreExpanded, Search, SetupMove,
-    Symmetry,
+    Symmetry, TTable,
     base128::Base128Encoder,
     book::encode_setup_move,
     constants::{Depth, Hyperparameters, ONE_PLY},
@@ -27,6 +26,8 @@ use wazir_drop::{
 #[derive(Parser, Debug)]
 struct Args {
     config_file: PathBuf,
+    #[command(flatten)]
+    verbosity: VerbosityArgs,
 }
 
 #[derive(Clone, Deserialize)]
@@ -47,6 +48,9 @@ struct Config {
     extra_depth_book_size: Vec<usize>,
     block: usize,
     log_period_seconds: f64,
+    /// Share one transposition table of this size between all searches of a depth.
+    #[serde(default)]
+    shared_ttable_size_mb: Option<usize>,
 }
 
 fn main() -> ExitCode {
@@ -70,15 +74,7 @@ fn run() -> Result<(), Box<dyn Error>> {
     config.export_book = config_dir.join(&config.export_book);
 
     let log_file = File::create(&config.log)?;
-    CombinedLogger::init(vec![
-        WriteLogger::new(LevelFilter::Info, simplelog::Config::default(), log_file),
-        TermLogger::new(
-            LevelFilter::Info,
-            simplelog::Config::default(),
-            TerminalMode::Stderr,
-            ColorChoice::Auto,
-        ),
-    ])?;
+    logging::init_logger(args.verbosity.verbosity(), log_file)?;
 
     rayon::ThreadPoolBuilder::new()
         .num_threads(config.cpus)
@@ -165,10 +161,7 @@ impl OpeningSolver {
         self.blue_setups = self
             .openings
             .iter()
-            .map(|opening| opening.red.with_color(Color::Blue))
-            .flat_map(|setup| {
-                [Symmetry::Identity, Symmetry::FlipX].map(|symmetry| symmetry.apply_to_setup(setup))
-            })
+            .flat_map(|opening| opening.red.with_color(Color::Blue).symmetric_variants())
             .collect();
     }
 
@@ -224,6 +217,11 @@ impl OpeningSolver {
 
     fn improve_openings_using_search(&mut self, n: usize, depth: Depth) {
         log::info!("Build book openings: {n} depth {depth}");
+        let start_time = Instant::now();
+        let ttable = self
+            .config
+            .shared_ttable_size_mb
+            .map(|size_mb| Arc::new(TTable::new(size_mb << 20)));
         let mut last_log_time = Instant::now();
         for (block_index, block) in self.openings[..n].chunks_mut(self.config.block).enumerate() {
             if last_log_time.elapsed().as_secs_f64() >= self.config.log_period_seconds {
@@ -237,10 +235,15 @@ impl OpeningSolver {
                     &self.hyperparameters,
                     depth,
                     &self.blue_setups,
+                    ttable.as_ref(),
                 );
             });
         }
         self.openings[..n].sort_by_key(|&opening| Reverse(opening));
+        log::info!(
+            "Depth {depth} done in {t:.1}s",
+            t = start_time.elapsed().as_secs_f64()
+        );
     }
 
     fn log_opening_stats(&self) {
@@ -278,7 +281,7 @@ impl OpeningSolver {
 
         for (index, opening) in self.openings.iter().enumerate() {
             let (symmetry, red_equivalent) =
-                Symmetry::normalize_red_setup(opening.blue.unwrap().with_color(Color::Red));
+                opening.blue.unwrap().with_color(Color::Red).normalize();
             let setup_number = setup_number_mapping
                 .get(&red_equivalent)
                 .copied()
@@ -358,8 +361,12 @@ fn compute_opening_search(
     hyperparameters: &Hyperparameters,
     depth: Depth,
     blue_setups: &[SetupMove],
+    ttable: Option<&Arc<TTable>>,
 ) -> Opening {
-    let mut search = Search::new(hyperparameters, evaluator);
+    let mut search = match ttable {
+        Some(ttable) => Search::with_ttable(hyperparameters, evaluator, ttable),
+        None => Search::new(hyperparameters, evaluator),
+    };
     let result = search.search_blue_setup(red, Some(depth), None, blue_setups);
     Opening {
         score: -result.score,
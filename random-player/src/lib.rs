@@ -1,7 +1,9 @@
 use extra::moverand;
 use rand::{SeedableRng, rngs::StdRng};
-use std::time::Duration;
-use wazir_drop::{AnyMove, Color, Player, PlayerFactory, Position, clock::Timer};
+use wazir_drop::{
+    AnyMove, Color, Player, PlayerFactory, Position,
+    clock::{TimeControl, Timer},
+};
 
 #[derive(Debug)]
 pub struct RandomPlayerFactory;
@@ -18,8 +20,9 @@ impl PlayerFactory for RandomPlayerFactory {
         &self,
         _game_id: &str,
         _color: Color,
+        _start: &Position,
         _opening: &[AnyMove],
-        _time_limit: Option<Duration>,
+        _time_control: Option<TimeControl>,
     ) -> Box<dyn Player> {
         Box::new(RandomPlayer::new())
     }
@@ -1,54 +1,85 @@
 use std::time::Duration;
 use wazir_drop::{
-    AnyMove, Color, Outcome, PlayerFactory, Position, Stage, clock::Timer,
-    constants::DEFAULT_TIME_LIMIT, enums::EnumMap,
+    AnyMove, Color, EndReason, Outcome, PlayerFactory, Position,
+    clock::{TimeControl, Timer},
+    constants::DEFAULT_TIME_LIMIT,
+    enums::EnumMap,
 };
 
 #[derive(Debug, Clone)]
 pub struct FinishedGame {
+    /// Played from the start position, including the opening.
     pub moves: Vec<AnyMove>,
     pub outcome: Outcome,
+    pub end_reason: EndReason,
     pub time_used: EnumMap<Color, Duration>,
+    /// Time taken by each move after the opening, in order.
+    pub move_times: EnumMap<Color, Vec<Duration>>,
+    /// Total nodes searched, if the player reports search info.
+    pub nodes: EnumMap<Color, Option<u64>>,
 }
 
+/// Plays a game from `start`, usually `Position::initial()`, after the `opening` moves.
 pub fn run_game(
     game_id: &str,
     player_factories: EnumMap<Color, &dyn PlayerFactory>,
+    start: &Position,
     opening: &[AnyMove],
-    time_limit: EnumMap<Color, Option<Duration>>,
+    time_control: EnumMap<Color, Option<TimeControl>>,
 ) -> FinishedGame {
-    let mut position = Position::initial();
+    let mut position = *start;
     let mut moves = opening.to_vec();
 
-    let mut timers =
-        EnumMap::from_fn(|color| Timer::new(time_limit[color].unwrap_or(DEFAULT_TIME_LIMIT)));
+    let mut timers = EnumMap::from_fn(|color| {
+        Timer::new(time_control[color].map_or(DEFAULT_TIME_LIMIT, |time_control| time_control.base))
+    });
 
     let mut players = EnumMap::from_fn(|color| {
         timers[color].start();
-        let player = player_factories[color].create(game_id, color, opening, time_limit[color]);
+        let player =
+            player_factories[color].create(game_id, color, start, opening, time_control[color]);
         timers[color].stop();
         player
     });
 
+    let mut nodes = EnumMap::from_fn(|_| None);
+    let mut move_times = EnumMap::from_fn(|_| Vec::new());
+
     for &mov in opening {
         position = position.make_any_move(mov).expect("Invalid opening move");
     }
 
-    let outcome = loop {
+    let (outcome, end_reason) = loop {
         let color = position.to_move();
         let opp = color.opposite();
 
-        if let Stage::End(outcome) = position.stage() {
-            break outcome;
+        if let Some(end) = position.end() {
+            break end;
         }
+        let used_before = timers[color].get_used();
         timers[color].start();
         let mov = players[color].make_move(&position, &timers[color]);
         timers[color].stop();
+        let move_time = timers[color].get_used() - used_before;
+        move_times[color].push(move_time);
+        if let Some(info) = players[color].last_search_info() {
+            *nodes[color].get_or_insert(0) += info.nodes;
+        }
 
         moves.push(mov);
+        if let Some(time_control) = time_control[color] {
+            if timers[color].get() == Duration::ZERO
+                || time_control
+                    .move_cap
+                    .is_some_and(|move_cap| move_time > move_cap)
+            {
+                break (Outcome::win(opp), EndReason::TimeForfeit);
+            }
+            timers[color].add(time_control.increment);
+        }
         let new_position = position.make_any_move(mov).expect("Invalid move");
 
-        if !matches!(new_position.stage(), Stage::End(_)) {
+        if new_position.end().is_none() {
             timers[opp].start();
             players[opp].opponent_move(&position, mov, &timers[opp]);
             timers[opp].stop();
@@ -60,6 +91,9 @@ pub fn run_game(
     FinishedGame {
         moves,
         outcome,
+        end_reason,
         time_used: EnumMap::from_fn(|color| timers[color].get_used()),
+        move_times,
+        nodes,
     }
 }
@@ -1,7 +1,9 @@
 mod game;
 mod opening;
+mod report;
 mod run_match;
 
 pub use game::run_game;
-pub use opening::random_opening;
-pub use run_match::run_match;
+pub use opening::{OpeningOptions, random_opening, random_opening_positions, random_opening_range};
+pub use report::{GAMES_CSV_HEADER, MATCHES_CSV_HEADER, games_csv, match_csv, match_json};
+pub use run_match::{GameRecord, MatchResult, Scoring, run_match};
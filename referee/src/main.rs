@@ -1,25 +1,30 @@
 use clap::Parser;
 use external_player::ExternalPlayerFactory;
-use log::LevelFilter;
+use extra::logging::{self, VerbosityArgs};
 use rand::{SeedableRng, rngs::StdRng};
 use random_player::RandomPlayerFactory;
-use referee::run_match;
+use referee::{
+    GAMES_CSV_HEADER, MATCHES_CSV_HEADER, OpeningOptions, Scoring, games_csv, match_csv,
+    match_json, run_match,
+};
 use serde::Deserialize;
-use simplelog::{ColorChoice, CombinedLogger, TermLogger, TerminalMode, WriteLogger};
 use std::{
     collections::HashMap,
     error::Error,
-    fs::{self, File},
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
     path::{Path, PathBuf},
     process::ExitCode,
     sync::Arc,
     time::Duration,
 };
-use wazir_drop::{MainPlayerFactory, PlayerFactory};
+use wazir_drop::{Evaluator, MainPlayerFactory, PlayerFactory, Position, clock::TimeControl};
 
 #[derive(Parser, Debug)]
 struct Args {
     config: PathBuf,
+    #[command(flatten)]
+    verbosity: VerbosityArgs,
 }
 
 #[derive(Debug, Deserialize)]
@@ -44,9 +49,25 @@ enum PlayerConfig {
 struct MatchConfig {
     players: [String; 2],
     opening_length: usize,
+    /// If set, opening lengths are picked uniformly from `opening_length..=opening_length_max`.
+    opening_length_max: Option<usize>,
+    /// Probability of starting from a book opening.
+    #[serde(default)]
+    book_probability: f64,
+    /// File with positions to start the rounds from in turn, one `Position::to_fen` per line.
+    /// The opening is played from each. The initial position if omitted.
+    start_positions: Option<PathBuf>,
     num_rounds: usize,
     time_limit_0: Option<u32>,
     time_limit_1: Option<u32>,
+    /// Milliseconds added to the clock after each move. Only used with a time limit.
+    #[serde(default)]
+    increment_0: u32,
+    #[serde(default)]
+    increment_1: u32,
+    /// Points per outcome. Standard win/draw/loss = 1/0/-1 scoring if omitted.
+    #[serde(default)]
+    scoring: Scoring,
 }
 
 fn main() -> ExitCode {
@@ -69,15 +90,7 @@ fn run() -> Result<(), Box<dyn Error>> {
     fs::create_dir_all(&log_dir)?;
     let log_file = File::create(log_dir.join("referee.log"))?;
 
-    CombinedLogger::init(vec![
-        WriteLogger::new(LevelFilter::Info, simplelog::Config::default(), log_file),
-        TermLogger::new(
-            LevelFilter::Info,
-            simplelog::Config::default(),
-            TerminalMode::Stderr,
-            ColorChoice::Auto,
-        ),
-    ])?;
+    logging::init_logger(args.verbosity.verbosity(), log_file)?;
 
     run_matches(&config, config_dir, &log_dir)?;
     Ok(())
@@ -89,7 +102,16 @@ fn run_matches(config: &Config, config_dir: &Path, log_dir: &Path) -> Result<(),
         .iter()
         .map(|(name, player_config)| {
             let player_factory: Arc<dyn PlayerFactory> = match player_config {
-                PlayerConfig::Main => Arc::new(MainPlayerFactory::default()),
+                PlayerConfig::Main => {
+                    let player_factory = MainPlayerFactory::default();
+                    let evaluator = player_factory.evaluator();
+                    log::info!(
+                        "Player {name}: evaluator {evaluator_name} {version:016x}",
+                        evaluator_name = evaluator.name(),
+                        version = evaluator.version(),
+                    );
+                    Arc::new(player_factory)
+                }
                 PlayerConfig::Random => Arc::new(RandomPlayerFactory::new()),
                 PlayerConfig::External { path } => Arc::new(ExternalPlayerFactory::new(
                     name,
@@ -107,10 +129,27 @@ fn run_matches(config: &Config, config_dir: &Path, log_dir: &Path) -> Result<(),
                 return Err(format!("Player {player_name} not found").into());
             }
         }
+        if match_config
+            .opening_length_max
+            .is_some_and(|max| max < match_config.opening_length)
+        {
+            return Err("opening_length_max is less than opening_length".into());
+        }
+        if !(0.0..=1.0).contains(&match_config.book_probability) {
+            return Err("book_probability must be between 0 and 1".into());
+        }
+        if match_config.scoring.win <= match_config.scoring.loss {
+            return Err("scoring.win must be greater than scoring.loss".into());
+        }
     }
 
     let mut rng = StdRng::from_os_rng();
 
+    let games_csv_path = log_dir.join("games.csv");
+    let matches_csv_path = log_dir.join("matches.csv");
+    fs::write(&games_csv_path, GAMES_CSV_HEADER)?;
+    fs::write(&matches_csv_path, MATCHES_CSV_HEADER)?;
+
     for (match_idx, match_config) in config.r#match.iter().enumerate() {
         let match_id = format!("{match_idx}");
         log::info!("Match {match_id}");
@@ -120,19 +159,75 @@ fn run_matches(config: &Config, config_dir: &Path, log_dir: &Path) -> Result<(),
             .each_ref()
             .map(|name| player_factories.get(name).unwrap().clone());
 
-        let time_limits = [match_config.time_limit_0, match_config.time_limit_1]
-            .map(|t| t.map(|t| Duration::from_millis(t.into())));
+        let time_controls = [
+            (match_config.time_limit_0, match_config.increment_0),
+            (match_config.time_limit_1, match_config.increment_1),
+        ]
+        .map(|(t, increment)| {
+            t.map(|t| TimeControl {
+                base: Duration::from_millis(t.into()),
+                increment: Duration::from_millis(increment.into()),
+                move_cap: None,
+            })
+        });
+
+        let start_positions = match &match_config.start_positions {
+            Some(path) => read_start_positions(&config_dir.join(path))?,
+            None => Vec::new(),
+        };
 
         let match_result = run_match(
             &match_id,
             match_config.num_rounds,
             config.num_cpus,
-            match_config.opening_length,
+            &OpeningOptions {
+                min_len: match_config.opening_length,
+                max_len: match_config
+                    .opening_length_max
+                    .unwrap_or(match_config.opening_length),
+                book_probability: match_config.book_probability,
+                start_positions,
+            },
             player_factories,
-            time_limits,
+            time_controls,
+            match_config.scoring,
             &mut rng,
         );
         log::info!("{match_result}");
+
+        fs::write(
+            log_dir.join(format!("match_{match_id}.json")),
+            match_json(&match_config.players, &match_result),
+        )?;
+        append(
+            &games_csv_path,
+            &games_csv(&match_config.players, &match_result),
+        )?;
+        append(
+            &matches_csv_path,
+            &match_csv(&match_config.players, &match_result),
+        )?;
     }
     Ok(())
 }
+
+/// One position per non-empty line.
+fn read_start_positions(path: &Path) -> Result<Vec<Position>, Box<dyn Error>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Can't read {path}: {e}", path = path.display()))?;
+    let positions = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| Position::from_fen(line).map_err(|_| format!("Invalid start position {line}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    if positions.is_empty() {
+        return Err(format!("No start positions in {path}", path = path.display()).into());
+    }
+    Ok(positions)
+}
+
+fn append(path: &Path, text: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().append(true).open(path)?;
+    file.write_all(text.as_bytes())
+}
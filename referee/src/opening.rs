@@ -1,14 +1,122 @@
 use extra::moverand;
-use rand::Rng;
-use wazir_drop::{AnyMove, Position, Stage};
+use rand::{Rng, seq::IndexedRandom};
+use std::{collections::HashSet, sync::LazyLock};
+use wazir_drop::{AnyMove, Position, SetupMove, Stage, book};
+
+/// The decoded opening book, shared by all openings so it's only decoded once.
+static BOOK_OPENINGS: LazyLock<Vec<(SetupMove, SetupMove)>> = LazyLock::new(book::openings);
+
+/// How openings are generated for a match.
+#[derive(Debug, Clone)]
+pub struct OpeningOptions {
+    pub min_len: usize,
+    pub max_len: usize,
+    /// Probability of taking the setup moves from the opening book rather than at random.
+    pub book_probability: f64,
+    /// Positions to start games from, one per round in turn, e.g. curated endgames. The opening
+    /// is played from there. Empty means the initial position.
+    pub start_positions: Vec<Position>,
+}
+
+impl OpeningOptions {
+    /// Random openings of exactly `len` plies.
+    pub fn fixed(len: usize) -> Self {
+        Self {
+            min_len: len,
+            max_len: len,
+            book_probability: 0.0,
+            start_positions: Vec::new(),
+        }
+    }
+
+    /// The start position for `round` and an opening played from it.
+    pub fn generate_for_round<RNG: Rng>(
+        &self,
+        round: usize,
+        rng: &mut RNG,
+    ) -> (Position, Vec<AnyMove>) {
+        let start = if self.start_positions.is_empty() {
+            Position::initial()
+        } else {
+            self.start_positions[round % self.start_positions.len()]
+        };
+        (start, self.generate_from(&start, rng))
+    }
+
+    /// An opening from the initial position.
+    ///
+    /// Panics if `min_len > max_len`.
+    pub fn generate<RNG: Rng>(&self, rng: &mut RNG) -> Vec<AnyMove> {
+        self.generate_from(&Position::initial(), rng)
+    }
+
+    /// An opening from `start`. The book is only used from the initial position.
+    ///
+    /// Panics if `min_len > max_len`.
+    pub fn generate_from<RNG: Rng>(&self, start: &Position, rng: &mut RNG) -> Vec<AnyMove> {
+        assert!(self.min_len <= self.max_len, "Empty opening length range");
+        let len = rng.random_range(self.min_len..=self.max_len);
+        let mut moves = Vec::new();
+        if start.ply() == 0
+            && self.book_probability > 0.0
+            && rng.random_bool(self.book_probability)
+            && let Some(&(red, blue)) = BOOK_OPENINGS.choose(rng)
+        {
+            moves.extend(
+                [AnyMove::Setup(red), AnyMove::Setup(blue)]
+                    .into_iter()
+                    .take(len),
+            );
+        }
+        let mut position = *start;
+        for &mov in &moves {
+            position = position.make_any_move(mov).unwrap();
+        }
+        while moves.len() < len && !matches!(position.stage(), Stage::End(_)) {
+            let mov = moverand::random_legal_move(&position, rng);
+            position = position.make_any_move(mov).unwrap();
+            moves.push(mov);
+        }
+        moves
+    }
+}
 
 pub fn random_opening<RNG: Rng>(len: usize, rng: &mut RNG) -> Vec<AnyMove> {
-    let mut moves = Vec::new();
-    let mut position = Position::initial();
-    while moves.len() < len && !matches!(position.stage(), Stage::End(_)) {
-        let mov = moverand::random_move(&position, rng);
-        position = position.make_any_move(mov).unwrap();
-        moves.push(mov);
-    }
-    moves
+    OpeningOptions::fixed(len).generate(rng)
+}
+
+/// Random opening with a length picked uniformly from `min..=max`.
+pub fn random_opening_range<RNG: Rng>(min: usize, max: usize, rng: &mut RNG) -> Vec<AnyMove> {
+    OpeningOptions {
+        min_len: min,
+        max_len: max,
+        book_probability: 0.0,
+        start_positions: Vec::new(),
+    }
+    .generate(rng)
+}
+
+/// `n` distinct positions after `plies` random moves, deduplicated by `canonical_hash`.
+///
+/// Games that end early are skipped. Gives up after `MAX_ATTEMPTS_PER_POSITION * n`
+/// attempts, so fewer positions are returned if there aren't enough distinct ones.
+pub fn random_opening_positions<RNG: Rng>(n: usize, plies: usize, rng: &mut RNG) -> Vec<Position> {
+    const MAX_ATTEMPTS_PER_POSITION: usize = 100;
+
+    let mut positions = Vec::with_capacity(n);
+    let mut seen = HashSet::new();
+    for _ in 0..MAX_ATTEMPTS_PER_POSITION * n {
+        if positions.len() == n {
+            break;
+        }
+        let opening = random_opening(plies, rng);
+        let position = Position::replay(&opening).unwrap();
+        if opening.len() == plies
+            && !matches!(position.stage(), Stage::End(_))
+            && seen.insert(position.canonical_hash())
+        {
+            positions.push(position);
+        }
+    }
+    positions
 }
@@ -0,0 +1,92 @@
+use crate::run_match::MatchResult;
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Header of the per-game CSV.
+pub const GAMES_CSV_HEADER: &str = "match_id,game_id,red,blue,start,opening,outcome,end_reason,\
+    num_moves,red_time_ms,blue_time_ms,red_nodes,blue_nodes,\
+    red_move_times_ms,blue_move_times_ms\n";
+
+/// Header of the per-match CSV.
+pub const MATCHES_CSV_HEADER: &str = "match_id,player0,player1,num_games,num_draws,\
+    player0_points,score_per_game,elo_diff,average_game_length,\
+    player0_max_time_ms,player1_max_time_ms\n";
+
+#[derive(Serialize)]
+struct MatchReport<'a> {
+    players: &'a [String; 2],
+    #[serde(flatten)]
+    result: &'a MatchResult,
+}
+
+/// The match result, including all games, as pretty-printed JSON.
+pub fn match_json(players: &[String; 2], result: &MatchResult) -> String {
+    serde_json::to_string_pretty(&MatchReport { players, result })
+        .expect("MatchResult serialization failed")
+}
+
+/// One CSV line per game, without the header.
+pub fn games_csv(players: &[String; 2], result: &MatchResult) -> String {
+    let mut csv = String::new();
+    for game in &result.games {
+        let red = &players[game.red_player];
+        let blue = &players[game.red_player ^ 1];
+        let nodes = game
+            .nodes
+            .map(|n| n.map(|n| n.to_string()).unwrap_or_default());
+        // Space-separated, like the opening.
+        let move_times = game.move_times.each_ref().map(|times| {
+            times
+                .iter()
+                .map(|t| t.as_millis().to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&result.match_id),
+            csv_field(&game.game_id),
+            csv_field(red),
+            csv_field(blue),
+            csv_field(&game.start),
+            csv_field(&game.opening),
+            game.outcome,
+            game.end_reason,
+            game.num_moves,
+            game.time_used[0].as_millis(),
+            game.time_used[1].as_millis(),
+            nodes[0],
+            nodes[1],
+            move_times[0],
+            move_times[1],
+        );
+    }
+    csv
+}
+
+/// One CSV line with the aggregate match result, without the header.
+pub fn match_csv(players: &[String; 2], result: &MatchResult) -> String {
+    format!(
+        "{},{},{},{},{},{},{:.3},{:.3},{:.2},{},{}\n",
+        csv_field(&result.match_id),
+        csv_field(&players[0]),
+        csv_field(&players[1]),
+        result.num_games,
+        result.num_draws,
+        result.player0_points,
+        result.score_per_game(),
+        result.elo_diff(),
+        result.total_game_length as f64 / result.num_games as f64,
+        result.max_time_used[0].as_millis(),
+        result.max_time_used[1].as_millis(),
+    )
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
@@ -1,21 +1,151 @@
-use crate::{random_opening, run_game};
+use crate::{OpeningOptions, run_game};
 use rand::Rng;
+use serde::{Deserialize, Serialize, Serializer};
 use std::{
     fmt::{self, Display, Formatter},
     sync::{Arc, Mutex},
     time::Duration,
 };
 use threadpool::ThreadPool;
-use wazir_drop::{Color, Outcome, PlayerFactory, enums::EnumMap};
+use wazir_drop::{Color, Outcome, PlayerFactory, clock::TimeControl, enums::EnumMap};
 
-#[derive(Debug, Clone)]
+/// Points a player gets for each game outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scoring {
+    pub win: i32,
+    pub draw: i32,
+    pub loss: i32,
+    /// Leave draws out of the points and the per-game average, so that only decisive games
+    /// count.
+    pub decisive_only: bool,
+}
+
+impl Scoring {
+    /// The same points as `Outcome::points`.
+    pub const STANDARD: Self = Self {
+        win: 1,
+        draw: 0,
+        loss: -1,
+        decisive_only: false,
+    };
+
+    /// Points for `color`. A draw is worth `draw` even with `decisive_only`; see
+    /// `match_points`.
+    pub fn points(self, outcome: Outcome, color: Color) -> i32 {
+        match outcome.points(color) {
+            1 => self.win,
+            0 => self.draw,
+            _ => self.loss,
+        }
+    }
+
+    /// Points for `color` added to the match total: no points for a draw with `decisive_only`.
+    pub fn match_points(self, outcome: Outcome, color: Color) -> i32 {
+        if self.decisive_only && outcome == Outcome::Draw {
+            0
+        } else {
+            self.points(outcome, color)
+        }
+    }
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct MatchResult {
     pub match_id: String,
     pub num_games: usize,
     pub num_draws: usize,
     pub player0_points: i32,
+    pub scoring: Scoring,
     pub total_game_length: usize,
+    #[serde(rename = "max_time_used_ms", serialize_with = "serialize_millis")]
     pub max_time_used: [Duration; 2],
+    /// In the order the games were scheduled.
+    pub games: Vec<GameRecord>,
+}
+
+/// Result of a single game in a match.
+#[derive(Debug, Clone, Serialize)]
+pub struct GameRecord {
+    pub game_id: String,
+    pub round: usize,
+    /// Which player (0 or 1) played red.
+    pub red_player: usize,
+    /// `Position::to_fen` of the position the opening was played from.
+    pub start: String,
+    /// Space-separated opening moves.
+    pub opening: String,
+    pub outcome: String,
+    pub end_reason: String,
+    pub num_moves: usize,
+    /// Indexed by color: red, blue.
+    #[serde(rename = "time_used_ms", serialize_with = "serialize_millis")]
+    pub time_used: [Duration; 2],
+    /// Time taken by each move after the opening. Indexed by color: red, blue.
+    #[serde(
+        rename = "move_times_ms",
+        serialize_with = "serialize_millis_per_color"
+    )]
+    pub move_times: [Vec<Duration>; 2],
+    /// Indexed by color: red, blue. `None` if the player doesn't report search info.
+    pub nodes: [Option<u64>; 2],
+}
+
+impl MatchResult {
+    /// Number of games the per-game average is taken over.
+    pub fn num_scored_games(&self) -> usize {
+        if self.scoring.decisive_only {
+            self.num_games - self.num_draws
+        } else {
+            self.num_games
+        }
+    }
+
+    /// Halfway between a win and a loss if no game is scored, e.g. all draws with
+    /// `decisive_only`.
+    pub fn score_per_game(&self) -> f64 {
+        match self.num_scored_games() {
+            0 => f64::from(self.scoring.win + self.scoring.loss) / 2.0,
+            n => self.player0_points as f64 / n as f64,
+        }
+    }
+
+    /// ELO difference of player 0 over player 1.
+    pub fn elo_diff(&self) -> f64 {
+        // win_prob = 1 / (1 + 10^(-elo_diff / 400))
+        // win_prob = (score_per_game - loss) / (win - loss)
+        // 1 + 10^(-elo_diff / 400) = 1 / win_prob
+        // elo_diff = -400 * log10(1 / win_prob - 1)
+        let scoring = self.scoring;
+        let win_prob = (self.score_per_game() - f64::from(scoring.loss))
+            / f64::from(scoring.win - scoring.loss);
+        -400.0 * (1.0 / win_prob - 1.0).log10()
+    }
+}
+
+fn serialize_millis<S: Serializer>(durations: &[Duration; 2], s: S) -> Result<S::Ok, S::Error> {
+    durations.map(|d| d.as_millis()).serialize(s)
+}
+
+fn serialize_millis_per_color<S: Serializer>(
+    durations: &[Vec<Duration>; 2],
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    durations
+        .each_ref()
+        .map(|durations| {
+            durations
+                .iter()
+                .map(Duration::as_millis)
+                .collect::<Vec<_>>()
+        })
+        .serialize(s)
 }
 
 impl Display for MatchResult {
@@ -23,8 +153,9 @@ impl Display for MatchResult {
         writeln!(f, "Match {}: ", self.match_id)?;
         writeln!(f, "  Games: {}", self.num_games)?;
         writeln!(f, "  Score: {}", self.player0_points)?;
-        let score_per_game = self.player0_points as f64 / self.num_games as f64;
-        let score_per_game_2stddev = 2.0 / (self.num_games as f64).sqrt();
+        let score_per_game = self.score_per_game();
+        let score_per_game_2stddev = f64::from(self.scoring.win - self.scoring.loss)
+            / (self.num_scored_games() as f64).sqrt();
         writeln!(
             f,
             "  Score per game: {score_per_game:.3}  +- {score_per_game_2stddev:.3}",
@@ -39,15 +170,7 @@ impl Display for MatchResult {
             "  Average game length: {:.2}",
             self.total_game_length as f64 / self.num_games as f64
         )?;
-        // win_prob = 1 / (1 + 10^(-elo_diff / 400))
-        // win_prob = (score_per_game + 1) / 2
-        // 1 / (1 + 10^(-elo_diff / 400)) = (score_per_game + 1) / 2
-        // 1 + 10^(-elo_diff / 400) = 2 / (score_per_game + 1)
-        // 10^(-elo_diff / 400) = 2 / (score_per_game + 1) - 1
-        // -elo_diff / 400 = log10(2 / (score_per_game + 1) - 1)
-        // elo_diff = -400 * log10(2 / (score_per_game + 1) - 1)
-        let elo_diff = -400.0 * (2.0 / (score_per_game + 1.0) - 1.0).log10();
-        writeln!(f, "  ELO: {elo_diff:.3}")?;
+        writeln!(f, "  ELO: {elo_diff:.3}", elo_diff = self.elo_diff())?;
         write!(f, "  Max time used:")?;
         for t in self.max_time_used {
             write!(f, " {}", t.as_millis())?;
@@ -57,13 +180,15 @@ impl Display for MatchResult {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn run_match<RNG: Rng>(
     match_id: &str,
     num_rounds: usize,
     num_threads: usize,
-    opening_length: usize,
+    openings: &OpeningOptions,
     player_factories: [Arc<dyn PlayerFactory>; 2],
-    time_limits: [Option<Duration>; 2],
+    time_controls: [Option<TimeControl>; 2],
+    scoring: Scoring,
     rng: &mut RNG,
 ) -> MatchResult {
     let thread_pool = ThreadPool::new(num_threads);
@@ -72,11 +197,13 @@ pub fn run_match<RNG: Rng>(
         num_games: 0,
         num_draws: 0,
         player0_points: 0,
+        scoring,
         total_game_length: 0,
         max_time_used: [Duration::ZERO; 2],
+        games: Vec::new(),
     }));
     for round in 0..num_rounds {
-        let opening = random_opening(opening_length, rng);
+        let (start, opening) = openings.generate_for_round(round, rng);
         for red_player_idx in 0..2 {
             let game_id = format!("{match_id}-{round}-{red_player_idx}");
             let opening = opening.clone();
@@ -86,13 +213,12 @@ pub fn run_match<RNG: Rng>(
                 let pf = EnumMap::from_fn(|color: Color| {
                     &*player_factories[red_player_idx ^ color.index()]
                 });
-                let tl =
-                    EnumMap::from_fn(|color: Color| time_limits[red_player_idx ^ color.index()]);
-                let finished_game = run_game(&game_id, pf, &opening, tl);
+                let tc =
+                    EnumMap::from_fn(|color: Color| time_controls[red_player_idx ^ color.index()]);
+                let finished_game = run_game(&game_id, pf, &start, &opening, tc);
 
-                let player0_points = finished_game
-                    .outcome
-                    .points(Color::from_index(red_player_idx));
+                let player0_points =
+                    scoring.match_points(finished_game.outcome, Color::from_index(red_player_idx));
 
                 let mut match_result = match_result.lock().unwrap();
                 match_result.num_games += 1;
@@ -105,8 +231,27 @@ pub fn run_match<RNG: Rng>(
                     match_result.max_time_used[i] = match_result.max_time_used[i]
                         .max(finished_game.time_used[Color::from_index(i ^ red_player_idx)]);
                 }
+                match_result.games.push(GameRecord {
+                    game_id: game_id.clone(),
+                    round,
+                    red_player: red_player_idx,
+                    start: start.to_fen(),
+                    opening: opening
+                        .iter()
+                        .map(|mov| mov.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    outcome: finished_game.outcome.to_string(),
+                    end_reason: finished_game.end_reason.to_string(),
+                    num_moves: finished_game.moves.len(),
+                    time_used: [Color::Red, Color::Blue].map(|color| finished_game.time_used[color]),
+                    move_times: [Color::Red, Color::Blue]
+                        .map(|color| finished_game.move_times[color].clone()),
+                    nodes: [Color::Red, Color::Blue].map(|color| finished_game.nodes[color]),
+                });
                 log::info!(
-                    "{game_id} points {player0_points} total {running_points} time used {time_used_0} ms {time_used_1} ms",
+                    "{game_id} points {player0_points} {end_reason} total {running_points} time used {time_used_0} ms {time_used_1} ms",
+                    end_reason = finished_game.end_reason,
                     running_points = match_result.player0_points,
                     time_used_0 = finished_game.time_used[Color::from_index(red_player_idx)].as_millis(),
                     time_used_1 = finished_game.time_used[Color::from_index(red_player_idx ^ 1)].as_millis(),
@@ -115,5 +260,9 @@ pub fn run_match<RNG: Rng>(
         }
     }
     thread_pool.join();
-    match_result.lock().unwrap().clone()
+    let mut match_result = match_result.lock().unwrap().clone();
+    match_result
+        .games
+        .sort_by_key(|game| (game.round, game.red_player));
+    match_result
 }
@@ -1,16 +1,43 @@
 use rand::{SeedableRng, rngs::StdRng};
 use random_player::RandomPlayerFactory;
-use wazir_drop::{PlayerFactory, enums::EnumMap};
+use wazir_drop::{EndReason, Outcome, PlayerFactory, Position, enums::EnumMap};
 
 #[test]
 fn test_referee() {
     let mut rng = StdRng::from_os_rng();
     let player_factory = RandomPlayerFactory::new();
     let player_factories = EnumMap::from_fn(|_| &player_factory as &dyn PlayerFactory);
-    let time_limits = EnumMap::from_fn(|_| None);
+    let time_controls = EnumMap::from_fn(|_| None);
 
     for opening_len in [0, 2] {
         let opening = referee::random_opening(opening_len, &mut rng);
-        _ = referee::run_game("", player_factories, &opening, time_limits);
+        let game = referee::run_game(
+            "",
+            player_factories,
+            &Position::initial(),
+            &opening,
+            time_controls,
+        );
+        let expected_reason = match game.outcome {
+            Outcome::Draw => EndReason::MoveLimit,
+            _ => EndReason::WazirCaptured,
+        };
+        assert_eq!(game.end_reason, expected_reason);
     }
 }
+
+#[test]
+fn test_referee_start_position() {
+    let mut rng = StdRng::from_os_rng();
+    let player_factory = RandomPlayerFactory::new();
+    let player_factories = EnumMap::from_fn(|_| &player_factory as &dyn PlayerFactory);
+    let time_controls = EnumMap::from_fn(|_| None);
+
+    let start = Position::replay(&referee::random_opening(3, &mut rng)).unwrap();
+    let game = referee::run_game("", player_factories, &start, &[], time_controls);
+    let mut position = start;
+    for &mov in &game.moves {
+        position = position.make_any_move(mov).unwrap();
+    }
+    assert_eq!(position.end(), Some((game.outcome, game.end_reason)));
+}
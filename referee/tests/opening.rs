@@ -0,0 +1,67 @@
+use rand::{SeedableRng, rngs::StdRng};
+use referee::OpeningOptions;
+use std::collections::HashSet;
+use wazir_drop::{AnyMove, Position, Stage, book};
+
+#[test]
+fn test_random_opening_positions() {
+    let mut rng = StdRng::seed_from_u64(445);
+    let positions = referee::random_opening_positions(20, 4, &mut rng);
+    assert_eq!(positions.len(), 20);
+    let hashes: HashSet<u64> = positions.iter().map(|p| p.canonical_hash()).collect();
+    assert_eq!(hashes.len(), positions.len());
+    for position in &positions {
+        assert_eq!(position.ply(), 4);
+        assert_eq!(position.stage(), Stage::Regular);
+    }
+
+    // Only one distinct initial position.
+    let positions = referee::random_opening_positions(5, 0, &mut rng);
+    assert_eq!(positions.len(), 1);
+}
+
+#[test]
+fn test_random_opening_range() {
+    let mut rng = StdRng::seed_from_u64(445);
+    let mut lengths = HashSet::new();
+    for _ in 0..100 {
+        let opening = referee::random_opening_range(2, 6, &mut rng);
+        assert!((2..=6).contains(&opening.len()));
+        assert!(Position::replay(&opening).is_ok());
+        _ = lengths.insert(opening.len());
+    }
+    assert!(lengths.len() > 1);
+
+    assert!(referee::random_opening_range(0, 0, &mut rng).is_empty());
+}
+
+#[test]
+fn test_book_openings() {
+    let mut rng = StdRng::seed_from_u64(445);
+    let openings = book::openings();
+    let options = OpeningOptions {
+        min_len: 2,
+        max_len: 4,
+        book_probability: 1.0,
+        start_positions: Vec::new(),
+    };
+    for _ in 0..20 {
+        let opening = options.generate(&mut rng);
+        assert!(Position::replay(&opening).is_ok());
+        let (AnyMove::Setup(red), AnyMove::Setup(blue)) = (opening[0], opening[1]) else {
+            panic!("Expected setup moves");
+        };
+        assert!(openings.contains(&(red, blue)));
+    }
+
+    // Shorter than the setup.
+    let options = OpeningOptions {
+        min_len: 1,
+        max_len: 1,
+        book_probability: 1.0,
+        start_positions: Vec::new(),
+    };
+    let opening = options.generate(&mut rng);
+    assert_eq!(opening.len(), 1);
+    assert!(Position::replay(&opening).is_ok());
+}
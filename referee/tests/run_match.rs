@@ -1,17 +1,180 @@
 use rand::{SeedableRng, rngs::StdRng};
 use random_player::RandomPlayerFactory;
-use referee::run_match;
-use std::{array, sync::Arc};
-use wazir_drop::PlayerFactory;
+use referee::{
+    GAMES_CSV_HEADER, MATCHES_CSV_HEADER, MatchResult, OpeningOptions, Scoring, games_csv,
+    match_csv, match_json, run_match,
+};
+use std::{array, str::FromStr, sync::Arc};
+use wazir_drop::{AnyMove, Color, Outcome, PlayerFactory, Position};
 
 #[test]
 fn test_run_match() {
     let mut rng = StdRng::from_os_rng();
     let player_factories =
         array::from_fn(|_| -> Arc<dyn PlayerFactory> { Arc::new(RandomPlayerFactory::new()) });
-    let time_limits = array::from_fn(|_| None);
+    let time_controls = array::from_fn(|_| None);
 
-    let match_results = run_match("test", 10, 2, 2, player_factories, time_limits, &mut rng);
+    let match_results = run_match(
+        "test",
+        10,
+        2,
+        &OpeningOptions::fixed(2),
+        player_factories,
+        time_controls,
+        Scoring::STANDARD,
+        &mut rng,
+    );
 
     assert_eq!(match_results.num_games, 20);
+    assert_eq!(match_results.games.len(), 20);
+    for (index, game) in match_results.games.iter().enumerate() {
+        assert_eq!(game.round, index / 2);
+        assert_eq!(game.red_player, index % 2);
+        assert_eq!(game.opening.split(' ').count(), 2);
+        // Random players don't search.
+        assert_eq!(game.nodes, [None, None]);
+        let num_moves = game.move_times[0].len() + game.move_times[1].len();
+        assert_eq!(num_moves + 2, game.num_moves);
+        assert!(game.move_times[1].len() <= game.move_times[0].len());
+    }
+}
+
+#[test]
+fn test_match_reports() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let player_factories =
+        array::from_fn(|_| -> Arc<dyn PlayerFactory> { Arc::new(RandomPlayerFactory::new()) });
+    let time_controls = array::from_fn(|_| None);
+    let match_results = run_match(
+        "m",
+        2,
+        1,
+        &OpeningOptions::fixed(2),
+        player_factories,
+        time_controls,
+        Scoring::STANDARD,
+        &mut rng,
+    );
+    let players = ["alice".to_string(), "bob, jr".to_string()];
+
+    let json: serde_json::Value =
+        serde_json::from_str(&match_json(&players, &match_results)).unwrap();
+    assert_eq!(json["players"][1], "bob, jr");
+    assert_eq!(json["num_games"], 4);
+    assert_eq!(json["games"].as_array().unwrap().len(), 4);
+    assert_eq!(json["games"][1]["red_player"], 1);
+    assert!(json["games"][0]["time_used_ms"].is_array());
+    assert_eq!(
+        json["games"][0]["move_times_ms"].as_array().unwrap().len(),
+        2
+    );
+
+    let header_fields = GAMES_CSV_HEADER.trim_end().split(',').count();
+    let games = games_csv(&players, &match_results);
+    assert_eq!(games.lines().count(), 4);
+    let first = games.lines().next().unwrap();
+    assert!(first.starts_with("m,m-0-0,alice,\"bob, jr\","));
+    assert_eq!(first.split(',').count(), header_fields + 1);
+
+    let header_fields = MATCHES_CSV_HEADER.trim_end().split(',').count();
+    let line = match_csv(&players, &match_results);
+    assert!(line.starts_with("m,alice,\"bob, jr\",4,"));
+    assert_eq!(line.trim_end().split(',').count(), header_fields + 1);
+}
+
+#[test]
+fn test_scoring() {
+    let scoring = Scoring {
+        win: 3,
+        draw: 1,
+        loss: 0,
+        decisive_only: false,
+    };
+    for outcome in [Outcome::RedWin, Outcome::Draw, Outcome::BlueWin] {
+        for color in [Color::Red, Color::Blue] {
+            assert_eq!(
+                Scoring::STANDARD.points(outcome, color),
+                outcome.points(color)
+            );
+        }
+    }
+    assert_eq!(scoring.points(Outcome::RedWin, Color::Red), 3);
+    assert_eq!(scoring.points(Outcome::RedWin, Color::Blue), 0);
+    assert_eq!(scoring.points(Outcome::Draw, Color::Blue), 1);
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let player_factories =
+        array::from_fn(|_| -> Arc<dyn PlayerFactory> { Arc::new(RandomPlayerFactory::new()) });
+    let match_result = run_match(
+        "s",
+        3,
+        1,
+        &OpeningOptions::fixed(2),
+        player_factories,
+        array::from_fn(|_| None),
+        Scoring {
+            decisive_only: true,
+            ..scoring
+        },
+        &mut rng,
+    );
+    let decisive = match_result.num_games - match_result.num_draws;
+    assert_eq!(match_result.num_scored_games(), decisive);
+    // Draws award no points.
+    let expected_points: i32 = match_result
+        .games
+        .iter()
+        .map(|game| {
+            let outcome = Outcome::from_str(&game.outcome).unwrap();
+            let player0 = Color::from_index(game.red_player);
+            if outcome == Outcome::Draw {
+                0
+            } else {
+                scoring.points(outcome, player0)
+            }
+        })
+        .sum();
+    assert_eq!(match_result.player0_points, expected_points);
+
+    // With every game drawn, nothing is scored and the players are even.
+    let all_draws = MatchResult {
+        num_draws: match_result.num_games,
+        player0_points: 0,
+        ..match_result
+    };
+    assert_eq!(all_draws.num_scored_games(), 0);
+    assert_eq!(all_draws.score_per_game(), 1.5);
+    assert_eq!(all_draws.elo_diff(), 0.0);
+}
+
+#[test]
+fn test_start_positions() {
+    let midgame =
+        Position::from_fen("regular 2 AAAAAAAADDDDFFN WNAADADA/AFFAADDA/8/8/8/8/8/7w").unwrap();
+    let mut rng = StdRng::seed_from_u64(482);
+    let player_factories =
+        array::from_fn(|_| -> Arc<dyn PlayerFactory> { Arc::new(RandomPlayerFactory::new()) });
+    let match_result = run_match(
+        "p",
+        3,
+        1,
+        &OpeningOptions {
+            start_positions: vec![midgame, Position::initial()],
+            ..OpeningOptions::fixed(2)
+        },
+        player_factories,
+        array::from_fn(|_| None),
+        Scoring::STANDARD,
+        &mut rng,
+    );
+    let starts = [midgame, Position::initial(), midgame].map(|position| position.to_fen());
+    for game in &match_result.games {
+        assert_eq!(game.start, starts[game.round]);
+        let mut position = Position::from_fen(&game.start).unwrap();
+        for mov in game.opening.split(' ') {
+            position = position
+                .make_any_move(AnyMove::from_str(mov).unwrap())
+                .unwrap();
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use std::str::Chars;
+use std::{iter, str::Chars};
 
 /// 2-byte, 11-bit character (special << 4) + x encodes sequence SPECIAL_MAP[special], x
 pub static SPECIAL_MAP: [Option<u8>; 16] = [
@@ -165,6 +165,23 @@ impl<'a> Base128Decoder<'a> {
         }
     }
 
+    /// Whether only the end-of-stream marker remains.
+    pub fn is_end(&self) -> bool {
+        // The marker is a 1 bit followed by at most 13 bits of zero padding, whereas any
+        // remaining data would put the marker's 1 bit higher.
+        let mut num_bits = self.num_buffered_bits;
+        let mut bits = self.buffered_bits;
+        for c in self.input.clone() {
+            if num_bits > 14 {
+                return false;
+            }
+            let (k, b) = Self::decode_char(c);
+            bits |= u64::from(b) << num_bits;
+            num_bits += k;
+        }
+        bits == 1
+    }
+
     /// Panics if the stream is not finished properly.
     pub fn finish(mut self) {
         if self.decode_bits(1) != 1 || self.buffered_bits != 0 || self.input.next().is_some() {
@@ -189,3 +206,38 @@ impl<'a> Base128Decoder<'a> {
         }
     }
 }
+
+/// Decodes all varints in a string produced by `Base128Encoder::encode_varint`
+/// followed by `Base128Encoder::finish`.
+///
+/// Panics if the string is not valid base128.
+pub fn decode_varints(s: &str) -> impl Iterator<Item = i32> + '_ {
+    let mut decoder = Some(Base128Decoder::new(s));
+    iter::from_fn(move || {
+        let d = decoder.as_mut()?;
+        if d.is_end() {
+            decoder.take().unwrap().finish();
+            None
+        } else {
+            Some(d.decode_varint())
+        }
+    })
+}
+
+/// Decodes all `n`-bit values in a string produced by `Base128Encoder::encode_bits`
+/// followed by `Base128Encoder::finish`.
+///
+/// Panics if the string is not valid base128.
+pub fn decode_bits(s: &str, n: u32) -> impl Iterator<Item = u32> + '_ {
+    assert!((1..=32).contains(&n));
+    let mut decoder = Some(Base128Decoder::new(s));
+    iter::from_fn(move || {
+        let d = decoder.as_mut()?;
+        if d.is_end() {
+            decoder.take().unwrap().finish();
+            None
+        } else {
+            Some(d.decode_bits(n))
+        }
+    })
+}
@@ -1,4 +1,9 @@
-use crate::{Coord, Square};
+use crate::{
+    enums::SimpleEnumExt,
+    impl_from_str_for_parsable,
+    parser::{self, Parser, ParserExt},
+    Coord, Square,
+};
 use std::{
     fmt::{self, Display, Formatter},
     ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not},
@@ -7,6 +12,8 @@ use std::{
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Bitboard(u64);
 
+const _: () = assert!(Square::COUNT == u64::BITS as usize);
+
 impl Bitboard {
     pub const EMPTY: Self = Self(0);
     pub const ALL: Self = Self(!0);
@@ -15,6 +22,14 @@ impl Bitboard {
         Self(bits)
     }
 
+    pub fn from_squares(squares: impl IntoIterator<Item = Square>) -> Self {
+        let mut bitboard = Self::EMPTY;
+        for square in squares {
+            bitboard.add(square);
+        }
+        bitboard
+    }
+
     pub fn is_empty(self) -> bool {
         self == Self::EMPTY
     }
@@ -60,8 +75,26 @@ impl Bitboard {
             Some(square)
         }
     }
+
+    /// Parses the `Display` format.
+    pub fn parser() -> impl Parser<Output = Self> {
+        parser::exact(b"x")
+            .map(|_| true)
+            .or(parser::exact(b".").map(|_| false))
+            .repeat(Coord::WIDTH..=Coord::WIDTH)
+            .then_ignore(parser::endl())
+            .repeat(Coord::HEIGHT..=Coord::HEIGHT)
+            .map(|rows| {
+                Self::from_squares(Square::all().filter(|&square| {
+                    let coord = Coord::from(square);
+                    rows[coord.y()][coord.x()]
+                }))
+            })
+    }
 }
 
+impl_from_str_for_parsable!(Bitboard);
+
 impl BitAnd for Bitboard {
     type Output = Self;
 
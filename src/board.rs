@@ -2,10 +2,13 @@ use crate::{
     enums::{EnumMap, SimpleEnumExt},
     error::Invalid,
     impl_from_str_for_parsable,
-    parser::{self, Parser, ParserExt},
+    parser::{self, ParseError, Parser, ParserExt},
     zobrist, Bitboard, Color, ColoredPiece, Coord, Piece, Square,
 };
-use std::fmt::{self, Display, Formatter};
+use std::{
+    fmt::{self, Display, Formatter},
+    iter,
+};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Board {
@@ -53,28 +56,60 @@ impl Board {
     }
 
     pub fn place_piece(&mut self, square: Square, cpiece: ColoredPiece) -> Result<(), Invalid> {
-        let s = &mut self.squares[square];
-        if s.is_some() {
+        if self.squares[square].is_some() {
             return Err(Invalid);
         }
+        self.place_piece_unchecked(square, cpiece);
+        Ok(())
+    }
+
+    /// `place_piece` on a square known to be empty.
+    pub(crate) fn place_piece_unchecked(&mut self, square: Square, cpiece: ColoredPiece) {
+        let s = &mut self.squares[square];
+        debug_assert!(s.is_none());
         *s = Some(cpiece);
         self.occupied_by[cpiece.color()].add(square);
         self.empty_squares.remove(square);
         self.occupied_by_piece[cpiece].add(square);
-        self.hash ^= zobrist::COLORED_PIECE_SQUARE[cpiece][square];
-        Ok(())
+        self.hash ^= zobrist::colored_piece_square(cpiece, square);
     }
 
     pub fn remove_piece(&mut self, square: Square, cpiece: ColoredPiece) -> Result<(), Invalid> {
-        let s = &mut self.squares[square];
-        if *s != Some(cpiece) {
+        if self.squares[square] != Some(cpiece) {
             return Err(Invalid);
         }
+        self.remove_piece_unchecked(square, cpiece);
+        Ok(())
+    }
+
+    /// `remove_piece` of a piece known to be on the square.
+    pub(crate) fn remove_piece_unchecked(&mut self, square: Square, cpiece: ColoredPiece) {
+        let s = &mut self.squares[square];
+        debug_assert!(*s == Some(cpiece));
         *s = None;
         self.occupied_by[cpiece.color()].remove(square);
         self.empty_squares.add(square);
         self.occupied_by_piece[cpiece].remove(square);
-        self.hash ^= zobrist::COLORED_PIECE_SQUARE[cpiece][square];
+        self.hash ^= zobrist::colored_piece_square(cpiece, square);
+    }
+
+    /// Verifies that the bitboards and hash match the pieces on the squares.
+    pub fn validate(&self) -> Result<(), Invalid> {
+        let mut fresh = Board::empty();
+        for square in Square::all() {
+            if let Some(cpiece) = self.squares[square] {
+                fresh.place_piece_unchecked(square, cpiece);
+            }
+        }
+        let consistent = Color::all()
+            .all(|color| fresh.occupied_by[color] == self.occupied_by[color])
+            && fresh.empty_squares == self.empty_squares
+            && ColoredPiece::all()
+                .all(|cpiece| fresh.occupied_by_piece[cpiece] == self.occupied_by_piece[cpiece])
+            && fresh.hash == self.hash;
+        if !consistent {
+            return Err(Invalid);
+        }
         Ok(())
     }
 
@@ -97,6 +132,74 @@ impl Board {
                 board
             })
     }
+
+    /// Rows separated by `/`, with runs of empty squares as digits, e.g. `2W5/...`.
+    pub fn fen_parser() -> impl Parser<Output = Self> {
+        fn row() -> impl Parser<Output = Vec<Option<ColoredPiece>>> {
+            ColoredPiece::parser()
+                .map(|cpiece| vec![Some(cpiece)])
+                .or(parser::byte().try_map(|b| match b {
+                    b'1'..=b'9' if usize::from(b - b'0') <= Coord::WIDTH => {
+                        Ok(vec![None; usize::from(b - b'0')])
+                    }
+                    _ => Err(ParseError),
+                }))
+                .repeat(1..=Coord::WIDTH)
+                .try_map(|runs| {
+                    let row: Vec<_> = runs.into_iter().flatten().collect();
+                    if row.len() == Coord::WIDTH {
+                        Ok(row)
+                    } else {
+                        Err(ParseError)
+                    }
+                })
+        }
+
+        row()
+            .and(
+                parser::exact(b"/")
+                    .ignore_then(row())
+                    .repeat(Coord::HEIGHT - 1..=Coord::HEIGHT - 1),
+            )
+            .map(|(first, rest)| {
+                let mut board = Board::empty();
+                for (y, row) in iter::once(first).chain(rest).enumerate() {
+                    for (x, cpiece) in row.into_iter().enumerate() {
+                        if let Some(cpiece) = cpiece {
+                            board.place_piece_unchecked(Coord::new(x, y).into(), cpiece);
+                        }
+                    }
+                }
+                board
+            })
+    }
+
+    /// The format read by `fen_parser`.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for y in 0..Coord::HEIGHT {
+            if y != 0 {
+                fen.push('/');
+            }
+            let mut empty = 0;
+            for x in 0..Coord::WIDTH {
+                match self.square(Coord::new(x, y).into()) {
+                    None => empty += 1,
+                    Some(cpiece) => {
+                        if empty != 0 {
+                            fen += &empty.to_string();
+                            empty = 0;
+                        }
+                        fen += &cpiece.to_string();
+                    }
+                }
+            }
+            if empty != 0 {
+                fen += &empty.to_string();
+            }
+        }
+        fen
+    }
 }
 
 impl_from_str_for_parsable!(Board);
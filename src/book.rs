@@ -1,11 +1,31 @@
-use std::time::{Duration, SystemTime};
-
 use crate::{
     base128::{Base128Decoder, Base128Encoder},
+    error::Invalid,
+    impl_from_str_for_parsable, log,
+    parser::{self, ParseError, Parser, ParserExt},
+    Color, Piece, SetupMove, Symmetry,
+};
+#[cfg(feature = "book")]
+use crate::{
     book_data,
     constants::{RED_SETUP_INDEX_BEGIN, RED_SETUP_INDEX_END},
-    log, Color, Piece, SetupMove, Symmetry,
 };
+#[cfg(not(feature = "book"))]
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// Red setup played without the `book` feature.
+#[cfg(not(feature = "book"))]
+const DEFAULT_RED_SETUP: &str = "AWAAAAAADFFDDDAN";
+
+/// Blue setups searched without the `book` feature.
+#[cfg(not(feature = "book"))]
+const DEFAULT_BLUE_SETUPS: [&str; 4] = [
+    "addadffdnaaaaawa",
+    "dffdddaaawaaaaan",
+    "dfddfdaaawaaaaan",
+    "dffdadaadwnaaaaa",
+];
 
 pub fn encode_setup_move(encoder: &mut Base128Encoder, setup_move: SetupMove) {
     for &piece in &setup_move.pieces {
@@ -45,6 +65,7 @@ pub fn decode_piece(decoder: &mut Base128Decoder) -> Piece {
     }
 }
 
+#[cfg(feature = "book")]
 pub fn red_setup() -> SetupMove {
     let r = time_based_random(2 * (RED_SETUP_INDEX_END - RED_SETUP_INDEX_BEGIN));
     let setup_idx = r / 2 + RED_SETUP_INDEX_BEGIN;
@@ -72,6 +93,7 @@ fn time_based_random(n: usize) -> usize {
     x as usize
 }
 
+#[cfg(feature = "book")]
 pub fn blue_setup(red: SetupMove) -> Option<SetupMove> {
     let (symmetry, red) = Symmetry::normalize_red_setup(red);
     for book_opening in BookIterator::new() {
@@ -84,6 +106,7 @@ pub fn blue_setup(red: SetupMove) -> Option<SetupMove> {
     None
 }
 
+#[cfg(feature = "book")]
 pub fn blue_setup_moves() -> Vec<SetupMove> {
     BookIterator::new()
         .flat_map(|book_opening| {
@@ -95,17 +118,31 @@ pub fn blue_setup_moves() -> Vec<SetupMove> {
         .collect()
 }
 
+/// All book openings as (red, blue) setup pairs.
+///
+/// Red setups are normalized with `Symmetry::normalize_red_setup`. Applying the same symmetry to
+/// both moves gives an equivalent opening.
+#[cfg(feature = "book")]
+pub fn openings() -> Vec<(SetupMove, SetupMove)> {
+    BookIterator::new()
+        .map(|book_opening| (book_opening.red, book_opening.blue))
+        .collect()
+}
+
+#[cfg(feature = "book")]
 struct BookOpening {
     index: usize,
     red: SetupMove,
     blue: SetupMove,
 }
 
+#[cfg(feature = "book")]
 struct BookIterator {
     next_index: usize,
     decoder: Option<Base128Decoder<'static>>,
 }
 
+#[cfg(feature = "book")]
 impl BookIterator {
     fn new() -> Self {
         Self {
@@ -115,6 +152,7 @@ impl BookIterator {
     }
 }
 
+#[cfg(feature = "book")]
 impl Iterator for BookIterator {
     type Item = BookOpening;
 
@@ -137,3 +175,112 @@ impl Iterator for BookIterator {
         Some(opening)
     }
 }
+
+#[cfg(not(feature = "book"))]
+pub fn red_setup() -> SetupMove {
+    log::info!("default red setup");
+    SetupMove::from_str(DEFAULT_RED_SETUP).unwrap()
+}
+
+#[cfg(not(feature = "book"))]
+pub fn blue_setup(_red: SetupMove) -> Option<SetupMove> {
+    None
+}
+
+#[cfg(not(feature = "book"))]
+pub fn blue_setup_moves() -> Vec<SetupMove> {
+    DEFAULT_BLUE_SETUPS
+        .iter()
+        .flat_map(|setup| {
+            let mov = SetupMove::from_str(setup).unwrap();
+            [Symmetry::Identity, Symmetry::FlipX]
+                .iter()
+                .map(move |symmetry| symmetry.apply_to_setup(mov))
+        })
+        .collect()
+}
+
+/// An opening book loaded at runtime, used instead of the compiled one.
+///
+/// The text format has one opening per line: the red setup followed by the blue setup, e.g.
+/// `AWAAAAAADFFDDDAN addadffdnaaaaawa`.
+#[derive(Debug, Clone)]
+pub struct OpeningBook {
+    /// Red setups are normalized with `Symmetry::normalize_red_setup`.
+    openings: Vec<(SetupMove, SetupMove)>,
+}
+
+impl OpeningBook {
+    /// Fails if a setup has the wrong color or pieces.
+    pub fn new(openings: &[(SetupMove, SetupMove)]) -> Result<Self, Invalid> {
+        let openings = openings
+            .iter()
+            .map(|&(red, blue)| {
+                if red.color != Color::Red
+                    || blue.color != Color::Blue
+                    || red.validate_pieces().is_err()
+                    || blue.validate_pieces().is_err()
+                {
+                    return Err(Invalid);
+                }
+                let (symmetry, red) = Symmetry::normalize_red_setup(red);
+                Ok((red, symmetry.apply_to_setup(blue)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { openings })
+    }
+
+    pub fn parser() -> impl Parser<Output = Self> {
+        SetupMove::parser()
+            .then_ignore(parser::exact(b" "))
+            .and(SetupMove::parser())
+            .then_ignore(parser::endl())
+            .repeat(..)
+            .try_map(|openings| Self::new(&openings).map_err(|_| ParseError))
+    }
+
+    pub fn len(&self) -> usize {
+        self.openings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.openings.is_empty()
+    }
+
+    /// A random book opening for red, with a random symmetry. `None` if the book is empty.
+    pub fn red_setup(&self) -> Option<SetupMove> {
+        if self.openings.is_empty() {
+            return None;
+        }
+        let r = time_based_random(2 * self.openings.len());
+        let setup_idx = r / 2;
+        let symmetry = [Symmetry::Identity, Symmetry::FlipX][r % 2];
+        log::info!("red setup #{setup_idx} {symmetry} (loaded book)");
+        Some(symmetry.apply_to_setup(self.openings[setup_idx].0))
+    }
+
+    /// The book reply to `red`, if it is in the book.
+    pub fn blue_setup(&self, red: SetupMove) -> Option<SetupMove> {
+        let (symmetry, red) = Symmetry::normalize_red_setup(red);
+        let index = self
+            .openings
+            .iter()
+            .position(|&(book_red, _)| book_red == red)?;
+        log::info!("blue setup #{index} (loaded book)");
+        Some(symmetry.inverse().apply_to_setup(self.openings[index].1))
+    }
+
+    /// All blue setups in the book, in both symmetries.
+    pub fn blue_setup_moves(&self) -> Vec<SetupMove> {
+        self.openings
+            .iter()
+            .flat_map(|&(_, blue)| {
+                [Symmetry::Identity, Symmetry::FlipX]
+                    .iter()
+                    .map(move |symmetry| symmetry.apply_to_setup(blue))
+            })
+            .collect()
+    }
+}
+
+impl_from_str_for_parsable!(OpeningBook);
@@ -24,23 +24,32 @@ impl CapturedOneSide {
     }
 
     pub fn add(&mut self, piece: Piece) -> Result<(), Invalid> {
-        let c = &mut self.counts[piece];
-        let count = usize::from(*c);
-        if count >= piece.total_count() {
+        if self.get(piece) >= piece.total_count() {
             return Err(Invalid);
         }
-        *c += 1;
+        self.add_unchecked(piece);
         Ok(())
     }
 
+    /// `add` when fewer than `piece.total_count()` are captured.
+    pub(crate) fn add_unchecked(&mut self, piece: Piece) {
+        debug_assert!(self.get(piece) < piece.total_count());
+        self.counts[piece] += 1;
+    }
+
     pub fn remove(&mut self, piece: Piece) -> Result<(), Invalid> {
-        let c = &mut self.counts[piece];
-        if *c == 0 {
+        if self.get(piece) == 0 {
             return Err(Invalid);
         }
-        *c -= 1;
+        self.remove_unchecked(piece);
         Ok(())
     }
+
+    /// `remove` when at least one is captured.
+    pub(crate) fn remove_unchecked(&mut self, piece: Piece) {
+        debug_assert!(self.get(piece) != 0);
+        self.counts[piece] -= 1;
+    }
 }
 
 impl Default for CapturedOneSide {
@@ -80,6 +89,14 @@ impl Captured {
         Ok(())
     }
 
+    /// `add` when fewer than `total_count` are captured.
+    pub(crate) fn add_unchecked(&mut self, cpiece: ColoredPiece) {
+        let color = cpiece.color();
+        let piece = cpiece.piece();
+        self.sides[color].add_unchecked(piece);
+        self.hash ^= zobrist::captured(cpiece, self.sides[color].get(piece) - 1);
+    }
+
     pub fn remove(&mut self, cpiece: ColoredPiece) -> Result<(), Invalid> {
         let color = cpiece.color();
         let piece = cpiece.piece();
@@ -88,6 +105,28 @@ impl Captured {
         Ok(())
     }
 
+    /// `remove` when at least one is captured.
+    pub(crate) fn remove_unchecked(&mut self, cpiece: ColoredPiece) {
+        let color = cpiece.color();
+        let piece = cpiece.piece();
+        self.sides[color].remove_unchecked(piece);
+        self.hash ^= zobrist::captured(cpiece, self.sides[color].get(piece));
+    }
+
+    /// Verifies the counts and that the hash matches them.
+    pub fn validate(&self) -> Result<(), Invalid> {
+        let mut fresh = Captured::new();
+        for cpiece in ColoredPiece::all() {
+            for _ in 0..self.get(cpiece) {
+                fresh.add(cpiece)?;
+            }
+        }
+        if fresh.hash != self.hash {
+            return Err(Invalid);
+        }
+        Ok(())
+    }
+
     pub fn parser() -> impl Parser<Output = Self> {
         ColoredPiece::parser()
             .repeat(0..=Color::COUNT * SetupMove::SIZE)
@@ -1,15 +1,18 @@
 use crate::{
-    clock::{Stopwatch, Timer},
+    clock::{Stopwatch, TimeControl, Timer},
     constants::DEFAULT_TIME_LIMIT,
     impl_from_str_for_parsable,
-    log::{self, Level},
+    log::{self, Verbosity},
     movegen,
     parser::{self, Parser, ParserExt},
-    platform, AnyMove, Color, PlayerFactory, Position, ShortMove,
+    platform, AnyMove, PlayerFactory, Position, ShortMove,
 };
 use std::{
+    env,
     fmt::{self, Display, Formatter},
-    io::{self, BufRead, Write},
+    fs::{self, File},
+    io::{self, BufRead, BufWriter, Cursor, Write},
+    path::PathBuf,
     process::ExitCode,
     time::Duration,
 };
@@ -17,27 +20,52 @@ use std::{
 #[derive(Debug, Clone)]
 pub enum CliCommand {
     TimeLimit(Duration),
+    /// The most time to spend on any one move, on top of the `TimeLimit` clock.
+    MoveCap(Duration),
+    /// Starts the game from this position rather than the initial one. Sent as
+    /// `Position::to_fen`.
+    Position(Position),
     Opening(Vec<AnyMove>),
     Start,
     OpponentMove(ShortMove),
+    /// Asks for `SearchInfo` about the last move made.
+    LastInfo,
+    /// Asks which evaluator and weights play, as `PlayerFactory::version`.
+    Version,
+    /// Whether to think on the opponent's time. Off by default.
+    Ponder(bool),
     Quit,
 }
 
 impl CliCommand {
     pub fn parser() -> impl Parser<Output = Self> {
-        parser::exact(b"Time ")
-            .ignore_then(parser::u32())
-            .map(|ms| CliCommand::TimeLimit(Duration::from_millis(ms.into())))
-            .or(parser::exact(b"Opening")
+        parser::any_of![
+            parser::exact(b"Time ")
+                .ignore_then(parser::u32())
+                .map(|ms| CliCommand::TimeLimit(Duration::from_millis(ms.into()))),
+            parser::exact(b"MoveCap ")
+                .ignore_then(parser::u32())
+                .map(|ms| CliCommand::MoveCap(Duration::from_millis(ms.into()))),
+            parser::exact(b"Position ")
+                .ignore_then(Position::fen_parser())
+                .map(CliCommand::Position),
+            parser::exact(b"Opening")
                 .ignore_then(
                     parser::exact(b" ")
                         .ignore_then(AnyMove::parser())
                         .repeat(0..),
                 )
-                .map(CliCommand::Opening))
-            .or(parser::exact(b"Start").map(|_| CliCommand::Start))
-            .or(parser::exact(b"Quit").map(|_| CliCommand::Quit))
-            .or(ShortMove::parser().map(CliCommand::OpponentMove))
+                .map(CliCommand::Opening),
+            parser::exact(b"Start").map(|_| CliCommand::Start),
+            parser::exact(b"LastInfo").map(|_| CliCommand::LastInfo),
+            parser::exact(b"Version").map(|_| CliCommand::Version),
+            parser::exact(b"Ponder ").ignore_then(parser::any_of![
+                parser::exact(b"on").map(|_| CliCommand::Ponder(true)),
+                parser::exact(b"off").map(|_| CliCommand::Ponder(false)),
+            ]),
+            parser::exact(b"Quit").map(|_| CliCommand::Quit),
+            ShortMove::parser().map(CliCommand::OpponentMove),
+        ]
     }
 }
 
@@ -47,6 +75,8 @@ impl Display for CliCommand {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             CliCommand::TimeLimit(duration) => write!(f, "Time {}", duration.as_millis())?,
+            CliCommand::MoveCap(duration) => write!(f, "MoveCap {}", duration.as_millis())?,
+            CliCommand::Position(position) => write!(f, "Position {}", position.to_fen())?,
             CliCommand::Opening(moves) => {
                 write!(f, "Opening")?;
                 for mov in moves {
@@ -55,6 +85,10 @@ impl Display for CliCommand {
             }
             CliCommand::Start => write!(f, "Start")?,
             CliCommand::OpponentMove(mov) => write!(f, "{mov}")?,
+            CliCommand::LastInfo => write!(f, "LastInfo")?,
+            CliCommand::Version => write!(f, "Version")?,
+            CliCommand::Ponder(true) => write!(f, "Ponder on")?,
+            CliCommand::Ponder(false) => write!(f, "Ponder off")?,
             CliCommand::Quit => write!(f, "Quit")?,
         }
         Ok(())
@@ -66,11 +100,14 @@ enum CliError {
     IoError(io::Error),
     InvalidCommand(Vec<u8>),
     TimeCommandTooLate,
+    MoveCapCommandTooLate,
+    PositionCommandTooLate,
     OpeningCommandTooLate,
     StartCommandTooLate,
     InvalidOpeningMove(AnyMove),
     InvalidPlayerMove(AnyMove),
     InvalidOpponentMove(ShortMove),
+    MissingArgumentValue(&'static str),
 }
 
 impl Display for CliError {
@@ -81,6 +118,8 @@ impl Display for CliError {
                 write!(f, "Invalid command: {}", String::from_utf8_lossy(bytes))
             }
             CliError::TimeCommandTooLate => write!(f, "Time command too late"),
+            CliError::MoveCapCommandTooLate => write!(f, "MoveCap command too late"),
+            CliError::PositionCommandTooLate => write!(f, "Position command too late"),
             CliError::OpeningCommandTooLate => write!(f, "Opening command too late"),
             CliError::StartCommandTooLate => write!(f, "Start command too late"),
             CliError::InvalidOpeningMove(mov) => write!(f, "Invalid opening move: {mov}"),
@@ -88,6 +127,7 @@ impl Display for CliError {
             CliError::InvalidOpponentMove(short_move) => {
                 write!(f, "Invalid opponent move: {short_move}")
             }
+            CliError::MissingArgumentValue(arg) => write!(f, "Missing value for {arg}"),
         }
     }
 }
@@ -107,42 +147,177 @@ pub fn run_cli(player_factory: &dyn PlayerFactory) -> ExitCode {
     ExitCode::SUCCESS
 }
 
+/// Command line options other than verbosity.
+#[derive(Debug, Default)]
+struct CliOptions {
+    /// Record the session to this file.
+    record: Option<PathBuf>,
+    /// Read commands from a recorded session instead of stdin.
+    replay: Option<PathBuf>,
+}
+
+impl CliOptions {
+    /// Looks for `--record <file>` and `--replay <file>` among `args`, ignoring other arguments.
+    fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (name, value) = match arg.as_str() {
+                "--record" => ("--record", &mut options.record),
+                "--replay" => ("--replay", &mut options.replay),
+                _ => continue,
+            };
+            *value = Some(
+                args.next()
+                    .ok_or(CliError::MissingArgumentValue(name))?
+                    .into(),
+            );
+        }
+        Ok(options)
+    }
+}
+
+/// The controller connection: commands in, moves and info out.
+///
+/// When recording, every command received is written as `< command` and every line sent as
+/// `> line`. Replaying feeds the `<` lines back in as commands.
+struct Session {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    record: Option<BufWriter<File>>,
+}
+
+impl Session {
+    fn new(options: &CliOptions) -> Result<Self, CliError> {
+        let input: Box<dyn BufRead> = match &options.replay {
+            Some(path) => {
+                log::info!("replay {}", path.display());
+                Box::new(Cursor::new(replay_commands(&fs::read_to_string(path)?)))
+            }
+            None => Box::new(io::stdin().lock()),
+        };
+        let record = match &options.record {
+            Some(path) => {
+                log::info!("record {}", path.display());
+                Some(BufWriter::new(File::create(path)?))
+            }
+            None => None,
+        };
+        Ok(Self {
+            input,
+            output: Box::new(io::stdout().lock()),
+            record,
+        })
+    }
+
+    /// Returns 0 at end of input.
+    fn read_command(&mut self, buffer: &mut Vec<u8>) -> io::Result<usize> {
+        let len = self.input.read_until(b'\n', buffer)?;
+        if let Some(record) = &mut self.record {
+            if len != 0 {
+                let command = buffer.strip_suffix(b"\n").unwrap_or(buffer);
+                let command = command.strip_suffix(b"\r").unwrap_or(command);
+                record.write_all(b"< ")?;
+                record.write_all(command)?;
+                record.write_all(b"\n")?;
+                record.flush()?;
+            }
+        }
+        Ok(len)
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.output, "{line}")?;
+        self.output.flush()?;
+        if let Some(record) = &mut self.record {
+            writeln!(record, "> {line}")?;
+            record.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// The commands from a recorded session, one per line.
+fn replay_commands(recording: &str) -> Vec<u8> {
+    let mut commands = Vec::new();
+    for line in recording.lines() {
+        if let Some(command) = line.strip_prefix("< ") {
+            commands.extend_from_slice(command.as_bytes());
+            commands.push(b'\n');
+        }
+    }
+    commands
+}
+
+/// `time_control` with `move_cap` applied. A move cap without a `TimeLimit` caps the default time
+/// limit.
+fn with_move_cap(
+    time_control: Option<TimeControl>,
+    move_cap: Option<Duration>,
+) -> Option<TimeControl> {
+    match move_cap {
+        None => time_control,
+        Some(move_cap) => Some(TimeControl {
+            move_cap: Some(move_cap),
+            ..time_control.unwrap_or(TimeControl::fixed(DEFAULT_TIME_LIMIT))
+        }),
+    }
+}
+
 fn run_internal(player_factory: &dyn PlayerFactory) -> Result<(), CliError> {
-    log::init(Level::Info);
+    log::init(Verbosity::from_args(env::args().skip(1)).level());
     log::info!("Platform: {}", platform::platform_description());
-    let mut stdin = io::stdin().lock();
-    let mut stdout = io::stdout().lock();
+    let mut session = Session::new(&CliOptions::from_args(env::args().skip(1))?)?;
 
+    let mut start = Position::initial();
     let mut opening = Vec::new();
-    let mut position = Position::initial();
-    let mut time_limit = None;
+    let mut position = start;
+    let mut time_control = None;
+    let mut move_cap = None;
     let mut timer = Timer::new(DEFAULT_TIME_LIMIT);
     let mut player = None;
     let mut command_buffer = Vec::new();
     let mut opp_stopwatch: Option<Stopwatch> = None;
+    let mut last_info = None;
+    let mut ponder = false;
 
     loop {
         log::flush();
         command_buffer.clear();
-        let command_len = stdin.read_until(b'\n', &mut command_buffer)?;
+        let command_len = session.read_command(&mut command_buffer)?;
         if command_len == 0 {
             log::info!("EOF");
             break;
         }
         let command = CliCommand::parser()
-            .then_ignore(parser::endl())
+            .then_ignore(parser::endl_or_end())
             .parse_all(&command_buffer)
             .map_err(|_| CliError::InvalidCommand(command_buffer.clone()))?;
 
         match command {
             CliCommand::TimeLimit(duration) => {
-                if player.is_some() || time_limit.is_some() {
+                if player.is_some() || time_control.is_some() {
                     return Err(CliError::TimeCommandTooLate);
                 }
                 log::info!("time limit {t}", t = duration.as_millis());
-                time_limit = Some(duration);
+                time_control = Some(TimeControl::fixed(duration));
                 timer = Timer::new(duration);
             }
+            CliCommand::MoveCap(duration) => {
+                if player.is_some() || move_cap.is_some() {
+                    return Err(CliError::MoveCapCommandTooLate);
+                }
+                log::info!("move cap {t}", t = duration.as_millis());
+                move_cap = Some(duration);
+            }
+            CliCommand::Position(new_start) => {
+                if player.is_some() || !opening.is_empty() || start.ply() != 0 {
+                    return Err(CliError::PositionCommandTooLate);
+                }
+                log::info!("{}", CliCommand::Position(new_start));
+                start = new_start;
+                position = start;
+            }
             CliCommand::Opening(moves) => {
                 if player.is_some() || !opening.is_empty() {
                     return Err(CliError::OpeningCommandTooLate);
@@ -160,7 +335,13 @@ fn run_internal(player_factory: &dyn PlayerFactory) -> Result<(), CliError> {
                     return Err(CliError::StartCommandTooLate);
                 }
                 timer.start();
-                player = Some(player_factory.create("", Color::Red, &opening, time_limit));
+                player = Some(player_factory.create(
+                    "",
+                    position.to_move(),
+                    &start,
+                    &opening,
+                    with_move_cap(time_control, move_cap),
+                ));
                 log::info!("init {} ms", timer.get().as_millis());
             }
             CliCommand::OpponentMove(short_move) => {
@@ -180,7 +361,13 @@ fn run_internal(player_factory: &dyn PlayerFactory) -> Result<(), CliError> {
                 );
 
                 if player.is_none() {
-                    player = Some(player_factory.create("", Color::Blue, &opening, time_limit));
+                    player = Some(player_factory.create(
+                        "",
+                        position.to_move().opposite(),
+                        &start,
+                        &opening,
+                        with_move_cap(time_control, move_cap),
+                    ));
                     log::info!("init {t} ms", t = timer.get().as_millis());
                 }
 
@@ -190,6 +377,34 @@ fn run_internal(player_factory: &dyn PlayerFactory) -> Result<(), CliError> {
                     .opponent_move(&position, mov, &timer);
                 position = position.make_any_move(mov).unwrap();
             }
+            CliCommand::LastInfo => {
+                // Responds with "Info" followed by `SearchInfo` if available.
+                match &last_info {
+                    Some(info) => session.send(&format!("Info {info}"))?,
+                    None => session.send("Info")?,
+                }
+                continue;
+            }
+            CliCommand::Version => {
+                // Responds with "Version" followed by the version if there is one.
+                let version = player_factory.version();
+                if version.is_empty() {
+                    session.send("Version")?;
+                } else {
+                    session.send(&format!("Version {version}"))?;
+                }
+                continue;
+            }
+            CliCommand::Ponder(on) => {
+                log::info!("ponder {}", if on { "on" } else { "off" });
+                ponder = on;
+                if !ponder {
+                    if let Some(player) = player.as_mut() {
+                        player.stop_ponder();
+                    }
+                }
+                continue;
+            }
             CliCommand::Quit => {
                 log::info!("quit");
                 break;
@@ -201,6 +416,7 @@ fn run_internal(player_factory: &dyn PlayerFactory) -> Result<(), CliError> {
         };
 
         let mov = player.make_move(&position, &timer);
+        last_info = player.last_search_info();
         let short_move = ShortMove::from(mov);
         position = position
             .make_any_move(mov)
@@ -218,8 +434,10 @@ fn run_internal(player_factory: &dyn PlayerFactory) -> Result<(), CliError> {
         opp_stopwatch.as_mut().unwrap().start();
 
         log::flush();
-        writeln!(stdout, "{short_move}")?;
-        stdout.flush()?;
+        session.send(&short_move.to_string())?;
+        if ponder {
+            player.start_ponder(&position);
+        }
     }
     log::flush();
     Ok(())
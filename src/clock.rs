@@ -70,4 +70,31 @@ impl Timer {
     pub fn instant_at(&self, t: Duration) -> Instant {
         self.stopwatch.instant_at(self.initial.saturating_sub(t))
     }
+
+    /// Adds `t` to the remaining time, e.g. an increment after a move.
+    pub fn add(&mut self, t: Duration) {
+        self.initial += t;
+    }
+}
+
+/// Time control for one player.
+///
+/// The clock starts at `base` and gains `increment` after every move. If `move_cap` is set, a
+/// single move may not take longer than that, whatever is left on the clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+    pub move_cap: Option<Duration>,
+}
+
+impl TimeControl {
+    /// A single time limit for the whole game.
+    pub fn fixed(base: Duration) -> Self {
+        Self {
+            base,
+            increment: Duration::ZERO,
+            move_cap: None,
+        }
+    }
 }
@@ -1,10 +1,11 @@
+#[cfg(feature = "book")]
 use crate::book_data;
+use crate::{search::SEARCH_OVERHEAD_BYTES, EvalCache};
 use std::time::Duration;
 
 pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(30);
 pub const TIME_MARGIN: Duration = Duration::from_millis(500);
 pub const MAX_VARIATION_LENGTH: usize = 100;
-pub const CHECK_TIMEOUT_NODES: u64 = 256;
 
 pub type Ply = u8;
 pub const PLY_AFTER_SETUP: Ply = 2;
@@ -18,7 +19,10 @@ pub const DEPTH_INCREMENT: Depth = ONE_PLY;
 
 pub type Eval = i32;
 
+/// Default for the `KILLERS` parameter of `Search`.
 pub const NUM_KILLER_MOVES: usize = 2;
+/// Upper bound for the `KILLERS` parameter of `Search`.
+pub const MAX_KILLER_MOVES: usize = 4;
 
 // 16 KB, 2 hashes: collision probability per hash is 1 / 80, total 1 / 6400
 pub const HISTORY_BLOOM_FILTER_LOG_SIZE: u32 = 14;
@@ -27,17 +31,46 @@ pub const HISTORY_BLOOM_FILTER_NUM_HASHES: usize = 2;
 pub const RED_SETUP_INDEX_BEGIN: usize = 0;
 pub const RED_SETUP_INDEX_END: usize = 1;
 
+#[cfg(feature = "book")]
 const _ASSERT_RED_SETUP_INDEX_VALID: () = assert!(
     RED_SETUP_INDEX_BEGIN < RED_SETUP_INDEX_END && RED_SETUP_INDEX_END < book_data::NUM_OPENINGS
 );
 
+/// Largest `Hyperparameters::aspiration_window`, far beyond any eval, so that the window can't
+/// overflow.
+pub const MAX_ASPIRATION_WINDOW: f64 = 100.0;
+
+/// Smallest table size used by `Hyperparameters::with_memory_budget`.
+pub const MIN_TABLE_SIZE: usize = 64 << 10;
+
+/// Search and time management parameters.
+///
+/// `contempt`, `null_move_margin`, `futility_margin`, `panic_eval_threshold`,
+/// `random_move_margin` and `aspiration_window` are in units of `Evaluator::scale()`, so they
+/// mean different evals for evaluators with different scales. Use `rescale` to move a tuned set
+/// to another evaluator.
 #[derive(Debug, Clone)]
 pub struct Hyperparameters {
     pub ttable_size: usize,
     pub pvtable_size: usize,
+    /// Size of the cache of leaf evals, in bytes. 0 disables the cache, otherwise at least
+    /// `EvalCache::MIN_SIZE`.
+    ///
+    /// Off by default: on `eval_bench` with NNUE a 4 MB cache answers about a third of the
+    /// lookups, but search is about 7% slower, because the accumulator is updated on every move
+    /// anyway and a hit only saves the output layers.
+    pub eval_cache_size: usize,
+    /// Threads used by `Search::search`, including the calling thread. The others run their own
+    /// iterative deepening on the same position and share the transposition table (Lazy SMP), so
+    /// they only help through the entries they store.
+    pub search_threads: usize,
+    /// In units of `Evaluator::scale()`.
     pub contempt: f64,
     pub min_depth_ttable: Depth,
     pub null_move_reduction: Depth,
+    /// Null move cutoffs at this depth or more are verified by a reduced search without null
+    /// moves, to guard against zugzwang. `None` disables verification.
+    pub null_verify_depth: Option<Depth>,
     pub late_move_reduction_start: usize,
     pub late_move_reduction_start_2: usize,
     pub blue_setup_late_move_reduction_start: usize,
@@ -45,17 +78,48 @@ pub struct Hyperparameters {
     pub iid_min_depth: Depth,
     pub iid_reduction: Depth,
     pub check_extension: Depth,
+    /// Moves from this ply on use `time_reduction_per_late_move`. In a game drawn at `ply_draw`
+    /// rather than `PLY_DRAW`, it moves `PLY_DRAW - ply_draw` plies earlier.
     pub late_ply: Ply,
+    /// Within this many plies of the move limit, evals are scaled linearly toward a draw, so
+    /// that a winning eval doesn't suddenly become a draw at the limit. `None` disables it.
+    ///
+    /// At most `PLY_DRAW - PLY_DRAWISH`: `Position::hash` only includes the ply that close to the
+    /// limit, and the blended evals are stored in the transposition table.
+    pub draw_blend_plies: Option<Ply>,
+    /// In units of `Evaluator::scale()`.
     pub null_move_margin: f64,
+    /// In units of `Evaluator::scale()`.
     pub futility_margin: f64,
+    /// In units of `Evaluator::scale()`. From `aspiration_min_depth` on, the best move of the
+    /// previous depth is first searched with a window this far on each side of its score. The
+    /// window doubles on the failing side for each re-search, and the third try uses a full
+    /// window. `None` always uses a full window. At most `MAX_ASPIRATION_WINDOW`.
+    pub aspiration_window: Option<f64>,
+    /// At least `2 * ONE_PLY`, the first depth after the one-ply search that gives the first
+    /// score.
+    pub aspiration_min_depth: Depth,
     pub time_reduction_per_setup_move: f64,
     pub time_reduction_per_move: f64,
     pub time_reduction_per_late_move: f64,
     pub soft_time_fraction: f64,
     pub start_next_depth_fraction: f64,
+    /// In units of `Evaluator::scale()`.
     pub panic_eval_threshold: f64,
     pub panic_multiplier: f64,
     pub panic_max_remaining: f64,
+    /// How often to check the hard deadline.
+    pub check_timeout_nodes: u64,
+    /// How often to check the hard deadline when little time remains.
+    pub low_latency_check_timeout_nodes: u64,
+    /// Remaining time below which `low_latency_check_timeout_nodes` applies.
+    pub low_latency_time: Duration,
+    /// In units of `Evaluator::scale()`. `MainPlayer` plays a random move among those scoring
+    /// within this margin of the best, for variety between games. `None` always plays the best
+    /// move.
+    pub random_move_margin: Option<f64>,
+    /// Together with the game id, determines the random move choices.
+    pub random_move_seed: u64,
 }
 
 impl Default for Hyperparameters {
@@ -63,9 +127,12 @@ impl Default for Hyperparameters {
         Self {
             ttable_size: 256 << 20,
             pvtable_size: 16 << 20,
+            eval_cache_size: 0,
+            search_threads: 1,
             contempt: 0.1,
             min_depth_ttable: ONE_PLY,
             null_move_reduction: 2 * ONE_PLY,
+            null_verify_depth: None,
             late_move_reduction_start: 5,
             late_move_reduction_start_2: 10,
             blue_setup_late_move_reduction_start: 50,
@@ -74,8 +141,11 @@ impl Default for Hyperparameters {
             iid_reduction: 2 * ONE_PLY,
             check_extension: ONE_PLY,
             late_ply: 96,
+            draw_blend_plies: None,
             null_move_margin: 0.109828,
             futility_margin: 0.608325,
+            aspiration_window: Some(1.0),
+            aspiration_min_depth: 5 * ONE_PLY,
             time_reduction_per_setup_move: 0.8,
             time_reduction_per_move: 0.057433,
             time_reduction_per_late_move: 0.8,
@@ -84,6 +154,188 @@ impl Default for Hyperparameters {
             panic_eval_threshold: 0.038722,
             panic_multiplier: 5.339634,
             panic_max_remaining: 0.402908,
+            check_timeout_nodes: 256,
+            low_latency_check_timeout_nodes: 16,
+            low_latency_time: Duration::from_millis(200),
+            random_move_margin: None,
+            random_move_seed: 0,
+        }
+    }
+}
+
+impl Hyperparameters {
+    /// The same parameters for an evaluator with scale `new_scale` rather than `old_scale`.
+    ///
+    /// The eval-relative parameters keep the same `Eval` values; everything else is unchanged.
+    pub fn rescale(&self, old_scale: f64, new_scale: f64) -> Self {
+        let ratio = old_scale / new_scale;
+        Self {
+            contempt: self.contempt * ratio,
+            null_move_margin: self.null_move_margin * ratio,
+            futility_margin: self.futility_margin * ratio,
+            panic_eval_threshold: self.panic_eval_threshold * ratio,
+            random_move_margin: self.random_move_margin.map(|margin| margin * ratio),
+            aspiration_window: self.aspiration_window.map(|window| window * ratio),
+            ..self.clone()
         }
     }
+
+    /// Default parameters with the tables sized to fit in `total_bytes`. See `with_memory_budget`.
+    pub fn from_memory_budget(total_bytes: usize) -> Self {
+        Self::default().with_memory_budget(total_bytes)
+    }
+
+    /// These parameters with the tables resized to fit in `total_bytes`, as counted by
+    /// `Search::memory_bytes`.
+    ///
+    /// Each of the `search_threads` searches has its own killer moves, history scores, PV table
+    /// and eval cache, and they share the transposition table. The killer moves and history
+    /// scores come out of the budget first. The transposition table and the per-thread PV tables
+    /// and eval caches split the rest in the proportions of their current sizes, so the defaults
+    /// with one thread give the transposition table 16/17 and the PV table 1/17. Each table gets
+    /// at least `MIN_TABLE_SIZE`, except that a disabled eval cache stays disabled. Tables round
+    /// down to a power of two buckets, so `Search::memory_bytes` is usually below the budget.
+    pub fn with_memory_budget(&self, total_bytes: usize) -> Self {
+        let threads = self.search_threads.max(1);
+        let tables_bytes = total_bytes.saturating_sub(threads * SEARCH_OVERHEAD_BYTES);
+        let total_size =
+            (self.ttable_size + threads * (self.pvtable_size + self.eval_cache_size)) as f64;
+        let share = |size: usize| {
+            ((tables_bytes as f64 * size as f64 / total_size) as usize).max(MIN_TABLE_SIZE)
+        };
+        let pvtable_size = share(self.pvtable_size);
+        let eval_cache_size = if self.eval_cache_size == 0 {
+            0
+        } else {
+            share(self.eval_cache_size)
+        };
+        let ttable_size = tables_bytes
+            .saturating_sub(threads * (pvtable_size + eval_cache_size))
+            .max(MIN_TABLE_SIZE);
+        Self {
+            ttable_size,
+            pvtable_size,
+            eval_cache_size,
+            ..self.clone()
+        }
+    }
+
+    /// Checks that the values make sense.
+    pub fn validate(&self) -> Result<(), String> {
+        fn check(condition: bool, message: &str) -> Result<(), String> {
+            if condition {
+                Ok(())
+            } else {
+                Err(message.to_owned())
+            }
+        }
+
+        fn is_fraction(x: f64) -> bool {
+            x > 0.0 && x <= 1.0
+        }
+
+        fn is_non_negative(x: f64) -> bool {
+            x >= 0.0 && x.is_finite()
+        }
+
+        fn is_reduction(x: f64) -> bool {
+            (0.0..1.0).contains(&x)
+        }
+
+        check(self.ttable_size > 0, "ttable_size must be positive")?;
+        check(self.pvtable_size > 0, "pvtable_size must be positive")?;
+        check(
+            self.eval_cache_size == 0 || self.eval_cache_size >= EvalCache::MIN_SIZE,
+            "eval_cache_size must be 0 or at least EvalCache::MIN_SIZE",
+        )?;
+        check(self.search_threads > 0, "search_threads must be positive")?;
+        check(self.contempt.is_finite(), "contempt must be finite")?;
+        check(
+            self.late_move_reduction_start <= self.late_move_reduction_start_2,
+            "late_move_reduction_start must be <= late_move_reduction_start_2",
+        )?;
+        check(
+            self.blue_setup_late_move_reduction_start
+                <= self.blue_setup_late_move_reduction_start_2,
+            "blue_setup_late_move_reduction_start must be <= blue_setup_late_move_reduction_start_2",
+        )?;
+        check(
+            self.iid_reduction <= self.iid_min_depth,
+            "iid_reduction must be <= iid_min_depth",
+        )?;
+        check(
+            self.null_verify_depth
+                .is_none_or(|depth| depth >= ONE_PLY + self.null_move_reduction),
+            "null_verify_depth must be >= ONE_PLY + null_move_reduction",
+        )?;
+        check(self.late_ply <= PLY_DRAW, "late_ply must be <= PLY_DRAW")?;
+        check(
+            self.draw_blend_plies
+                .is_none_or(|plies| plies > 0 && plies <= PLY_DRAW - PLY_DRAWISH),
+            "draw_blend_plies must be in 1..=PLY_DRAW - PLY_DRAWISH",
+        )?;
+        check(
+            is_non_negative(self.null_move_margin),
+            "null_move_margin must be non-negative",
+        )?;
+        check(
+            is_non_negative(self.futility_margin),
+            "futility_margin must be non-negative",
+        )?;
+        check(
+            is_reduction(self.time_reduction_per_setup_move),
+            "time_reduction_per_setup_move must be in [0, 1)",
+        )?;
+        check(
+            is_reduction(self.time_reduction_per_move),
+            "time_reduction_per_move must be in [0, 1)",
+        )?;
+        check(
+            is_reduction(self.time_reduction_per_late_move),
+            "time_reduction_per_late_move must be in [0, 1)",
+        )?;
+        check(
+            is_fraction(self.soft_time_fraction),
+            "soft_time_fraction must be in (0, 1]",
+        )?;
+        check(
+            is_fraction(self.start_next_depth_fraction),
+            "start_next_depth_fraction must be in (0, 1]",
+        )?;
+        check(
+            is_non_negative(self.panic_eval_threshold),
+            "panic_eval_threshold must be non-negative",
+        )?;
+        check(
+            self.panic_multiplier >= 1.0 && self.panic_multiplier.is_finite(),
+            "panic_multiplier must be >= 1",
+        )?;
+        check(
+            is_fraction(self.panic_max_remaining),
+            "panic_max_remaining must be in (0, 1]",
+        )?;
+        check(
+            self.check_timeout_nodes > 0,
+            "check_timeout_nodes must be positive",
+        )?;
+        check(
+            self.low_latency_check_timeout_nodes > 0
+                && self.low_latency_check_timeout_nodes <= self.check_timeout_nodes,
+            "low_latency_check_timeout_nodes must be in 1..=check_timeout_nodes",
+        )?;
+        check(
+            self.random_move_margin.is_none_or(is_non_negative),
+            "random_move_margin must be non-negative",
+        )?;
+        check(
+            self.aspiration_window
+                .is_none_or(|window| window > 0.0 && window <= MAX_ASPIRATION_WINDOW),
+            "aspiration_window must be in (0, MAX_ASPIRATION_WINDOW]",
+        )?;
+        check(
+            (2 * ONE_PLY..=MAX_SEARCH_DEPTH).contains(&self.aspiration_min_depth),
+            "aspiration_min_depth must be in 2 * ONE_PLY..=MAX_SEARCH_DEPTH",
+        )?;
+        Ok(())
+    }
 }
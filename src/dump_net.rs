@@ -0,0 +1,145 @@
+use crate::{
+    nnue::{NnueLayer, WeightStats},
+    Nnue,
+};
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+const NUM_BUCKETS: usize = 16;
+const BAR_WIDTH: usize = 50;
+const SVG_WIDTH: f64 = 1024.0;
+const SVG_HEIGHT: f64 = 384.0;
+const SVG_MARGIN: f64 = 50.0;
+
+/// `wazir-drop dumpnet [--svg <dir>]`: prints the shape and weight statistics of every layer of
+/// the compiled net. With `--svg`, also writes a weight CDF chart per layer into `dir`.
+pub fn run_dump_net(args: impl IntoIterator<Item = String>) -> ExitCode {
+    match dump_net(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("dumpnet: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn dump_net(args: impl IntoIterator<Item = String>) -> Result<(), io::Error> {
+    let mut svg_dir: Option<PathBuf> = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--svg" => {
+                svg_dir = Some(
+                    args.next()
+                        .ok_or_else(|| io::Error::other("--svg requires a directory"))?
+                        .into(),
+                )
+            }
+            _ => return Err(io::Error::other(format!("unknown argument {arg}"))),
+        }
+    }
+
+    let mut out = io::stdout().lock();
+    for layer in Nnue::layers() {
+        out.write_all(format_layer(&layer).as_bytes())?;
+        if let Some(dir) = &svg_dir {
+            let path = dir.join(format!("weights_{}.svg", layer.name));
+            write_cdf_svg(&layer.weights, &path)?;
+            writeln!(out, "written {}", path.display())?;
+        }
+    }
+    Ok(())
+}
+
+/// Shape, statistics and a text histogram of one layer.
+pub fn format_layer(layer: &NnueLayer) -> String {
+    let mut s = String::new();
+    let [rows, columns] = layer.shape;
+    _ = writeln!(
+        s,
+        "{name}: weights {rows}x{columns}, bias {bias}",
+        name = layer.name,
+        bias = layer.bias.len(),
+    );
+    for (what, values) in [("weights", &layer.weights), ("bias", &layer.bias)] {
+        let Some(stats) = WeightStats::new(values, NUM_BUCKETS) else {
+            continue;
+        };
+        _ = writeln!(
+            s,
+            "  {what}: min={min} max={max} mean={mean:.3}",
+            min = stats.min,
+            max = stats.max,
+            mean = stats.mean,
+        );
+        if what == "weights" {
+            let max_count = stats.histogram.iter().copied().max().unwrap_or(0).max(1);
+            for (bucket, &count) in stats.histogram.iter().enumerate() {
+                let bar = "#".repeat((count * BAR_WIDTH).div_ceil(max_count));
+                _ = writeln!(
+                    s,
+                    "    {start:>10.1} {count:>9} {bar}",
+                    start = stats.bucket_start(bucket),
+                );
+            }
+        }
+    }
+    s
+}
+
+/// Same chart as the trainer's weight CDF plot, drawn as a bare SVG polyline.
+fn write_cdf_svg(weights: &[i32], path: &Path) -> Result<(), io::Error> {
+    let mut sorted = weights.to_vec();
+    sorted.sort_unstable();
+    let (Some(&min), Some(&max)) = (sorted.first(), sorted.last()) else {
+        return Err(io::Error::other("no weights"));
+    };
+    let x_range = f64::from(max - min).max(1.0);
+    let plot_width = SVG_WIDTH - 2.0 * SVG_MARGIN;
+    let plot_height = SVG_HEIGHT - 2.0 * SVG_MARGIN;
+    let step_by = (sorted.len() / 10000).max(1);
+    let denominator = (sorted.len() - 1).max(1) as f64;
+    let mut points = String::new();
+    for index in (0..sorted.len()).step_by(step_by) {
+        let x = SVG_MARGIN + f64::from(sorted[index] - min) / x_range * plot_width;
+        let y = SVG_HEIGHT - SVG_MARGIN - index as f64 / denominator * plot_height;
+        _ = write!(points, "{x:.1},{y:.1} ");
+    }
+
+    let mut svg = String::new();
+    _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{SVG_WIDTH}" height="{SVG_HEIGHT}">"#
+    );
+    _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    _ = writeln!(
+        svg,
+        r#"<text x="{x}" y="35" font-family="sans-serif" font-size="30" text-anchor="middle">Weight CDF</text>"#,
+        x = SVG_WIDTH / 2.0,
+    );
+    _ = writeln!(
+        svg,
+        r#"<rect x="{SVG_MARGIN}" y="{SVG_MARGIN}" width="{plot_width}" height="{plot_height}" fill="none" stroke="black"/>"#
+    );
+    for (value, anchor, x) in [
+        (min, "start", SVG_MARGIN),
+        (max, "end", SVG_WIDTH - SVG_MARGIN),
+    ] {
+        _ = writeln!(
+            svg,
+            r#"<text x="{x}" y="{y}" font-family="sans-serif" font-size="14" text-anchor="{anchor}">{value}</text>"#,
+            y = SVG_HEIGHT - SVG_MARGIN + 20.0,
+        );
+    }
+    _ = writeln!(
+        svg,
+        r#"<polyline points="{points}" fill="none" stroke="blue"/>"#
+    );
+    _ = writeln!(svg, "</svg>");
+    fs::write(path, svg)
+}
@@ -1,2 +1,46 @@
+use crate::{parser::ParseError, InvalidMove};
+use std::fmt::{self, Display, Formatter};
+
 #[derive(Debug, Clone, Copy)]
 pub struct Invalid;
+
+/// Any error returned by the library.
+///
+/// The specific error types convert into this, so `?` works across parsing and move application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Invalid,
+    InvalidMove,
+    Parse,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::Invalid => "invalid value",
+            Self::InvalidMove => "invalid move",
+            Self::Parse => "parse error",
+        };
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Invalid> for Error {
+    fn from(_: Invalid) -> Self {
+        Self::Invalid
+    }
+}
+
+impl From<InvalidMove> for Error {
+    fn from(_: InvalidMove) -> Self {
+        Self::InvalidMove
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(_: ParseError) -> Self {
+        Self::Parse
+    }
+}
@@ -1,7 +1,11 @@
+#[cfg(feature = "make-unmake")]
+use crate::enums::SimpleEnumExt;
 use crate::{
     constants::Eval, enums::EnumMap, AnyMove, Color, Features, InvalidMove, Move, Position,
     SetupMove,
 };
+#[cfg(feature = "make-unmake")]
+use std::mem;
 
 pub trait Evaluator: Send + Sync + 'static {
     type Accumulator: Copy;
@@ -13,18 +17,69 @@ pub trait Evaluator: Send + Sync + 'static {
     fn remove_feature(&self, accumulator: &mut Self::Accumulator, feature: usize);
     fn evaluate(&self, accumulators: &EnumMap<Color, Self::Accumulator>, to_move: Color) -> Eval;
     fn scale(&self) -> f64;
+
+    /// `evaluate` from the point of view of `color`, with intermediate values for inspection.
+    ///
+    /// Recomputes the accumulators from scratch, so it is slow. By default there are no terms.
+    fn evaluate_detailed(&self, position: &Position, color: Color) -> EvalDetail
+    where
+        Self: Sized,
+    {
+        EvalDetail {
+            eval: self.evaluate(&refresh_all(self, position), color),
+            terms: Vec::new(),
+        }
+    }
+
+    /// Short name identifying the kind of evaluator, e.g. "nnue".
+    fn name(&self) -> &str;
+
+    /// Hash of the weights, identifying a specific revision.
+    fn version(&self) -> u64;
+}
+
+/// Result of `Evaluator::evaluate_detailed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalDetail {
+    pub eval: Eval,
+    /// Named intermediate values. Their meaning depends on the evaluator.
+    pub terms: Vec<(String, Eval)>,
+}
+
+/// FNV-1a hash of the weights, for `Evaluator::version`.
+pub fn weights_version(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug)]
 pub struct EvaluatedPosition<'a, E: Evaluator> {
     evaluator: &'a E,
     position: Position,
     accumulators: EnumMap<Color, E::Accumulator>,
 }
 
+// Not derived, because that would require `E: Clone`.
+impl<E: Evaluator> Clone for EvaluatedPosition<'_, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E: Evaluator> Copy for EvaluatedPosition<'_, E> {}
+
+/// What `EvaluatedPosition::make_move_in_place` saves to undo the move.
+#[cfg(feature = "make-unmake")]
+pub(crate) struct Undo<A> {
+    mov: Move,
+    /// Accumulators that were refreshed rather than updated. The others are updated back.
+    accumulators: EnumMap<Color, Option<A>>,
+}
+
 impl<'a, E: Evaluator> EvaluatedPosition<'a, E> {
     pub fn new(evaluator: &'a E, position: Position) -> Self {
-        let accumulators = EnumMap::from_fn(|color| refresh(evaluator, &position, color));
+        let accumulators = refresh_all(evaluator, &position);
         Self {
             evaluator,
             position,
@@ -63,6 +118,17 @@ impl<'a, E: Evaluator> EvaluatedPosition<'a, E> {
 
     pub fn make_move(&self, mov: Move) -> Result<Self, InvalidMove> {
         let position = self.position.make_move(mov)?;
+        Ok(self.with_regular_move(mov, position))
+    }
+
+    /// `make_move` for a move known to be pseudo-legal, e.g. from the move generator.
+    pub(crate) fn make_move_unchecked(&self, mov: Move) -> Self {
+        let position = self.position.make_move_unchecked(mov);
+        self.with_regular_move(mov, position)
+    }
+
+    /// `position` is the position after `mov`.
+    fn with_regular_move(&self, mov: Move, position: Position) -> Self {
         let accumulators = EnumMap::from_fn(|color| {
             update(
                 self.evaluator,
@@ -72,11 +138,53 @@ impl<'a, E: Evaluator> EvaluatedPosition<'a, E> {
                 self.evaluator.features().diff(mov, &position, color),
             )
         });
-        Ok(Self {
+        Self {
             evaluator: self.evaluator,
             position,
             accumulators,
-        })
+        }
+    }
+
+    /// `make_move_unchecked` modifying the position rather than returning a new one.
+    #[cfg(feature = "make-unmake")]
+    pub(crate) fn make_move_in_place(&mut self, mov: Move) -> Undo<E::Accumulator> {
+        self.position.make_move_in_place_unchecked(mov);
+        let accumulators = EnumMap::from_fn(|color| {
+            match self.evaluator.features().diff(mov, &self.position, color) {
+                Some((added, removed)) => {
+                    let accumulator = &mut self.accumulators[color];
+                    removed.for_each(|feature| self.evaluator.remove_feature(accumulator, feature));
+                    added.for_each(|feature| self.evaluator.add_feature(accumulator, feature));
+                    None
+                }
+                None => Some(mem::replace(
+                    &mut self.accumulators[color],
+                    refresh(self.evaluator, &self.position, color),
+                )),
+            }
+        });
+        Undo { mov, accumulators }
+    }
+
+    /// Undoes `make_move_in_place`.
+    #[cfg(feature = "make-unmake")]
+    pub(crate) fn unmake_move(&mut self, undo: Undo<E::Accumulator>) {
+        for color in Color::all() {
+            match undo.accumulators[color] {
+                Some(accumulator) => self.accumulators[color] = accumulator,
+                None => {
+                    let (added, removed) = self
+                        .evaluator
+                        .features()
+                        .diff(undo.mov, &self.position, color)
+                        .unwrap();
+                    let accumulator = &mut self.accumulators[color];
+                    added.for_each(|feature| self.evaluator.remove_feature(accumulator, feature));
+                    removed.for_each(|feature| self.evaluator.add_feature(accumulator, feature));
+                }
+            }
+        }
+        self.position.unmake_move_in_place_unchecked(undo.mov);
     }
 
     pub fn make_null_move(&self) -> Result<Self, InvalidMove> {
@@ -94,6 +202,13 @@ impl<'a, E: Evaluator> EvaluatedPosition<'a, E> {
     }
 }
 
+pub(crate) fn refresh_all<E: Evaluator>(
+    evaluator: &E,
+    position: &Position,
+) -> EnumMap<Color, E::Accumulator> {
+    EnumMap::from_fn(|color| refresh(evaluator, position, color))
+}
+
 fn refresh<E: Evaluator>(evaluator: &E, position: &Position, color: Color) -> E::Accumulator {
     let mut acc = evaluator.new_accumulator();
     evaluator
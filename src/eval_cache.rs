@@ -0,0 +1,85 @@
+use crate::constants::Eval;
+use std::mem;
+
+/// Evaluator results for leaf positions, so that positions reached again don't need the network.
+///
+/// Direct-mapped: each position has one slot, and a store always replaces it.
+pub struct EvalCache {
+    entries: Vec<Entry>,
+    stats: EvalCacheStats,
+}
+
+/// Usage counters of an `EvalCache`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct EvalCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl EvalCacheStats {
+    /// Fraction of lookups that found an eval.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+impl EvalCache {
+    /// The smallest `size` for `new`: one entry.
+    pub const MIN_SIZE: usize = mem::size_of::<Entry>();
+
+    pub fn new(size: usize) -> Self {
+        let num_entries = size / mem::size_of::<Entry>();
+        assert!(num_entries > 0);
+        let num_entries = 1 << num_entries.ilog2();
+        Self {
+            entries: vec![Entry::default(); num_entries],
+            stats: EvalCacheStats::default(),
+        }
+    }
+
+    /// Bytes allocated, at most the size passed to `new`.
+    pub fn memory_bytes(&self) -> usize {
+        self.entries.len() * mem::size_of::<Entry>()
+    }
+
+    /// Usage counters since the last call to `take_stats`.
+    pub fn take_stats(&mut self) -> EvalCacheStats {
+        mem::take(&mut self.stats)
+    }
+
+    pub fn get(&mut self, hash: u64) -> Option<Eval> {
+        let entry = &self.entries[self.index(hash)];
+        if entry.valid && entry.hash == hash {
+            self.stats.hits += 1;
+            Some(entry.eval)
+        } else {
+            self.stats.misses += 1;
+            None
+        }
+    }
+
+    pub fn set(&mut self, hash: u64, eval: Eval) {
+        let index = self.index(hash);
+        self.entries[index] = Entry {
+            hash,
+            eval,
+            valid: true,
+        };
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        hash as usize & (self.entries.len() - 1)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+struct Entry {
+    hash: u64,
+    eval: Eval,
+    valid: bool,
+}
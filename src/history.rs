@@ -1,6 +1,6 @@
 use crate::{
     constants::{Ply, HISTORY_BLOOM_FILTER_LOG_SIZE, HISTORY_BLOOM_FILTER_NUM_HASHES},
-    Position,
+    MoveEffect, Position,
 };
 use std::iter;
 
@@ -24,6 +24,18 @@ impl History {
         Self::new(position.hash_for_repetition())
     }
 
+    /// History of a game starting at `position`, which need not be at ply 0.
+    ///
+    /// Its ply matches the position's. Nothing before `position` is known, so the earlier plies
+    /// can't repeat.
+    pub fn new_from_start(position: &Position) -> Self {
+        let mut history = Self::new_from_position(position);
+        for _ in 0..position.ply() {
+            history.push_position_irreversible(position);
+        }
+        history
+    }
+
     pub fn ply(&self) -> Ply {
         (self.hashes.len() - 1) as Ply
     }
@@ -48,6 +60,15 @@ impl History {
         self.push_irreversible(position.hash_for_repetition());
     }
 
+    /// Pushes the position reached by a move with the given effect.
+    pub fn push_position_after(&mut self, position: &Position, effect: &MoveEffect) {
+        if effect.irreversible {
+            self.push_position_irreversible(position);
+        } else {
+            self.push_position(position);
+        }
+    }
+
     pub fn pop(&mut self) {
         let hash = self.hashes.pop().unwrap();
         for index in Self::indices(hash) {
@@ -59,6 +80,25 @@ impl History {
         }
     }
 
+    /// Rewinds to `ply`, as if `pop` was called until `self.ply() == ply`.
+    pub fn truncate(&mut self, ply: Ply) {
+        assert!(ply <= self.ply());
+        let len = usize::from(ply) + 1;
+        for &hash in &self.hashes[len..] {
+            for index in Self::indices(hash) {
+                self.bloom_filter[index] -= 1;
+            }
+        }
+        self.hashes.truncate(len);
+        let num_irreversible = self.irreversible.partition_point(|&p| p <= ply);
+        self.irreversible.truncate(num_irreversible);
+    }
+
+    /// Rewinds to the starting position.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
     pub fn find_repetition(&self) -> Option<Ply> {
         let mut ply = self.hashes.len() - 1;
         let hash = self.hashes[ply];
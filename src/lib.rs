@@ -12,18 +12,26 @@ pub mod smallvec;
 mod bitboard;
 mod board;
 pub mod book;
+#[cfg(feature = "book")]
 mod book_data;
 mod captured;
 mod cli;
 mod color;
 pub mod constants;
+#[cfg(feature = "nnue")]
+mod dump_net;
 mod eval;
+mod eval_cache;
 mod features;
 mod history;
+mod linear_eval;
+mod linear_wps_weights;
 mod main_player;
 pub mod movegen;
 mod moves;
+#[cfg(feature = "nnue")]
 mod nnue;
+#[cfg(feature = "nnue")]
 mod nnue_weights;
 mod piece;
 mod player;
@@ -47,24 +55,33 @@ pub use board::Board;
 pub use captured::{captured_index, Captured, CapturedOneSide, NUM_CAPTURED_INDEXES};
 pub use cli::{run_cli, CliCommand};
 pub use color::Color;
-pub use eval::{EvaluatedPosition, Evaluator};
+#[cfg(feature = "nnue")]
+pub use dump_net::{format_layer, run_dump_net};
+pub use eval::{weights_version, EvalDetail, EvaluatedPosition, Evaluator};
+pub use eval_cache::{EvalCache, EvalCacheStats};
 pub use features::Features;
 pub use history::History;
-pub use main_player::MainPlayerFactory;
+pub use linear_eval::LinearEvaluator;
+pub use main_player::{MainPlayerFactory, MainPlayerFactoryBuilder};
 pub use moves::{AnyMove, InvalidMove, Move, SetupMove, ShortMove, ShortMoveFrom};
-pub use nnue::Nnue;
+#[cfg(feature = "nnue")]
+pub use nnue::{Nnue, NnueLayer, WeightStats};
 pub use piece::{ColoredPiece, Piece};
-pub use player::{Player, PlayerFactory};
-pub use position::{Outcome, Position, Stage};
-pub use pvtable::PVTable;
+pub use player::{Player, PlayerFactory, SearchInfo};
+pub use position::{EndReason, MoveEffect, Outcome, Position, Stage};
+pub use pvtable::{PVTable, PVTableStats};
 pub use score::{Score, ScoreExpanded};
-pub use search::{Deadlines, ScoredMove, Search};
+pub use search::{CumulativeStats, Deadlines, ScoredMove, Search, TimeoutKind, TimeoutStats};
 pub use square::{Coord, Direction, Square};
 pub use symmetry::{NormalizedSquare, Symmetry};
+pub use ttable::{TTable, TTableEntry, TTableScoreType};
 pub use variation::{
     EmptyVariation, ExtendableVariation, LongVariation, NonEmptyVariation, OneMoveVariation,
     Variation,
 };
 pub use wps_features::WPSFeatures;
 
+#[cfg(feature = "nnue")]
 pub type DefaultEvaluator = Nnue;
+#[cfg(not(feature = "nnue"))]
+pub type DefaultEvaluator = LinearEvaluator<WPSFeatures>;
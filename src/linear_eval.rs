@@ -0,0 +1,99 @@
+use crate::{
+    constants::Eval,
+    enums::EnumMap,
+    eval::{refresh_all, EvalDetail},
+    linear_wps_weights, weights_version, Color, Evaluator, Features, Position, WPSFeatures,
+};
+
+#[derive(Debug)]
+pub struct LinearEvaluator<F> {
+    name: String,
+    version: u64,
+    features: F,
+    to_move_weight: i16,
+    feature_weights: Vec<i16>,
+    scale: f64,
+}
+
+impl<F: Features> LinearEvaluator<F> {
+    pub fn new(features: F, to_move_weight: i16, feature_weights: &[i16], scale: f64) -> Self {
+        assert_eq!(feature_weights.len(), features.count());
+        let version = weights_version(
+            [to_move_weight]
+                .iter()
+                .chain(feature_weights)
+                .flat_map(|w| w.to_le_bytes())
+                .chain(scale.to_le_bytes()),
+        );
+        Self {
+            name: format!("linear-{}", features.name()),
+            version,
+            features,
+            to_move_weight,
+            feature_weights: feature_weights.to_vec(),
+            scale,
+        }
+    }
+}
+
+impl<F: Features> Evaluator for LinearEvaluator<F> {
+    type Accumulator = Eval;
+    type Features = F;
+
+    fn features(&self) -> Self::Features {
+        self.features
+    }
+
+    fn new_accumulator(&self) -> Self::Accumulator {
+        0
+    }
+
+    fn add_feature(&self, accumulator: &mut Self::Accumulator, feature: usize) {
+        *accumulator += Eval::from(self.feature_weights[feature]);
+    }
+
+    fn remove_feature(&self, accumulator: &mut Self::Accumulator, feature: usize) {
+        *accumulator -= Eval::from(self.feature_weights[feature]);
+    }
+
+    fn evaluate(&self, accumulators: &EnumMap<Color, Self::Accumulator>, to_move: Color) -> Eval {
+        accumulators[to_move] - accumulators[to_move.opposite()] + Eval::from(self.to_move_weight)
+    }
+
+    fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Terms are the feature sums of both sides and the side to move bonus. They add up to the
+    /// evaluation.
+    fn evaluate_detailed(&self, position: &Position, color: Color) -> EvalDetail {
+        let accumulators = refresh_all(self, position);
+        EvalDetail {
+            eval: self.evaluate(&accumulators, color),
+            terms: vec![
+                ("own".to_string(), accumulators[color]),
+                ("opponent".to_string(), -accumulators[color.opposite()]),
+                ("to_move".to_string(), Eval::from(self.to_move_weight)),
+            ],
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+impl Default for LinearEvaluator<WPSFeatures> {
+    fn default() -> Self {
+        Self::new(
+            WPSFeatures,
+            linear_wps_weights::TO_MOVE,
+            &linear_wps_weights::FEATURES,
+            linear_wps_weights::SCALE,
+        )
+    }
+}
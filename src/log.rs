@@ -1,5 +1,9 @@
+use crate::{
+    impl_from_str_for_parsable,
+    parser::{self, Parser, ParserExt},
+};
 use std::{
-    fmt,
+    env, fmt,
     io::{BufWriter, Stderr, Write},
     sync::Mutex,
 };
@@ -11,6 +15,69 @@ pub enum Level {
     Always,
 }
 
+/// How much the binaries log.
+///
+/// Chosen with the `--quiet` and `--verbose` flags, or the `WAZIR_DROP_LOG` environment variable
+/// set to `quiet`, `normal` or `verbose`. Flags take precedence over the environment.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Verbosity {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+}
+
+impl Verbosity {
+    pub const ENV_VAR: &str = "WAZIR_DROP_LOG";
+
+    pub fn new(quiet: bool, verbose: bool) -> Self {
+        if verbose {
+            Self::Verbose
+        } else if quiet {
+            Self::Quiet
+        } else {
+            Self::from_env().unwrap_or_default()
+        }
+    }
+
+    /// Looks for `--quiet` and `--verbose` among `args`, ignoring other arguments.
+    pub fn from_args<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> Self {
+        let mut quiet = false;
+        let mut verbose = false;
+        for arg in args {
+            match arg.as_ref() {
+                "--quiet" => quiet = true,
+                "--verbose" => verbose = true,
+                _ => {}
+            }
+        }
+        Self::new(quiet, verbose)
+    }
+
+    /// `None` if the variable is unset or invalid.
+    pub fn from_env() -> Option<Self> {
+        env::var(Self::ENV_VAR).ok()?.parse().ok()
+    }
+
+    /// The minimum level written by `write`.
+    pub fn level(self) -> Level {
+        match self {
+            Self::Quiet => Level::Always,
+            Self::Normal => Level::Info,
+            Self::Verbose => Level::Verbose,
+        }
+    }
+
+    pub fn parser() -> impl Parser<Output = Self> {
+        parser::exact(b"quiet")
+            .map(|()| Self::Quiet)
+            .or(parser::exact(b"normal").map(|()| Self::Normal))
+            .or(parser::exact(b"verbose").map(|()| Self::Verbose))
+    }
+}
+
+impl_from_str_for_parsable!(Verbosity);
+
 #[derive(Debug)]
 struct Logger {
     level: Level,
@@ -1,7 +1,41 @@
-use wazir_drop::{run_cli, MainPlayerFactory};
+use wazir_drop::{constants::Hyperparameters, run_cli, DefaultEvaluator, MainPlayerFactory};
 
-use std::process::ExitCode;
+use std::{env, process::ExitCode, sync::Arc};
 
 fn main() -> ExitCode {
-    run_cli(&MainPlayerFactory::default())
+    #[cfg(feature = "nnue")]
+    if env::args().nth(1).as_deref() == Some("dumpnet") {
+        return wazir_drop::run_dump_net(env::args().skip(2));
+    }
+    let search_threads = match search_threads_from_args(env::args().skip(1)) {
+        Ok(search_threads) => search_threads,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let hyperparameters = Hyperparameters {
+        search_threads,
+        ..Hyperparameters::default()
+    };
+    run_cli(&MainPlayerFactory::new(
+        &hyperparameters,
+        &Arc::new(DefaultEvaluator::default()),
+    ))
+}
+
+/// Looks for `--threads <n>` among `args`, ignoring other arguments. 1 if absent.
+fn search_threads_from_args(args: impl IntoIterator<Item = String>) -> Result<usize, String> {
+    let mut search_threads = 1;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--threads" {
+            let value = args.next().ok_or("Missing value for --threads")?;
+            search_threads = match value.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(format!("Invalid --threads {value}")),
+            };
+        }
+    }
+    Ok(search_threads)
 }
@@ -1,30 +1,68 @@
 use crate::{
-    book,
-    clock::Timer,
-    constants::{Hyperparameters, Ply, PLY_AFTER_SETUP, PLY_DRAW, TIME_MARGIN},
-    log, AnyMove, Color, Deadlines, DefaultEvaluator, Evaluator, History, Player, PlayerFactory,
-    Position, Search, SetupMove, Stage,
+    book::{self, OpeningBook},
+    clock::{TimeControl, Timer},
+    constants::{Depth, Eval, Hyperparameters, PLY_AFTER_SETUP, PLY_DRAW, TIME_MARGIN},
+    log,
+    search::{ScoredMove, SearchResult},
+    AnyMove, Color, Deadlines, DefaultEvaluator, Evaluator, History, Move, Player, PlayerFactory,
+    Position, Search, SearchInfo, SetupMove, Square, Stage, Symmetry,
+};
+use std::{
+    array, iter,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
 };
-use std::{sync::Arc, time::Duration};
 
 struct MainPlayer<E: Evaluator> {
     hyperparameters: Hyperparameters,
+    evaluator: Arc<E>,
+    time_control: Option<TimeControl>,
+    max_depth: Option<Depth>,
+    /// Derived from `Hyperparameters::random_move_seed` and the game id.
+    random_seed: u64,
     search: Search<E>,
+    book: Option<Arc<OpeningBook>>,
     red_setup: Option<SetupMove>,
     position: Position,
     history: History,
+    last_search_info: Option<SearchInfo>,
+    /// The opponent reply predicted by the last search.
+    expected_reply: Option<Move>,
+    /// Shares the transposition table with `search`. Moved to the ponder thread while pondering.
+    ponder_search: Option<Search<E>>,
+    ponder: Option<Ponder<E>>,
+}
+
+/// A search of the position after the expected reply, running on another thread.
+struct Ponder<E: Evaluator> {
+    reply: Move,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<(Search<E>, SearchResult)>,
 }
 
 impl<E: Evaluator> MainPlayer<E> {
-    fn time_allocation(&self, ply: Ply, time_left: Duration, timer: &Timer) -> Deadlines {
+    fn time_allocation(
+        &self,
+        position: &Position,
+        time_left: Duration,
+        timer: &Timer,
+    ) -> Deadlines {
         let mut weight = 1.0;
         let mut total_weight = 0.0;
-        let mut p = ply;
-        while p < PLY_DRAW {
+        let late_ply = self
+            .hyperparameters
+            .late_ply
+            .saturating_sub(PLY_DRAW - position.ply_draw());
+        let mut p = position.ply();
+        while p < position.ply_draw() {
             total_weight += weight;
             let reduction = if p < PLY_AFTER_SETUP {
                 self.hyperparameters.time_reduction_per_setup_move
-            } else if p < self.hyperparameters.late_ply {
+            } else if p < late_ply {
                 self.hyperparameters.time_reduction_per_move
             } else {
                 self.hyperparameters.time_reduction_per_late_move
@@ -33,6 +71,22 @@ impl<E: Evaluator> MainPlayer<E> {
             p += 2;
         }
         let to_allocate = time_left.saturating_sub(TIME_MARGIN);
+        let increment = self
+            .time_control
+            .map_or(Duration::ZERO, |time_control| time_control.increment);
+        let move_cap = self
+            .time_control
+            .and_then(|time_control| time_control.move_cap)
+            .map_or(Duration::MAX, |move_cap| {
+                move_cap.saturating_sub(TIME_MARGIN)
+            });
+        // The increment comes back after the move, so it can be spent now on top of the share.
+        let deadline = |fraction: f64| {
+            let allocated = (to_allocate.mul_f64(fraction) + increment)
+                .min(to_allocate)
+                .min(move_cap);
+            timer.instant_at(time_left.saturating_sub(allocated))
+        };
         let fraction = 1.0 / total_weight;
         let soft_fraction = fraction * self.hyperparameters.soft_time_fraction;
         let next_depth_fraction = fraction * self.hyperparameters.start_next_depth_fraction;
@@ -41,79 +95,182 @@ impl<E: Evaluator> MainPlayer<E> {
             .max(fraction);
         let panic_soft_fraction = panic_fraction * self.hyperparameters.soft_time_fraction;
         Deadlines {
-            hard: timer.instant_at(time_left.saturating_sub(to_allocate.mul_f64(fraction))),
-            soft: timer.instant_at(time_left.saturating_sub(to_allocate.mul_f64(soft_fraction))),
-            start_next_depth: timer
-                .instant_at(time_left.saturating_sub(to_allocate.mul_f64(next_depth_fraction))),
-            panic_hard: timer
-                .instant_at(time_left.saturating_sub(to_allocate.mul_f64(panic_fraction))),
-            panic_soft: timer
-                .instant_at(time_left.saturating_sub(to_allocate.mul_f64(panic_soft_fraction))),
+            hard: deadline(fraction),
+            soft: deadline(soft_fraction),
+            start_next_depth: deadline(next_depth_fraction),
+            panic_hard: deadline(panic_fraction),
+            panic_soft: deadline(panic_soft_fraction),
+        }
+    }
+
+    /// A random move among `top_moves`, determined by the seed and the position. `None` if
+    /// `top_moves` is empty.
+    fn random_top_move(&self, position: &Position, top_moves: &[ScoredMove]) -> Option<Move> {
+        if top_moves.is_empty() {
+            return None;
+        }
+        let index = seeded_random(self.random_seed ^ position.hash(), top_moves.len());
+        Some(top_moves[index].mov)
+    }
+
+    fn book_red_setup(&self) -> SetupMove {
+        self.book
+            .as_ref()
+            .and_then(|book| book.red_setup())
+            .unwrap_or_else(book::red_setup)
+    }
+
+    fn book_blue_setup(&self, red: SetupMove) -> Option<SetupMove> {
+        match &self.book {
+            Some(book) => book.blue_setup(red),
+            None => book::blue_setup(red),
+        }
+    }
+
+    fn book_blue_setup_moves(&self) -> Vec<SetupMove> {
+        match &self.book {
+            Some(book) if !book.is_empty() => book.blue_setup_moves(),
+            _ => book::blue_setup_moves(),
+        }
+    }
+
+    fn start_ponder_search(&mut self, position: &Position) {
+        self.stop_ponder_search(None);
+        if position.stage() != Stage::Regular {
+            return;
         }
+        let Some(reply) = self.expected_reply else {
+            return;
+        };
+        let Ok(predicted) = position.make_move(reply) else {
+            return;
+        };
+        if predicted.stage() != Stage::Regular {
+            return;
+        }
+        let mut history = self.history.clone();
+        history.push_position(&predicted);
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut search = self.ponder_search.take().unwrap_or_else(|| {
+            Search::with_ttable(&self.hyperparameters, &self.evaluator, self.search.ttable())
+        });
+        search.set_stop_flag(Some(Arc::clone(&stop)));
+        log::info!("ponder {reply}");
+        let thread = thread::spawn(move || {
+            let result = search.search(&predicted, None, None, None, false, &history);
+            (search, result)
+        });
+        self.ponder = Some(Ponder {
+            reply,
+            stop,
+            thread,
+        });
+    }
+
+    /// `mov` is the opponent's actual move, if known.
+    ///
+    /// The ponder search filled the shared transposition table, so after a ponder hit the next
+    /// search starts from its results.
+    fn stop_ponder_search(&mut self, mov: Option<AnyMove>) {
+        let Some(ponder) = self.ponder.take() else {
+            return;
+        };
+        ponder.stop.store(true, Ordering::Relaxed);
+        let (search, result) = ponder.thread.join().expect("Ponder thread panicked");
+        self.ponder_search = Some(search);
+        let outcome = match mov {
+            Some(mov) if mov == AnyMove::Regular(ponder.reply) => "hit",
+            Some(_) => "miss",
+            None => "stopped",
+        };
+        log::info!(
+            "ponder {outcome} d={depth} n={knodes}k",
+            depth = result.depth,
+            knodes = result.nodes / 1000,
+        );
     }
 
     fn move_made(&mut self, mov: AnyMove) {
-        self.position = self.position.make_any_move(mov).expect("Invalid move");
-        match mov {
-            AnyMove::Setup(mov) => {
-                if mov.color == Color::Red {
-                    self.red_setup = Some(mov);
-                }
-                self.history.push_position_irreversible(&self.position);
-            }
-            AnyMove::Regular(_) => {
-                self.history.push_position(&self.position);
+        let (position, effect) = self
+            .position
+            .make_any_move_with_effect(mov)
+            .expect("Invalid move");
+        self.position = position;
+        if let AnyMove::Setup(mov) = mov {
+            if mov.color == Color::Red {
+                self.red_setup = Some(mov);
             }
         }
+        self.history.push_position_after(&self.position, &effect);
     }
 }
 
 impl<E: Evaluator> Player for MainPlayer<E> {
     fn opponent_move(&mut self, _position: &Position, mov: AnyMove, _timer: &Timer) {
+        self.stop_ponder_search(Some(mov));
         self.move_made(mov);
     }
 
     fn make_move(&mut self, position: &Position, timer: &Timer) -> AnyMove {
         let time_left = timer.get();
-        let deadlines = self.time_allocation(position.ply(), time_left, timer);
+        self.stop_ponder_search(None);
+        let deadlines = self.time_allocation(position, time_left, timer);
+        self.last_search_info = None;
+        self.expected_reply = None;
         let mov = match position.stage() {
             Stage::Setup => match position.to_move() {
-                Color::Red => book::red_setup().into(),
+                Color::Red => self.book_red_setup().into(),
                 Color::Blue => {
                     let red_setup = self.red_setup.expect("Red setup not found");
-                    if let Some(mov) = book::blue_setup(red_setup) {
+                    if let Some(mov) = self.book_blue_setup(red_setup) {
                         mov.into()
                     } else {
                         let result = self.search.search_blue_setup(
                             red_setup,
-                            None,
+                            self.max_depth,
                             Some(deadlines),
-                            &book::blue_setup_moves(),
+                            &self.book_blue_setup_moves(),
                         );
                         let elapsed = time_left.saturating_sub(timer.get());
                         log::info!(
                             "d={depth} {root_moves_considered}/{root_all_moves} \
-                                    s={score} n={knodes}k kns={knps:.0} t={t}ms pv={setup} {pv}",
+                                    s={score} n={knodes}k kns={knps:.0} ebf={ebf:.2} t={t}ms \
+                                    to={timeouts} pv={setup} {pv}",
                             depth = result.depth,
                             root_moves_considered = result.root_moves_considered,
                             root_all_moves = result.num_root_moves,
                             score = result.score.to_relative(position.ply()),
                             knodes = result.nodes / 1000,
                             knps = result.nodes as f64 / elapsed.as_secs_f64() / 1000.0,
+                            ebf = result.branching_factor.unwrap_or(f64::NAN),
                             setup = result.mov,
                             t = elapsed.as_millis(),
+                            timeouts = result.timeouts,
                             pv = result.pv,
                         );
+                        self.last_search_info = Some(SearchInfo {
+                            depth: result.depth,
+                            score: result.score.to_relative(position.ply()),
+                            nodes: result.nodes,
+                            time: elapsed,
+                            pv: iter::once(AnyMove::from(result.mov))
+                                .chain(result.pv.iter().map(|&mov| mov.into()))
+                                .collect(),
+                        });
                         result.mov.into()
                     }
                 }
             },
             Stage::Regular => {
+                let multi_move_threshold = self
+                    .hyperparameters
+                    .random_move_margin
+                    .map(|margin| (margin * self.evaluator.scale()) as Eval);
                 let result = self.search.search(
                     position,
-                    None, /* max_depth */
+                    self.max_depth,
                     Some(deadlines),
-                    None,  /* multi_move_threshold */
+                    multi_move_threshold,
                     false, /* is_score_important */
                     &self.history,
                 );
@@ -121,29 +278,149 @@ impl<E: Evaluator> Player for MainPlayer<E> {
                 log::info!(
                     "d={depth} {root_moves_considered}/{root_all_moves} \
                         s={score} \
-                        n={knodes}k kns={knps:.0} t={t}ms pv={pv}",
+                        n={knodes}k kns={knps:.0} ebf={ebf:.2} t={t}ms to={timeouts} \
+                        asp={aspiration_researches} pv={pv}",
                     depth = result.depth,
                     root_moves_considered = result.root_moves_considered,
                     root_all_moves = result.num_root_moves,
                     score = result.score.to_relative(position.ply()),
                     knodes = result.nodes / 1000,
                     knps = result.nodes as f64 / elapsed.as_secs_f64() / 1000.0,
+                    ebf = result.branching_factor.unwrap_or(f64::NAN),
                     t = elapsed.as_millis(),
+                    timeouts = result.timeouts,
+                    aspiration_researches = result.aspiration_researches,
                     pv = result.pv,
                 );
-                result.pv.moves[0].into()
+                log::verbose!(
+                    "pvtable hits={hits:.0}% evictions={evictions} full={full}",
+                    hits = 100.0 * result.pvtable_stats.hit_rate(),
+                    evictions = result.pvtable_stats.evictions,
+                    full = self.search.pvtable().hashfull(),
+                );
+                if self.hyperparameters.eval_cache_size > 0 {
+                    log::verbose!(
+                        "eval cache hits={hits:.0}%",
+                        hits = 100.0 * result.eval_cache_stats.hit_rate(),
+                    );
+                }
+                if self.hyperparameters.search_threads > 1 {
+                    log::verbose!(
+                        "helpers threads={threads} n={knodes}k",
+                        threads = self.hyperparameters.search_threads - 1,
+                        knodes = result.helper_nodes / 1000,
+                    );
+                }
+                let best = result.pv.moves[0];
+                let mov = self
+                    .random_top_move(position, &result.top_moves)
+                    .unwrap_or(best);
+                if mov != best {
+                    log::info!(
+                        "random move {mov} of {num_moves}",
+                        num_moves = result.top_moves.len()
+                    );
+                }
+                self.last_search_info = Some(SearchInfo {
+                    depth: result.depth,
+                    score: result.score.to_relative(position.ply()),
+                    nodes: result.nodes,
+                    time: elapsed,
+                    pv: if mov == best {
+                        result.pv.iter().map(|&mov| mov.into()).collect()
+                    } else {
+                        vec![mov.into()]
+                    },
+                });
+                if mov == best {
+                    self.expected_reply = result.pv.moves.get(1).copied();
+                }
+                mov.into()
             }
             Stage::End(_) => panic!("Game is over"),
         };
         self.move_made(mov);
         mov
     }
+
+    fn last_search_info(&self) -> Option<SearchInfo> {
+        self.last_search_info.clone()
+    }
+
+    fn start_ponder(&mut self, position: &Position) {
+        self.start_ponder_search(position);
+    }
+
+    fn stop_ponder(&mut self) {
+        self.stop_ponder_search(None);
+    }
+}
+
+/// The red setup of a position in the setup stage, if red has already set up.
+fn red_setup_on_board(position: &Position) -> Option<SetupMove> {
+    if position.stage() != Stage::Setup || position.ply() == 0 {
+        return None;
+    }
+    let symmetry = Symmetry::pov(Color::Red).inverse();
+    let pieces = array::from_fn(|i| {
+        position
+            .square(symmetry.apply(Square::from_index(i)))
+            .expect("Missing red setup piece")
+            .piece()
+    });
+    Some(SetupMove {
+        color: Color::Red,
+        pieces,
+    })
+}
+
+/// Mixes `game_id` into `seed` with 64-bit FNV-1a. Unlike `DefaultHasher`, it doesn't change
+/// between Rust versions, so the same seed keeps giving the same games.
+fn game_seed(seed: u64, game_id: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    seed.to_le_bytes()
+        .iter()
+        .chain(game_id.as_bytes())
+        .fold(OFFSET_BASIS, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        })
+}
+
+/// A number in `0..n` determined by `seed`.
+fn seeded_random(seed: u64, n: usize) -> usize {
+    // Compute hash x = (a * seed + b) % MODULUS % n;
+    const MODULUS: u128 = (1 << 61) - 1;
+    const A: u128 = 0x10c82ee50ad34876;
+    const B: u128 = 0x41cd6910f455faa;
+    let x = (A * (u128::from(seed) % MODULUS) + B) % MODULUS % n as u128;
+    x as usize
+}
+
+impl<E: Evaluator> Drop for MainPlayer<E> {
+    fn drop(&mut self) {
+        self.stop_ponder_search(None);
+    }
 }
 
 #[derive(Debug)]
 pub struct MainPlayerFactory<E: Evaluator> {
     hyperparameters: Hyperparameters,
     evaluator: Arc<E>,
+    book: Option<Arc<OpeningBook>>,
+    max_depth: Option<Depth>,
+}
+
+impl MainPlayerFactory<DefaultEvaluator> {
+    /// Starts with the default hyperparameters, the default evaluator and the compiled book.
+    pub fn builder() -> MainPlayerFactoryBuilder<DefaultEvaluator> {
+        MainPlayerFactoryBuilder {
+            hyperparameters: Hyperparameters::default(),
+            evaluator: Arc::new(DefaultEvaluator::default()),
+            book: None,
+            max_depth: None,
+        }
+    }
 }
 
 impl<E: Evaluator> MainPlayerFactory<E> {
@@ -151,8 +428,14 @@ impl<E: Evaluator> MainPlayerFactory<E> {
         Self {
             hyperparameters: hyperparameters.clone(),
             evaluator: evaluator.clone(),
+            book: None,
+            max_depth: None,
         }
     }
+
+    pub fn evaluator(&self) -> &Arc<E> {
+        &self.evaluator
+    }
 }
 
 impl Default for MainPlayerFactory<DefaultEvaluator> {
@@ -164,26 +447,95 @@ impl Default for MainPlayerFactory<DefaultEvaluator> {
     }
 }
 
+#[derive(Debug)]
+pub struct MainPlayerFactoryBuilder<E: Evaluator> {
+    hyperparameters: Hyperparameters,
+    evaluator: Arc<E>,
+    book: Option<Arc<OpeningBook>>,
+    max_depth: Option<Depth>,
+}
+
+impl<E: Evaluator> MainPlayerFactoryBuilder<E> {
+    pub fn hyperparameters(mut self, hyperparameters: &Hyperparameters) -> Self {
+        self.hyperparameters = hyperparameters.clone();
+        self
+    }
+
+    pub fn evaluator<E2: Evaluator>(self, evaluator: &Arc<E2>) -> MainPlayerFactoryBuilder<E2> {
+        MainPlayerFactoryBuilder {
+            hyperparameters: self.hyperparameters,
+            evaluator: Arc::clone(evaluator),
+            book: self.book,
+            max_depth: self.max_depth,
+        }
+    }
+
+    /// Use `book` instead of the compiled book. If `book` is empty, the compiled book is still
+    /// used.
+    pub fn book(mut self, book: OpeningBook) -> Self {
+        self.book = Some(Arc::new(book));
+        self
+    }
+
+    /// Stop every search at `depth`, so that the moves don't depend on timing. Deadlines still
+    /// apply.
+    pub fn max_depth(mut self, depth: Depth) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    pub fn build(self) -> MainPlayerFactory<E> {
+        MainPlayerFactory {
+            hyperparameters: self.hyperparameters,
+            evaluator: self.evaluator,
+            book: self.book,
+            max_depth: self.max_depth,
+        }
+    }
+}
+
 impl<E: Evaluator> PlayerFactory for MainPlayerFactory<E> {
     fn create(
         &self,
-        _game_id: &str,
+        game_id: &str,
         _color: Color,
+        start: &Position,
         opening: &[AnyMove],
-        _time_limit: Option<Duration>,
+        time_control: Option<TimeControl>,
     ) -> Box<dyn crate::Player> {
-        let position = Position::initial();
-        let history = History::new_from_position(&position);
+        log::verbose!("evaluator {}", self.version());
+        if let Some(book) = &self.book {
+            log::info!("loaded book {len} openings", len = book.len());
+        }
+        let position = *start;
+        let history = History::new_from_start(&position);
         let mut player = MainPlayer {
             hyperparameters: self.hyperparameters.clone(),
+            evaluator: Arc::clone(&self.evaluator),
+            time_control,
+            max_depth: self.max_depth,
+            random_seed: game_seed(self.hyperparameters.random_move_seed, game_id),
             search: Search::new(&self.hyperparameters, &self.evaluator),
-            red_setup: None,
+            book: self.book.clone(),
+            red_setup: red_setup_on_board(&position),
             position,
             history,
+            last_search_info: None,
+            expected_reply: None,
+            ponder_search: None,
+            ponder: None,
         };
         for mov in opening {
             player.move_made(*mov);
         }
         Box::new(player)
     }
+
+    fn version(&self) -> String {
+        format!(
+            "{name} {version:016x}",
+            name = self.evaluator.name(),
+            version = self.evaluator.version()
+        )
+    }
 }
@@ -1,6 +1,9 @@
 use crate::{
-    either::Either, enums::SimpleEnumExt, smallvec::SmallVec, AnyMove, Bitboard, Color,
-    InvalidMove, Move, Piece, Position, SetupMove, ShortMove, ShortMoveFrom, Square, Stage,
+    either::Either,
+    enums::{EnumMap, SimpleEnumExt},
+    smallvec::SmallVec,
+    AnyMove, Bitboard, Color, ColoredPiece, InvalidMove, Move, Piece, Position, SetupMove,
+    ShortMove, ShortMoveFrom, Square, Stage,
 };
 use std::iter;
 
@@ -33,6 +36,25 @@ pub fn validate_from_to(piece: Piece, from: Square, to: Square) -> Result<(), In
     Ok(())
 }
 
+/// Squares where `cpiece` may be dropped, if empty.
+///
+/// All squares under the current rules.
+pub fn drop_bitboard(_cpiece: ColoredPiece) -> Bitboard {
+    Bitboard::ALL
+}
+
+/// Whether the drop rules allow dropping `cpiece` on `square`, assuming it is empty.
+pub fn is_legal_drop_square(cpiece: ColoredPiece, square: Square) -> bool {
+    drop_bitboard(cpiece).contains(square)
+}
+
+pub fn validate_drop(cpiece: ColoredPiece, to: Square) -> Result<(), InvalidMove> {
+    if !is_legal_drop_square(cpiece, to) {
+        return Err(InvalidMove);
+    }
+    Ok(())
+}
+
 const CONST_NO_MOVE_TABLE: [[Bitboard; Square::COUNT]; Piece::COUNT] = {
     let mut table = [Bitboard::EMPTY; Square::COUNT];
     let mut square_idx = 0;
@@ -148,6 +170,7 @@ pub fn any_move_from_short_move(
                     if captured.is_some() || position.num_captured(cpiece) == 0 {
                         return Err(InvalidMove);
                     }
+                    validate_drop(cpiece, to)?;
                     (cpiece, None)
                 }
                 ShortMoveFrom::Square(square) => {
@@ -170,10 +193,114 @@ pub fn any_move_from_short_move(
     }
 }
 
+/// Whether `mov` is one of the moves generated by `pseudomoves`.
+pub fn is_pseudo_legal(position: &Position, mov: Move) -> bool {
+    let me = position.to_move();
+    if position.stage() != Stage::Regular || mov.colored_piece.color() != me {
+        return false;
+    }
+    let target = mov.captured.map(|piece| piece.with_color(me.opposite()));
+    if position.square(mov.to) != target {
+        return false;
+    }
+    match mov.from {
+        None => {
+            mov.captured.is_none()
+                && position.num_captured(mov.colored_piece) != 0
+                && is_legal_drop_square(mov.colored_piece, mov.to)
+        }
+        Some(from) => {
+            position.square(from) == Some(mov.colored_piece)
+                && move_bitboard(mov.colored_piece.piece(), from).contains(mov.to)
+        }
+    }
+}
+
+/// Whether `mov` is one of the moves generated by `moves`: pseudo-legal and not a suicide.
+///
+/// Capturing the opponent's wazir wins, so it is legal even if the wazir is left attacked.
+pub fn is_legal(position: &Position, mov: Move) -> bool {
+    if !is_pseudo_legal(position, mov) {
+        return false;
+    }
+    mov.captured == Some(Piece::Wazir)
+        || !in_check(&position.make_move_unchecked(mov), position.to_move())
+}
+
+/// Number of setup moves for one color: 16! / (8! 4! 2! 1! 1!).
+pub const NUM_SETUP_MOVES: u32 = 10810800;
+
+/// All setup moves in lexicographic order of pieces.
 pub fn setup_moves(color: Color) -> impl Iterator<Item = SetupMove> {
     SetupMoveIterator { color, mov: None }
 }
 
+/// `setup_moves` starting at rank `skip`, without iterating over the earlier moves.
+pub fn setup_moves_from(color: Color, skip: u32) -> impl Iterator<Item = SetupMove> {
+    let mov = skip
+        .min(NUM_SETUP_MOVES)
+        .checked_sub(1)
+        .map(|prev| setup_move_unrank(color, prev).unwrap());
+    SetupMoveIterator { color, mov }
+}
+
+/// Index of the move in `setup_moves`.
+///
+/// The pieces must be valid.
+pub fn setup_move_rank(mov: SetupMove) -> u32 {
+    let mut counts = EnumMap::from_fn(Piece::initial_count);
+    let mut rank = 0;
+    for &piece in &mov.pieces {
+        for smaller in Piece::all().take_while(|&p| p < piece) {
+            if counts[smaller] != 0 {
+                counts[smaller] -= 1;
+                rank += num_arrangements(&counts);
+                counts[smaller] += 1;
+            }
+        }
+        assert!(counts[piece] != 0, "Invalid setup move");
+        counts[piece] -= 1;
+    }
+    rank
+}
+
+/// Inverse of `setup_move_rank`. `None` if `rank >= NUM_SETUP_MOVES`.
+pub fn setup_move_unrank(color: Color, mut rank: u32) -> Option<SetupMove> {
+    if rank >= NUM_SETUP_MOVES {
+        return None;
+    }
+    let mut counts = EnumMap::from_fn(Piece::initial_count);
+    let mut pieces = [Piece::Alfil; SetupMove::SIZE];
+    for square_piece in &mut pieces {
+        for piece in Piece::all() {
+            if counts[piece] == 0 {
+                continue;
+            }
+            counts[piece] -= 1;
+            let n = num_arrangements(&counts);
+            if rank < n {
+                *square_piece = piece;
+                break;
+            }
+            rank -= n;
+            counts[piece] += 1;
+        }
+    }
+    Some(SetupMove { color, pieces })
+}
+
+/// Number of distinct orderings of a multiset of pieces.
+fn num_arrangements(counts: &EnumMap<Piece, usize>) -> u32 {
+    fn factorial(n: usize) -> u64 {
+        (1..=n as u64).product()
+    }
+    let total: usize = counts.iter().map(|(_, &count)| count).sum();
+    let arrangements = Piece::all().fold(factorial(total), |acc, piece| {
+        acc / factorial(counts[piece])
+    });
+    arrangements.try_into().unwrap()
+}
+
 #[derive(Debug)]
 struct SetupMoveIterator {
     color: Color,
@@ -232,6 +359,30 @@ pub fn attacked_by(position: &Position, square: Square, color: Color) -> Bitboar
     res
 }
 
+/// All squares attacked by `color`: the union of `attacked_by` over the whole board.
+pub fn attack_map(position: &Position, color: Color) -> Bitboard {
+    let mut res = Bitboard::EMPTY;
+    for piece in Piece::all() {
+        for square in position.occupied_by_piece(piece.with_color(color)) {
+            res |= move_bitboard(piece, square);
+        }
+    }
+    res
+}
+
+/// Number of `color` pieces attacking each square.
+pub fn attack_counts(position: &Position, color: Color) -> EnumMap<Square, u8> {
+    let mut res = EnumMap::from_fn(|_| 0);
+    for piece in Piece::all() {
+        for square in position.occupied_by_piece(piece.with_color(color)) {
+            for target in move_bitboard(piece, square) {
+                res[target] += 1;
+            }
+        }
+    }
+    res
+}
+
 pub fn is_attacked_by(position: &Position, square: Square, color: Color) -> bool {
     !attacked_by(position, square, color).is_empty()
 }
@@ -243,14 +394,67 @@ pub fn in_check(position: &Position, color: Color) -> bool {
     is_attacked_by(position, wazir_square, color.opposite())
 }
 
+/// Whether `mov` attacks the opponent's wazir. `mov` must be valid in `position`.
+pub fn gives_check(position: &Position, mov: Move) -> bool {
+    let opp = position.to_move().opposite();
+    let new_position = position.make_move(mov).expect("Invalid move");
+    in_check(&new_position, opp)
+}
+
+/// `pseudomoves` in the regular stage, `setup_moves` in the setup stage.
+///
+/// Panics at `Stage::End`.
 pub fn any_pseudomoves<'a>(position: &'a Position) -> impl Iterator<Item = AnyMove> + 'a {
     match position.stage() {
-        Stage::Setup => Either::Case0(setup_moves(position.to_move()).map(AnyMove::Setup)),
-        Stage::Regular => Either::Case1(pseudomoves(position).map(AnyMove::Regular)),
+        Stage::Setup => Either::Case0(setup_moves(position.to_move()).map(AnyMove::from)),
+        Stage::Regular => Either::Case1(pseudomoves(position).map(AnyMove::from)),
         Stage::End(_) => panic!("End of game"),
     }
 }
 
+/// Same as `legal_moves`, named to match `any_pseudomoves`.
+pub fn any_legal_moves<'a>(position: &'a Position) -> impl Iterator<Item = AnyMove> + 'a {
+    legal_moves(position)
+}
+
+/// All legal moves in any stage: `setup_moves` in the setup stage, `moves` in the regular stage.
+///
+/// Like `moves`, never yields a suicide, so it is empty in the regular stage when every move loses
+/// the wazir. Panics at `Stage::End`, like `any_pseudomoves`.
+pub fn legal_moves<'a>(position: &'a Position) -> impl Iterator<Item = AnyMove> + 'a {
+    match position.stage() {
+        Stage::Setup => Either::Case0(setup_moves(position.to_move()).map(AnyMove::from)),
+        Stage::Regular => Either::Case1(moves(position).map(AnyMove::from)),
+        Stage::End(_) => panic!("End of game"),
+    }
+}
+
+/// Number of move sequences of length `depth` following `legal_moves`, for testing move
+/// generation.
+///
+/// Games end at `Stage::End`, so sequences that end the game early are not counted.
+pub fn perft(position: &Position, depth: u32) -> u64 {
+    match depth {
+        0 => 1,
+        _ if matches!(position.stage(), Stage::End(_)) => 0,
+        1 => legal_moves(position).count() as u64,
+        _ => legal_moves(position)
+            .map(|mov| perft(&position.make_any_move(mov).unwrap(), depth - 1))
+            .sum(),
+    }
+}
+
+/// `perft` split by the first move. `depth` must be at least 1 and the game must not be over.
+pub fn perft_divide(position: &Position, depth: u32) -> Vec<(AnyMove, u64)> {
+    assert!(depth >= 1);
+    legal_moves(position)
+        .map(|mov| {
+            let count = perft(&position.make_any_move(mov).unwrap(), depth - 1);
+            (mov, count)
+        })
+        .collect()
+}
+
 /// Generate all pseudomoves.
 /// Includes non-escapes and suicides.
 pub fn pseudomoves<'a>(position: &'a Position) -> impl Iterator<Item = Move> + 'a {
@@ -260,6 +464,8 @@ pub fn pseudomoves<'a>(position: &'a Position) -> impl Iterator<Item = Move> + '
 }
 
 /// Generate all moves except suicides.
+///
+/// Regular stage only. `legal_moves` also covers the setup stage.
 pub fn moves<'a>(position: &'a Position) -> impl Iterator<Item = Move> + 'a {
     if in_check(position, position.to_move()) {
         Either::Case0(check_evasions(position))
@@ -322,11 +528,16 @@ pub fn captures_non_checks<'a>(position: &'a Position) -> impl Iterator<Item = M
         .chain(captures_by_wazir(position))
 }
 
-/// Generates all captures by the wazir.
+/// Generates all captures by the wazir that are not suicides, except capturing the opponent's
+/// wazir.
+///
+/// The wazirs can only be adjacent in check, where `check_evasions_capture_attacker` generates the
+/// wazir-wazir capture.
 pub fn captures_by_wazir<'a>(position: &'a Position) -> impl Iterator<Item = Move> + 'a {
     let opp = position.to_move().opposite();
-    pseudocaptures_by_piece(position, Piece::Wazir)
-        .filter(move |mov| !is_attacked_by(position, mov.to, opp))
+    pseudocaptures_by_piece(position, Piece::Wazir).filter(move |mov| {
+        mov.captured != Some(Piece::Wazir) && !is_attacked_by(position, mov.to, opp)
+    })
 }
 
 fn pseudocaptures_by_piece<'a>(
@@ -367,8 +578,102 @@ pub fn captures_of_wazir<'a>(position: &'a Position) -> impl Iterator<Item = Mov
     pseudocaptures_of_square(position, wazir_square)
 }
 
+/// A capture of the opponent's wazir, which wins immediately.
+///
+/// `None` if there isn't one or the position isn't in the regular stage. The search plays the
+/// first of `captures_of_wazir` without searching, and this returns the same move.
+pub fn winning_capture(position: &Position) -> Option<Move> {
+    if position.stage() != Stage::Regular {
+        return None;
+    }
+    captures_of_wazir(position).next()
+}
+
+/// `capture_race` result when the side to move captures the opponent's wazir.
+pub const CAPTURE_RACE_WIN: i32 = 1000;
+
+/// Material outcome for the side to move of both sides trading on `square`.
+///
+/// Each side captures with its least valuable attacker or stops. All pieces are leapers, so the
+/// attackers are known up front. Capturing the wazir ends the game with `CAPTURE_RACE_WIN`, so a
+/// wazir never recaptures onto a defended square. Returns 0 unless `square` holds an opponent
+/// piece in the regular stage.
+pub fn capture_race(position: &Position, square: Square) -> i32 {
+    if position.stage() != Stage::Regular {
+        return 0;
+    }
+    let me = position.to_move();
+    let Some(target) = position.square(square) else {
+        return 0;
+    };
+    if target.color() == me {
+        return 0;
+    }
+    let attackers = sorted_attackers(position, square);
+    capture_race_from(target.piece(), &attackers, EnumMap::from_fn(|_| 0), me)
+}
+
+/// Static exchange evaluation: material outcome of `mov` for the side to move, if both sides
+/// then trade on the target square as in `capture_race`.
+///
+/// Unlike `capture_race`, the first capture is forced, so the result is negative if `mov` loses
+/// material. Returns 0 if `mov` isn't a capture. `mov` must be valid in `position`.
+pub fn see(position: &Position, mov: Move) -> i32 {
+    let Some(captured) = mov.captured else {
+        return 0;
+    };
+    if captured == Piece::Wazir {
+        return CAPTURE_RACE_WIN;
+    }
+    let me = position.to_move();
+    let piece = mov.colored_piece.piece();
+    let mut attackers = sorted_attackers(position, mov.to);
+    // The moving piece is used up first. Pieces of the same kind are interchangeable.
+    let index = attackers[me]
+        .iter()
+        .position(|&attacker| attacker == piece)
+        .expect("Not an attacker");
+    attackers[me][index..].rotate_left(1);
+    _ = attackers[me].pop();
+    captured.exchange_value()
+        - capture_race_from(piece, &attackers, EnumMap::from_fn(|_| 0), me.opposite())
+}
+
+/// Pieces of each color attacking `square`, least valuable first and the wazir last.
+fn sorted_attackers(position: &Position, square: Square) -> EnumMap<Color, SmallVec<Piece, 16>> {
+    EnumMap::from_fn(|color| {
+        let mut pieces: SmallVec<Piece, 16> = attacked_by(position, square, color)
+            .into_iter()
+            .map(|sq| position.square(sq).unwrap().piece())
+            .collect();
+        pieces.sort_by_key(|&piece| (piece == Piece::Wazir, piece.exchange_value()));
+        pieces
+    })
+}
+
+fn capture_race_from(
+    target: Piece,
+    attackers: &EnumMap<Color, SmallVec<Piece, 16>>,
+    used: EnumMap<Color, usize>,
+    side: Color,
+) -> i32 {
+    let Some(&attacker) = attackers[side].get(used[side]) else {
+        return 0;
+    };
+    if target == Piece::Wazir {
+        return CAPTURE_RACE_WIN;
+    }
+    let mut used = used;
+    used[side] += 1;
+    let gain =
+        target.exchange_value() - capture_race_from(attacker, attackers, used, side.opposite());
+    gain.max(0)
+}
+
 // Must be in check.
-// Generates all captures that capture the checking piece.
+// Generates all captures of the checking piece by pieces other than the wazir, and the capture of
+// the opponent's wazir by the wazir. Other captures by the wazir are in `captures_by_wazir`, which
+// also makes sure the wazir is safe afterwards.
 pub fn check_evasions_capture_attacker<'a>(
     position: &'a Position,
 ) -> impl Iterator<Item = Move> + 'a {
@@ -382,11 +687,23 @@ pub fn check_evasions_capture_attacker<'a>(
         // checked by multiple pieces
         only_checked_by = None;
     }
+    // Wazir-wazir capture wins the game, so it's fine even in double check or if the opponent's
+    // wazir is defended.
+    let wazir_capture = position
+        .wazir_square(opp)
+        .filter(|&to| checked_by.contains(to))
+        .map(|to| Move {
+            colored_piece: Piece::Wazir.with_color(me),
+            from: Some(wazir_square),
+            captured: Some(Piece::Wazir),
+            to,
+        });
     // It's OK to use pseudocaptures here because there is only one attacker.
-    // Wazir-wazir capture is fine.
     only_checked_by
         .into_iter()
         .flat_map(move |to| pseudocaptures_of_square(position, to))
+        .filter(|mov| mov.colored_piece.piece() != Piece::Wazir)
+        .chain(wazir_capture)
 }
 
 // Generate all captures of a piece on a square.
@@ -577,7 +894,7 @@ fn drops_piece_to_mask<'a>(
     let me = position.to_move();
     let colored_piece = piece.with_color(me);
     let targets = if position.num_captured(colored_piece) > 0 {
-        position.empty_squares() & to_mask
+        position.empty_squares() & drop_bitboard(colored_piece) & to_mask
     } else {
         Bitboard::EMPTY
     };
@@ -1,8 +1,8 @@
 use crate::{
     enums::EnumMap,
-    impl_from_str_for_parsable,
+    impl_from_str_for_parsable, movegen,
     parser::{self, ParseError, Parser, ParserExt},
-    Color, ColoredPiece, Piece, Square,
+    Color, ColoredPiece, Coord, Piece, Position, Square, Symmetry,
 };
 use std::{
     array,
@@ -20,10 +20,21 @@ pub struct SetupMove {
     pub pieces: [Piece; Self::SIZE],
 }
 
+// Setup fills the first two rows.
+const _: () = assert!(SetupMove::SIZE == 2 * Coord::WIDTH);
+const _: () = assert!(
+    Piece::Alfil.initial_count()
+        + Piece::Dabbaba.initial_count()
+        + Piece::Ferz.initial_count()
+        + Piece::Knight.initial_count()
+        + Piece::Wazir.initial_count()
+        == SetupMove::SIZE
+);
+
 impl SetupMove {
     pub const SIZE: usize = 16;
 
-    fn parser() -> impl Parser<Output = Self> {
+    pub fn parser() -> impl Parser<Output = Self> {
         ColoredPiece::parser()
             .repeat(SetupMove::SIZE..=SetupMove::SIZE)
             .try_map(|colored_pieces| {
@@ -56,12 +67,57 @@ impl SetupMove {
         Ok(())
     }
 
+    /// Whether it has the right number of each piece.
+    pub fn is_valid(&self) -> bool {
+        self.validate_pieces().is_ok()
+    }
+
+    /// The equivalent setup with the wazir on the left half, and the symmetry that maps `self`
+    /// to it. Works for either color.
+    ///
+    /// Panics if there is no wazir.
+    pub fn normalize(self) -> (Symmetry, SetupMove) {
+        let wazir_index = self
+            .pieces
+            .iter()
+            .position(|&piece| piece == Piece::Wazir)
+            .expect("Invalid setup");
+        let wazir_coord = Coord::from_square(Square::from_index(wazir_index));
+        let symmetry = if wazir_coord.x() < Coord::WIDTH / 2 {
+            Symmetry::Identity
+        } else {
+            Symmetry::FlipX
+        };
+        (symmetry, symmetry.apply_to_setup(self))
+    }
+
+    /// All setups equivalent to `self` by symmetry, starting with `self`.
+    pub fn symmetric_variants(self) -> impl Iterator<Item = SetupMove> {
+        Symmetry::SETUP_SYMMETRIES
+            .into_iter()
+            .map(move |symmetry| symmetry.apply_to_setup(self))
+    }
+
     pub fn with_color(self, color: Color) -> Self {
         Self {
             color,
             pieces: self.pieces,
         }
     }
+
+    /// Index in `movegen::setup_moves`, in `0..movegen::NUM_SETUP_MOVES`.
+    ///
+    /// The pieces must be valid.
+    pub fn rank(self) -> u32 {
+        movegen::setup_move_rank(self)
+    }
+
+    /// Inverse of `rank`.
+    ///
+    /// Panics if `rank >= movegen::NUM_SETUP_MOVES`.
+    pub fn from_rank(color: Color, rank: u32) -> Self {
+        movegen::setup_move_unrank(color, rank).expect("Setup move rank out of range")
+    }
 }
 
 impl_from_str_for_parsable!(SetupMove);
@@ -95,22 +151,47 @@ pub struct Move {
 const _: () = assert!(mem::size_of::<Move>() == 4);
 
 impl Move {
+    /// Whether no position before the move can occur again after it.
+    ///
+    /// Always false: captured pieces come back into play as drops, so even after a capture or a
+    /// drop every earlier position of the regular stage can repeat. Only setup moves are
+    /// irreversible, see `AnyMove::is_irreversible`.
+    pub fn is_irreversible(self) -> bool {
+        false
+    }
+
+    /// Notation with `+` for check and `#` for capturing the wazir.
+    ///
+    /// `position` is the position before the move. Panics if the move is invalid.
+    pub fn to_notation(self, position: &Position) -> String {
+        let suffix = if self.captured == Some(Piece::Wazir) {
+            "#"
+        } else if movegen::gives_check(position, self) {
+            "+"
+        } else {
+            ""
+        };
+        format!("{self}{suffix}")
+    }
+
     pub fn parser() -> impl Parser<Output = Self> {
         ColoredPiece::parser()
             .and_then(move |cpiece| {
-                parser::exact(b"@")
-                    .map(|_| (None, None)) // (from, captured)
-                    .or(Square::parser().map(Some).and(
-                        parser::exact(b"-").map(|_| None).or(parser::exact(b"x")
+                parser::any_of![
+                    parser::exact(b"@").map(|_| (None, None)), // (from, captured)
+                    Square::parser().map(Some).and(parser::any_of![
+                        parser::exact(b"-").map(|_| None),
+                        parser::exact(b"x")
                             .ignore_then(ColoredPiece::parser())
                             .try_map(move |cpiece2| {
                                 if cpiece2.color() != cpiece.color().opposite() {
                                     return Err(ParseError);
                                 }
                                 Ok(Some(cpiece2.piece()))
-                            })),
-                    ))
-                    .map(move |(from, captured)| (cpiece, from, captured))
+                            }),
+                    ]),
+                ]
+                .map(move |(from, captured)| (cpiece, from, captured))
             })
             .and(Square::parser())
             .map(|((colored_piece, from, captured), to)| Move {
@@ -153,6 +234,31 @@ impl AnyMove {
             .map(AnyMove::from)
             .or(Move::parser().map(AnyMove::from))
     }
+
+    pub fn is_setup(self) -> bool {
+        matches!(self, AnyMove::Setup(_))
+    }
+
+    pub fn is_regular(self) -> bool {
+        matches!(self, AnyMove::Regular(_))
+    }
+
+    /// Whether no position before the move can occur again after it, so that repetition
+    /// detection can start over. True for setup moves only, see `Move::is_irreversible`.
+    pub fn is_irreversible(self) -> bool {
+        match self {
+            AnyMove::Setup(_) => true,
+            AnyMove::Regular(mov) => mov.is_irreversible(),
+        }
+    }
+
+    /// The color of the player making the move.
+    pub fn color(self) -> Color {
+        match self {
+            AnyMove::Setup(mov) => mov.color,
+            AnyMove::Regular(mov) => mov.colored_piece.color(),
+        }
+    }
 }
 
 impl_from_str_for_parsable!(AnyMove);
@@ -4,7 +4,7 @@ use crate::{
     enums::EnumMap,
     nnue_weights::{EMBEDDING_SIZE, HIDDEN_SIZES, HIDDEN_WEIGHT_BITS, SCALE, WEIGHTS},
     vector::{crelu16, crelu32, dot_product, mul_add, vector_concat, Vector16, Vector32, Vector8},
-    Color, Evaluator, Features, WPSFeatures,
+    weights_version, Color, Evaluator, Features, WPSFeatures,
 };
 use std::array;
 
@@ -18,6 +18,7 @@ const fn exact_div(a: usize, b: usize) -> usize {
 type EmbeddingVector = Vector16<{ exact_div(EMBEDDING_SIZE, 8) }>;
 
 pub struct Nnue {
+    version: u64,
     features: WPSFeatures,
     embedding_weights: Vec<EmbeddingVector>,
     embedding_bias: EmbeddingVector,
@@ -69,6 +70,7 @@ impl Nnue {
         decoder.finish();
 
         Self {
+            version: weights_version(WEIGHTS.bytes()),
             features,
             embedding_weights,
             embedding_bias,
@@ -81,6 +83,48 @@ impl Nnue {
         }
     }
 
+    /// Decodes the compiled weights layer by layer as plain integers, for inspection.
+    ///
+    /// Follows the same layout as [`Nnue::new`].
+    pub fn layers() -> Vec<NnueLayer> {
+        let mut decoder = Base128Decoder::new(WEIGHTS);
+        let mut layer = |name, rows: usize, columns: usize, bias_len: usize| {
+            let weights = (0..rows * columns)
+                .map(|_| decoder.decode_varint())
+                .collect();
+            let bias = (0..bias_len).map(|_| decoder.decode_varint()).collect();
+            NnueLayer {
+                name,
+                shape: [rows, columns],
+                weights,
+                bias,
+            }
+        };
+        let layers = vec![
+            layer(
+                "embedding",
+                WPSFeatures.count(),
+                EMBEDDING_SIZE,
+                EMBEDDING_SIZE,
+            ),
+            layer(
+                "hidden_0",
+                HIDDEN_SIZES[0],
+                2 * EMBEDDING_SIZE,
+                HIDDEN_SIZES[0],
+            ),
+            layer(
+                "hidden_1",
+                HIDDEN_SIZES[1],
+                HIDDEN_SIZES[0],
+                HIDDEN_SIZES[1],
+            ),
+            layer("final", 1, HIDDEN_SIZES[1], 1),
+        ];
+        decoder.finish();
+        layers
+    }
+
     fn decode_vector8<const N: usize, const N16: usize>(
         decoder: &mut Base128Decoder,
     ) -> Vector8<N16> {
@@ -157,4 +201,64 @@ impl Evaluator for Nnue {
     fn scale(&self) -> f64 {
         SCALE
     }
+
+    fn name(&self) -> &str {
+        "nnue"
+    }
+
+    fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+/// Raw quantized weights of one layer of the compiled net.
+#[derive(Debug, Clone)]
+pub struct NnueLayer {
+    pub name: &'static str,
+    /// `[rows, columns]` of `weights` in storage order: one row per feature for the embedding,
+    /// one row per output for the other layers.
+    pub shape: [usize; 2],
+    pub weights: Vec<i32>,
+    pub bias: Vec<i32>,
+}
+
+/// Summary of a list of weights.
+#[derive(Debug, Clone)]
+pub struct WeightStats {
+    pub min: i32,
+    pub max: i32,
+    pub mean: f64,
+    /// Counts in equal-width buckets covering `min..=max`.
+    pub histogram: Vec<usize>,
+}
+
+impl WeightStats {
+    /// `None` if `weights` is empty.
+    pub fn new(weights: &[i32], num_buckets: usize) -> Option<Self> {
+        assert!(num_buckets > 0);
+        let min = *weights.iter().min()?;
+        let max = *weights.iter().max()?;
+        let mean = weights.iter().map(|&w| f64::from(w)).sum::<f64>() / weights.len() as f64;
+        let mut histogram = vec![0; num_buckets];
+        for &w in weights {
+            histogram[Self::bucket(w, min, max, num_buckets)] += 1;
+        }
+        Some(Self {
+            min,
+            max,
+            mean,
+            histogram,
+        })
+    }
+
+    /// Smallest weight that falls into `bucket`.
+    pub fn bucket_start(&self, bucket: usize) -> f64 {
+        let width = f64::from(self.max - self.min + 1) / self.histogram.len() as f64;
+        f64::from(self.min) + width * bucket as f64
+    }
+
+    fn bucket(w: i32, min: i32, max: i32, num_buckets: usize) -> usize {
+        let range = i64::from(max) - i64::from(min) + 1;
+        ((i64::from(w) - i64::from(min)) * num_buckets as i64 / range) as usize
+    }
 }
@@ -176,6 +176,11 @@ pub fn endl() -> impl Parser<Output = ()> {
     exact(b"\n")
 }
 
+/// End of line, or end of input for a last line without a newline.
+pub fn endl_or_end() -> impl Parser<Output = ()> {
+    endl().or(end())
+}
+
 pub fn u8() -> impl Parser<Output = u8> {
     byte()
         .try_map(|b| match b {
@@ -264,6 +269,27 @@ impl<P1: Parser, P2: Parser<Output = P1::Output>> Parser for Or<P1, P2> {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+struct FromFn<F> {
+    f: F,
+}
+
+impl<T, F: for<'a> Fn(&'a [u8]) -> ParseResult<'a, T>> Parser for FromFn<F> {
+    type Output = T;
+
+    fn parse<'a>(&self, input: &'a [u8]) -> ParseResult<'a, T> {
+        (self.f)(input)
+    }
+}
+
+/// A parser that calls `f`.
+pub fn from_fn<T, F>(f: F) -> impl Parser<Output = T>
+where
+    F: for<'a> Fn(&'a [u8]) -> ParseResult<'a, T>,
+{
+    FromFn { f }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct TryMap<P: Parser, F> {
     parser: P,
@@ -315,6 +341,27 @@ impl<P: Parser> Parser for Repeat<P> {
     }
 }
 
+/// Tries the parsers in order, the first successful match wins. Same as chaining them with
+/// `ParserExt::or`, but flat.
+///
+/// Each parser expression is only evaluated when its turn comes, so later alternatives are not
+/// constructed if an earlier one matches.
+#[macro_export]
+macro_rules! any_of {
+    ($($parser:expr),+ $(,)?) => {
+        $crate::parser::from_fn(move |input| {
+            $(
+                if let Ok(success) = $crate::parser::Parser::parse(&$parser, input) {
+                    return Ok(success);
+                }
+            )+
+            Err($crate::parser::ParseError)
+        })
+    };
+}
+
+pub use any_of;
+
 #[macro_export]
 macro_rules! impl_from_str_for_parsable {
     ($type:ty) => {
@@ -28,6 +28,19 @@ impl Piece {
         }
     }
 
+    /// Rough material value used when resolving exchanges.
+    ///
+    /// The wazir has no value here: capturing it ends the game.
+    pub const fn exchange_value(self) -> i32 {
+        match self {
+            Self::Alfil => 1,
+            Self::Dabbaba => 1,
+            Self::Ferz => 2,
+            Self::Knight => 3,
+            Self::Wazir => 0,
+        }
+    }
+
     pub const fn total_count(self) -> usize {
         Color::COUNT * self.initial_count()
     }
@@ -1,19 +1,81 @@
-use crate::{clock::Timer, AnyMove, Color, Position};
-use std::time::Duration;
+use crate::{
+    clock::{TimeControl, Timer},
+    constants::Depth,
+    AnyMove, Color, Position, Score,
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
 
 /// It can play a single game.
 pub trait Player {
     fn opponent_move(&mut self, _position: &Position, _mov: AnyMove, _timer: &Timer) {}
     fn make_move(&mut self, position: &Position, timer: &Timer) -> AnyMove;
+
+    /// Information about the search for the last move, if there was a search.
+    fn last_search_info(&self) -> Option<SearchInfo> {
+        None
+    }
+
+    /// Starts thinking on the opponent's time. `position` is after the player's own move.
+    ///
+    /// Pondering stops at the next `opponent_move` or `stop_ponder`. Does nothing by default.
+    fn start_ponder(&mut self, _position: &Position) {}
+
+    fn stop_ponder(&mut self) {}
+}
+
+/// Displayed as key=value fields on one line:
+/// `depth=400 score=12 nodes=12345 time=100 pv=Aa3-c5,ah7-f5`
+///
+/// Time is in milliseconds. Score is from the point of view of the player to move, with mates
+/// counted from the searched position.
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub depth: Depth,
+    pub score: Score,
+    pub nodes: u64,
+    pub time: Duration,
+    pub pv: Vec<AnyMove>,
+}
+
+impl Display for SearchInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "depth={} score={} nodes={} time={} pv=",
+            self.depth,
+            self.score,
+            self.nodes,
+            self.time.as_millis()
+        )?;
+        for (index, mov) in self.pv.iter().enumerate() {
+            if index != 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{mov}")?;
+        }
+        Ok(())
+    }
 }
 
 /// It can create players.
 pub trait PlayerFactory: Send + Sync {
+    /// The game starts at `start`, usually `Position::initial()`, followed by the `opening`
+    /// moves. `time_control` is `None` if the game is not timed.
     fn create(
         &self,
         game_id: &str,
         color: Color,
+        start: &Position,
         opening: &[AnyMove],
-        time_limit: Option<Duration>,
+        time_control: Option<TimeControl>,
     ) -> Box<dyn Player>;
+
+    /// Identifies what plays, e.g. the evaluator and its weights version. Reported by the CLI
+    /// `Version` command.
+    fn version(&self) -> String {
+        String::new()
+    }
 }
@@ -18,10 +18,11 @@ pub enum Outcome {
 
 impl Outcome {
     pub fn parser() -> impl Parser<Output = Self> {
-        parser::exact(b"red_win")
-            .map(|_| Self::RedWin)
-            .or(parser::exact(b"draw").map(|_| Self::Draw))
-            .or(parser::exact(b"blue_win").map(|_| Self::BlueWin))
+        parser::any_of![
+            parser::exact(b"red_win").map(|_| Self::RedWin),
+            parser::exact(b"draw").map(|_| Self::Draw),
+            parser::exact(b"blue_win").map(|_| Self::BlueWin),
+        ]
     }
 
     pub fn win(color: Color) -> Self {
@@ -63,6 +64,42 @@ impl Display for Outcome {
     }
 }
 
+/// Why a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndReason {
+    WazirCaptured,
+    MoveLimit,
+    Resignation,
+    TimeForfeit,
+    Adjudication,
+}
+
+impl EndReason {
+    pub fn parser() -> impl Parser<Output = Self> {
+        parser::any_of![
+            parser::exact(b"wazir_captured").map(|_| Self::WazirCaptured),
+            parser::exact(b"move_limit").map(|_| Self::MoveLimit),
+            parser::exact(b"resignation").map(|_| Self::Resignation),
+            parser::exact(b"time_forfeit").map(|_| Self::TimeForfeit),
+            parser::exact(b"adjudication").map(|_| Self::Adjudication),
+        ]
+    }
+}
+
+impl_from_str_for_parsable!(EndReason);
+
+impl Display for EndReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EndReason::WazirCaptured => write!(f, "wazir_captured"),
+            EndReason::MoveLimit => write!(f, "move_limit"),
+            EndReason::Resignation => write!(f, "resignation"),
+            EndReason::TimeForfeit => write!(f, "time_forfeit"),
+            EndReason::Adjudication => write!(f, "adjudication"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Stage {
     Setup,
@@ -72,12 +109,13 @@ pub enum Stage {
 
 impl Stage {
     fn parser() -> impl Parser<Output = Self> {
-        parser::exact(b"setup")
-            .map(|_| Stage::Setup)
-            .or(parser::exact(b"regular").map(|_| Stage::Regular))
-            .or(parser::exact(b"end ")
+        parser::any_of![
+            parser::exact(b"setup").map(|_| Stage::Setup),
+            parser::exact(b"regular").map(|_| Stage::Regular),
+            parser::exact(b"end ")
                 .ignore_then(Outcome::parser())
-                .map(Stage::End))
+                .map(Stage::End),
+        ]
     }
 }
 
@@ -93,6 +131,18 @@ impl Display for Stage {
     }
 }
 
+/// What a move changed, as returned by `Position::make_any_move_with_effect`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MoveEffect {
+    /// The stage before the move, if the move changed it.
+    pub previous_stage: Option<Stage>,
+    pub captured: Option<Piece>,
+    /// Set if the move ended the game.
+    pub outcome: Option<Outcome>,
+    /// No earlier position can repeat after the move, see `History::push_position_irreversible`.
+    pub irreversible: bool,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Position {
     stage: Stage,
@@ -100,6 +150,7 @@ pub struct Position {
     board: Board,
     captured: Captured,
     null_move_counter: u8,
+    ply_draw: Ply,
 }
 
 impl Position {
@@ -110,9 +161,25 @@ impl Position {
             board: Board::empty(),
             captured: Captured::new(),
             null_move_counter: 0,
+            ply_draw: PLY_DRAW,
         }
     }
 
+    /// Initial position of a game that is drawn at `ply_draw` rather than `PLY_DRAW`.
+    pub fn initial_with_ply_draw(ply_draw: Ply) -> Result<Self, Invalid> {
+        Self::initial().with_ply_draw(ply_draw)
+    }
+
+    /// The same position in a game that is drawn at `ply_draw`.
+    ///
+    /// `ply_draw` must be after setup and at most `PLY_DRAW`.
+    pub fn with_ply_draw(&self, ply_draw: Ply) -> Result<Self, Invalid> {
+        let mut position =
+            Self::from_parts(self.stage, self.ply, self.board, self.captured, ply_draw)?;
+        position.null_move_counter = self.null_move_counter;
+        Ok(position)
+    }
+
     pub fn stage(&self) -> Stage {
         self.stage
     }
@@ -121,6 +188,20 @@ impl Position {
         self.ply
     }
 
+    /// The ply at which the game is drawn.
+    pub fn ply_draw(&self) -> Ply {
+        self.ply_draw
+    }
+
+    /// The outcome and why the game ended, if it ended on the board.
+    pub fn end(&self) -> Option<(Outcome, EndReason)> {
+        match self.stage {
+            Stage::End(Outcome::Draw) => Some((Outcome::Draw, EndReason::MoveLimit)),
+            Stage::End(outcome) => Some((outcome, EndReason::WazirCaptured)),
+            _ => None,
+        }
+    }
+
     pub fn to_move(&self) -> Color {
         Color::from_index(usize::from(self.ply()) % Color::COUNT)
     }
@@ -149,15 +230,76 @@ impl Position {
         self.captured.get(cpiece)
     }
 
+    /// Total `Piece::exchange_value` of `color`'s pieces, on the board and captured.
+    pub fn material(&self, color: Color) -> i32 {
+        Piece::all_non_wazir()
+            .map(|piece| {
+                let cpiece = piece.with_color(color);
+                let count = self.occupied_by_piece(cpiece).count() + self.num_captured(cpiece);
+                count as i32 * piece.exchange_value()
+            })
+            .sum()
+    }
+
+    /// `material` of the side to move minus that of the opponent.
+    pub fn material_balance(&self) -> i32 {
+        let me = self.to_move();
+        self.material(me) - self.material(me.opposite())
+    }
+
+    /// A one-line summary for logs, e.g. "Regular, Red to move, +2 material, in check, move 17".
+    ///
+    /// Moves are numbered from 1 and include both colors' plies, setup included.
+    pub fn describe(&self) -> String {
+        let color = match self.to_move() {
+            Color::Red => "Red",
+            Color::Blue => "Blue",
+        };
+        let mut description = match self.stage {
+            Stage::Setup => format!("Setup, {color} to move"),
+            Stage::Regular => {
+                let balance = self.material_balance();
+                let mut description = format!("Regular, {color} to move, {balance:+} material");
+                if movegen::in_check(self, self.to_move()) {
+                    description += ", in check";
+                }
+                description
+            }
+            Stage::End(Outcome::RedWin) => "Red won".to_owned(),
+            Stage::End(Outcome::Draw) => "Drawn".to_owned(),
+            Stage::End(Outcome::BlueWin) => "Blue won".to_owned(),
+        };
+        description += &format!(", move {}", usize::from(self.ply) / 2 + 1);
+        description
+    }
+
     pub fn hash(&self) -> u64 {
         let mut res = self.hash_for_repetition();
-        let ply = self.ply();
+        // Distance to the draw matters, so shift the ply as if the game ended at `PLY_DRAW`.
+        let ply = self.ply() + (PLY_DRAW - self.ply_draw);
         if ply >= PLY_DRAWISH {
-            res ^= zobrist::PLY[usize::from(ply)];
+            res ^= zobrist::ply(ply);
         }
         res
     }
 
+    /// Like `hash`, but the same for positions that are mirror images left to right.
+    ///
+    /// The rules are symmetric under `Symmetry::FlipX`, so such positions are equivalent.
+    pub fn canonical_hash(&self) -> u64 {
+        let mut flipped = *self;
+        flipped.board = Board::empty();
+        for square in Square::all() {
+            if let Some(cpiece) = self.board.square(square) {
+                flipped
+                    .board
+                    .place_piece(Symmetry::FlipX.apply(square), cpiece)
+                    .unwrap();
+            }
+        }
+        self.hash().min(flipped.hash())
+    }
+
     pub fn hash_for_repetition(&self) -> u64 {
         self.hash_for_repetition_ignoring_captured() ^ self.captured.hash()
     }
@@ -165,30 +307,113 @@ impl Position {
     pub fn hash_for_repetition_ignoring_captured(&self) -> u64 {
         // There is a collision because we ignore `stage`. Setup with blue on move may look identical as a red win.
         // We ignore it, it's rare and harmless.
-        zobrist::TO_MOVE[self.to_move()]
+        zobrist::to_move(self.to_move())
             ^ self.board.hash()
-            ^ zobrist::NULL_MOVE_COUNTER[usize::from(self.null_move_counter)]
+            ^ zobrist::null_move_counter(self.null_move_counter)
+    }
+
+    /// Whether the positions have the same pieces on the board and captured, the same side to
+    /// move and the same number of null moves, however many moves it took to get there.
+    ///
+    /// Equal positions have equal `hash_for_repetition`. Like that hash, it ignores `stage`, so
+    /// setup with blue to move can look identical to a red win.
+    pub fn same_material_and_board(&self, other: &Position) -> bool {
+        self.to_move() == other.to_move()
+            && self.null_move_counter == other.null_move_counter
+            && Square::all().all(|square| self.square(square) == other.square(square))
+            && ColoredPiece::all()
+                .all(|cpiece| self.num_captured(cpiece) == other.num_captured(cpiece))
     }
 
     pub fn parser() -> impl Parser<Output = Self> {
         Stage::parser()
             .then_ignore(parser::endl())
-            .and(parser::u8())
+            .and(ply_parser())
             .then_ignore(parser::endl())
             .and(Captured::parser())
             .then_ignore(parser::endl())
             .and(Board::parser())
-            .try_map(|(((stage, ply), captured), board)| {
-                Self::from_parts(stage, ply, board, captured).map_err(|_| ParseError)
+            .try_map(|(((stage, (ply, ply_draw)), captured), board)| {
+                Self::from_parts(stage, ply, board, captured, ply_draw).map_err(|_| ParseError)
+            })
+    }
+
+    /// A single-line format: stage, ply, captured pieces (`-` if none) and `Board::to_fen`,
+    /// separated by spaces, e.g. `regular 4 Af 2W5/...`.
+    ///
+    /// Like `Display`, it writes the ply as `ply/ply_draw` if `ply_draw` isn't `PLY_DRAW`, and
+    /// doesn't record null moves.
+    pub fn to_fen(&self) -> String {
+        let captured = self.captured.to_string();
+        format!(
+            "{stage} {ply} {captured} {board}",
+            stage = self.stage,
+            ply = self.display_ply(),
+            captured = if captured.is_empty() { "-" } else { &captured },
+            board = self.board.to_fen(),
+        )
+    }
+
+    /// The ply, followed by `/` and `ply_draw` if it isn't `PLY_DRAW`.
+    fn display_ply(&self) -> String {
+        if self.ply_draw == PLY_DRAW {
+            self.ply.to_string()
+        } else {
+            format!("{}/{}", self.ply, self.ply_draw)
+        }
+    }
+
+    /// Parses `to_fen`, rejecting the same invalid positions as `parser`.
+    pub fn from_fen(fen: &str) -> Result<Self, ParseError> {
+        Self::fen_parser().parse_all(fen.as_bytes())
+    }
+
+    pub fn fen_parser() -> impl Parser<Output = Self> {
+        Stage::parser()
+            .then_ignore(parser::exact(b" "))
+            .and(ply_parser())
+            .then_ignore(parser::exact(b" "))
+            .and(
+                parser::exact(b"-")
+                    .map(|_| Captured::new())
+                    .or(Captured::parser()),
+            )
+            .then_ignore(parser::exact(b" "))
+            .and(Board::fen_parser())
+            .try_map(|(((stage, (ply, ply_draw)), captured), board)| {
+                Self::from_parts(stage, ply, board, captured, ply_draw).map_err(|_| ParseError)
             })
     }
 
+    /// Verifies all the invariants checked when parsing, plus internal consistency.
+    ///
+    /// Every position produced by this crate from a valid position is valid.
+    pub fn validate(&self) -> Result<(), Invalid> {
+        self.board.validate()?;
+        self.captured.validate()?;
+        if self.stage == Stage::Setup && self.null_move_counter != 0 {
+            return Err(Invalid);
+        }
+        _ = Self::from_parts(
+            self.stage,
+            self.ply,
+            self.board,
+            self.captured,
+            self.ply_draw,
+        )?;
+        Ok(())
+    }
+
     fn from_parts(
         stage: Stage,
         ply: Ply,
         board: Board,
         captured: Captured,
+        ply_draw: Ply,
     ) -> Result<Position, Invalid> {
+        if !(PLY_AFTER_SETUP < ply_draw && ply_draw <= PLY_DRAW) {
+            return Err(Invalid);
+        }
         let to_move = Color::from_index(usize::from(ply) % Color::COUNT);
 
         // Verify total piece count.
@@ -214,17 +439,17 @@ impl Position {
                 }
             }
             Stage::Regular => {
-                if !(Color::COUNT as Ply..PLY_DRAW).contains(&ply) {
+                if !(Color::COUNT as Ply..ply_draw).contains(&ply) {
                     return Err(Invalid);
                 }
             }
             Stage::End(Outcome::Draw) => {
-                if ply != PLY_DRAW {
+                if ply != ply_draw {
                     return Err(Invalid);
                 }
             }
             Stage::End(outcome) => {
-                if !(Color::COUNT as Ply..=PLY_DRAW).contains(&ply)
+                if !(Color::COUNT as Ply..=ply_draw).contains(&ply)
                     || outcome != Outcome::win(to_move.opposite())
                 {
                     return Err(Invalid);
@@ -276,6 +501,14 @@ impl Position {
             board,
             captured,
             null_move_counter: 0,
+            ply_draw,
+        })
+    }
+
+    /// Plays a sequence of moves from the initial position.
+    pub fn replay(moves: &[AnyMove]) -> Result<Position, InvalidMove> {
+        moves.iter().try_fold(Self::initial(), |position, &mov| {
+            position.make_any_move(mov)
         })
     }
 
@@ -286,6 +519,28 @@ impl Position {
         }
     }
 
+    /// Like `make_any_move`, but also describes what the move changed.
+    pub fn make_any_move_with_effect(
+        &self,
+        mov: AnyMove,
+    ) -> Result<(Position, MoveEffect), InvalidMove> {
+        let new_position = self.make_any_move(mov)?;
+        let stage_changed = new_position.stage != self.stage;
+        let effect = MoveEffect {
+            previous_stage: stage_changed.then_some(self.stage),
+            captured: match mov {
+                AnyMove::Setup(_) => None,
+                AnyMove::Regular(mov) => mov.captured,
+            },
+            outcome: match new_position.stage {
+                Stage::End(outcome) if stage_changed => Some(outcome),
+                _ => None,
+            },
+            irreversible: mov.is_irreversible(),
+        };
+        Ok((new_position, effect))
+    }
+
     pub fn make_setup_move(&self, mov: SetupMove) -> Result<Position, InvalidMove> {
         let me = self.to_move();
         if self.stage != Stage::Setup || mov.color != me {
@@ -309,49 +564,59 @@ impl Position {
     }
 
     pub fn make_move(&self, mov: Move) -> Result<Position, InvalidMove> {
-        let me = self.to_move();
-        let opp = me.opposite();
-        if self.stage != Stage::Regular || mov.colored_piece.color() != me {
+        if !movegen::is_pseudo_legal(self, mov) {
             return Err(InvalidMove);
         }
+        Ok(self.make_move_unchecked(mov))
+    }
+
+    /// `make_move` for a move known to be pseudo-legal, e.g. from the move generator.
+    pub(crate) fn make_move_unchecked(&self, mov: Move) -> Position {
         let mut new_position = *self;
+        new_position.make_move_in_place_unchecked(mov);
+        new_position
+    }
+
+    /// `make_move_unchecked` modifying the position rather than returning a new one.
+    pub(crate) fn make_move_in_place_unchecked(&mut self, mov: Move) {
+        debug_assert!(movegen::is_pseudo_legal(self, mov), "Invalid move {mov}");
+        let me = self.to_move();
         match mov.from {
-            None => {
-                new_position
-                    .captured
-                    .remove(mov.colored_piece)
-                    .map_err(|_| InvalidMove)?;
-            }
-            Some(from) => {
-                movegen::validate_from_to(mov.colored_piece.piece(), from, mov.to)?;
-                new_position
-                    .board
-                    .remove_piece(from, mov.colored_piece)
-                    .map_err(|_| InvalidMove)?;
-            }
+            None => self.captured.remove_unchecked(mov.colored_piece),
+            Some(from) => self.board.remove_piece_unchecked(from, mov.colored_piece),
         }
         if let Some(captured) = mov.captured {
-            new_position
-                .board
-                .remove_piece(mov.to, captured.with_color(opp))
-                .map_err(|_| InvalidMove)?;
-            new_position
-                .captured
-                .add(captured.with_color(me))
-                .map_err(|_| InvalidMove)?;
+            self.board
+                .remove_piece_unchecked(mov.to, captured.with_color(me.opposite()));
+            self.captured.add_unchecked(captured.with_color(me));
             if captured == Piece::Wazir {
-                new_position.stage = Stage::End(Outcome::win(me));
+                self.stage = Stage::End(Outcome::win(me));
             }
         }
-        new_position
-            .board
-            .place_piece(mov.to, mov.colored_piece)
-            .map_err(|_| InvalidMove)?;
-        new_position.ply += 1;
-        if new_position.ply() == PLY_DRAW && new_position.stage == Stage::Regular {
-            new_position.stage = Stage::End(Outcome::Draw);
+        self.board.place_piece_unchecked(mov.to, mov.colored_piece);
+        self.ply += 1;
+        if self.ply == self.ply_draw && self.stage == Stage::Regular {
+            self.stage = Stage::End(Outcome::Draw);
         }
-        Ok(new_position)
+    }
+
+    /// Undoes `make_move_in_place_unchecked(mov)`.
+    #[cfg(feature = "make-unmake")]
+    pub(crate) fn unmake_move_in_place_unchecked(&mut self, mov: Move) {
+        let me = self.to_move().opposite();
+        self.board.remove_piece_unchecked(mov.to, mov.colored_piece);
+        if let Some(captured) = mov.captured {
+            self.captured.remove_unchecked(captured.with_color(me));
+            self.board
+                .place_piece_unchecked(mov.to, captured.with_color(me.opposite()));
+        }
+        match mov.from {
+            None => self.captured.add_unchecked(mov.colored_piece),
+            Some(from) => self.board.place_piece_unchecked(from, mov.colored_piece),
+        }
+        self.ply -= 1;
+        self.stage = Stage::Regular;
+        debug_assert!(movegen::is_pseudo_legal(self, mov), "Invalid move {mov}");
     }
 
     pub fn make_null_move(&self) -> Result<Position, InvalidMove> {
@@ -360,20 +625,137 @@ impl Position {
         }
         let mut new_position = *self;
         new_position.ply += 1;
-        if new_position.ply() == PLY_DRAW {
+        if new_position.ply() == self.ply_draw {
             new_position.stage = Stage::End(Outcome::Draw);
         }
         new_position.null_move_counter += 1;
         Ok(new_position)
     }
+
+    /// The position after the side to move passes, as in the search's null move.
+    ///
+    /// `None` if the game is over or the side to move is in check, since passing would let the
+    /// opponent capture the wazir.
+    pub fn after_null_move(&self) -> Option<Position> {
+        if self.stage != Stage::Regular || movegen::in_check(self, self.to_move()) {
+            return None;
+        }
+        self.make_null_move().ok()
+    }
+
+    /// The position before `mov`, the inverse of `make_any_move`.
+    ///
+    /// `mov` must be the last move played. Fails if it can't have led to this position.
+    pub fn unmake_any_move(&self, mov: AnyMove) -> Result<Position, InvalidMove> {
+        match mov {
+            AnyMove::Setup(mov) => self.unmake_setup_move(mov),
+            AnyMove::Regular(mov) => self.unmake_move(mov),
+        }
+    }
+
+    /// The inverse of `make_setup_move`.
+    pub fn unmake_setup_move(&self, mov: SetupMove) -> Result<Position, InvalidMove> {
+        let me = self.to_move().opposite();
+        let after_setup = match self.stage {
+            Stage::Setup => self.ply != 0,
+            Stage::Regular => self.ply == PLY_AFTER_SETUP,
+            Stage::End(_) => false,
+        };
+        if !after_setup || mov.color != me {
+            return Err(InvalidMove);
+        }
+        let mut parent = *self;
+        let symmetry = Symmetry::pov(me).inverse();
+        for (i, &piece) in mov.pieces.iter().enumerate() {
+            let square = symmetry.apply(Square::from_index(i));
+            parent
+                .board
+                .remove_piece(square, piece.with_color(me))
+                .map_err(|_| InvalidMove)?;
+        }
+        parent.ply -= 1;
+        parent.stage = Stage::Setup;
+        Ok(parent)
+    }
+
+    /// The inverse of `make_move`.
+    ///
+    /// `mov` carries the captured piece, so nothing else needs to be remembered.
+    pub fn unmake_move(&self, mov: Move) -> Result<Position, InvalidMove> {
+        let me = self.to_move().opposite();
+        let captured_wazir = mov.captured == Some(Piece::Wazir);
+        let stage_matches = match self.stage {
+            Stage::Setup => false,
+            Stage::Regular => !captured_wazir,
+            Stage::End(Outcome::Draw) => self.ply == self.ply_draw && !captured_wazir,
+            Stage::End(outcome) => captured_wazir && outcome == Outcome::win(me),
+        };
+        if !stage_matches || self.ply <= PLY_AFTER_SETUP || mov.colored_piece.color() != me {
+            return Err(InvalidMove);
+        }
+        let mut parent = *self;
+        parent
+            .board
+            .remove_piece(mov.to, mov.colored_piece)
+            .map_err(|_| InvalidMove)?;
+        if let Some(captured) = mov.captured {
+            parent
+                .captured
+                .remove(captured.with_color(me))
+                .map_err(|_| InvalidMove)?;
+            parent
+                .board
+                .place_piece(mov.to, captured.with_color(me.opposite()))
+                .map_err(|_| InvalidMove)?;
+        }
+        match mov.from {
+            None => parent.captured.add(mov.colored_piece),
+            Some(from) => parent.board.place_piece(from, mov.colored_piece),
+        }
+        .map_err(|_| InvalidMove)?;
+        parent.ply -= 1;
+        parent.stage = Stage::Regular;
+        if !movegen::is_pseudo_legal(&parent, mov) {
+            return Err(InvalidMove);
+        }
+        Ok(parent)
+    }
+
+    /// The inverse of `make_null_move`.
+    ///
+    /// Fails if no null move has been played, but can't tell whether it was the last move.
+    pub fn unmake_null_move(&self) -> Result<Position, InvalidMove> {
+        let stage_matches = match self.stage {
+            Stage::Regular => true,
+            Stage::End(Outcome::Draw) => self.ply == self.ply_draw,
+            _ => false,
+        };
+        if !stage_matches || self.ply <= PLY_AFTER_SETUP || self.null_move_counter == 0 {
+            return Err(InvalidMove);
+        }
+        let mut parent = *self;
+        parent.ply -= 1;
+        parent.stage = Stage::Regular;
+        parent.null_move_counter -= 1;
+        Ok(parent)
+    }
 }
 
 impl_from_str_for_parsable!(Position);
 
+/// The ply and `ply_draw`, which is `PLY_DRAW` unless given after a `/`.
+fn ply_parser() -> impl Parser<Output = (Ply, Ply)> {
+    parser::u8().and(
+        parser::exact(b"/")
+            .ignore_then(parser::u8())
+            .or(parser::empty().map(|_| PLY_DRAW)),
+    )
+}
+
 impl Display for Position {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         writeln!(f, "{}", self.stage)?;
-        writeln!(f, "{}", self.ply())?;
+        writeln!(f, "{}", self.display_ply())?;
         writeln!(f, "{}", self.captured)?;
         write!(f, "{}", self.board)?;
         Ok(())
@@ -4,6 +4,29 @@ use std::mem;
 pub struct PVTable {
     buckets: Vec<Bucket>,
     epoch: u8,
+    stats: PVTableStats,
+}
+
+/// Usage counters of a `PVTable`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct PVTableStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub stores: u64,
+    /// Stores that replaced a variation for a different position.
+    pub evictions: u64,
+}
+
+impl PVTableStats {
+    /// Fraction of lookups that found a variation.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
 }
 
 impl PVTable {
@@ -14,9 +37,49 @@ impl PVTable {
         Self {
             buckets: vec![Bucket::default(); num_buckets],
             epoch: 1,
+            stats: PVTableStats::default(),
         }
     }
 
+    /// Number of variations that can be stored.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * Bucket::SIZE
+    }
+
+    /// Bytes allocated, at most the size passed to `new`.
+    pub fn memory_bytes(&self) -> usize {
+        self.buckets.len() * mem::size_of::<Bucket>()
+    }
+
+    /// Number of stored variations.
+    pub fn len(&self) -> usize {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| &bucket.entries)
+            .filter(|entry| !entry.is_empty())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Permille of sampled entries used in the current epoch.
+    pub fn hashfull(&self) -> u32 {
+        let sample = self.buckets.len().min(1000 / Bucket::SIZE);
+        let used = self.buckets[..sample]
+            .iter()
+            .flat_map(|bucket| &bucket.entries)
+            .filter(|entry| entry.epoch == self.epoch)
+            .count();
+        (1000 * used / (sample * Bucket::SIZE)) as u32
+    }
+
+    /// Usage counters since the last call to `take_stats`.
+    pub fn take_stats(&mut self) -> PVTableStats {
+        mem::take(&mut self.stats)
+    }
+
     pub fn new_epoch(&mut self) {
         self.epoch = if self.epoch == u8::MAX {
             1
@@ -28,11 +91,16 @@ impl PVTable {
     pub fn get(&mut self, hash: u64) -> Option<LongVariation> {
         let (hash, bucket_idx) = self.split_hash(hash);
         let bucket = &mut self.buckets[bucket_idx];
-        let (index, entry) = bucket
+        let Some((index, entry)) = bucket
             .entries
             .iter_mut()
             .enumerate()
-            .find(|(_, entry)| entry.hash == hash)?;
+            .find(|(_, entry)| !entry.is_empty() && entry.hash == hash)
+        else {
+            self.stats.misses += 1;
+            return None;
+        };
+        self.stats.hits += 1;
         entry.epoch = self.epoch;
         Some(bucket.variations[index].clone())
     }
@@ -46,6 +114,10 @@ impl PVTable {
             .enumerate()
             .max_by_key(|(_, entry)| (entry.hash == hash, entry.epoch != self.epoch))
             .unwrap();
+        self.stats.stores += 1;
+        if !best_entry.is_empty() && best_entry.hash != hash {
+            self.stats.evictions += 1;
+        }
         *best_entry = Entry {
             hash,
             epoch: self.epoch,
@@ -67,6 +139,13 @@ struct Entry {
     epoch: u8,
 }
 
+impl Entry {
+    // Epoch 0 is never used.
+    fn is_empty(&self) -> bool {
+        self.epoch == 0
+    }
+}
+
 #[derive(Default, Clone)]
 #[repr(align(64))]
 struct Bucket {
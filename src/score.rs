@@ -1,4 +1,4 @@
-use crate::constants::{Eval, Ply, PLY_DRAW};
+use crate::constants::{Eval, Ply};
 use std::{
     fmt::{self, Display, Formatter},
     ops::Neg,
@@ -21,28 +21,22 @@ impl ScoreExpanded {
         }
     }
 
-    pub fn to_absolute(self, ply: Ply) -> Self {
+    /// Wins and losses beyond `ply_draw`, see `Position::ply_draw`, become draws.
+    pub fn to_absolute(self, ply: Ply, ply_draw: Ply) -> Self {
         match self {
-            Self::Win(p) => {
-                let p2 = p + ply;
-                if p2 <= PLY_DRAW {
-                    Self::Win(p2)
-                } else {
-                    Self::Eval(0)
-                }
-            }
-            Self::Loss(p) => {
-                let p2 = p + ply;
-                if p2 <= PLY_DRAW {
-                    Self::Loss(p2)
-                } else {
-                    Self::Eval(0)
-                }
-            }
+            Self::Win(p) => match p.checked_add(ply) {
+                Some(p2) if p2 <= ply_draw => Self::Win(p2),
+                _ => Self::Eval(0),
+            },
+            Self::Loss(p) => match p.checked_add(ply) {
+                Some(p2) if p2 <= ply_draw => Self::Loss(p2),
+                _ => Self::Eval(0),
+            },
             Self::Eval(_) => self,
         }
     }
 
+    /// Only applies to evals. Saturates rather than turning into a win or loss.
     pub fn offset(self, offset: Eval) -> Self {
         match self {
             Self::Eval(eval) => Self::Eval(eval.saturating_add(offset)),
@@ -69,25 +63,70 @@ impl Score {
     pub const DRAW: Score = Score(0);
     pub const WIN_MAX_PLY: Score = Score(Self::INFINITE.0 - u8::MAX as Eval);
 
+    /// Saturates at `INFINITE`.
     pub fn next(self) -> Self {
+        self.debug_assert_valid();
         Self((self.0 + 1).min(Self::INFINITE.0))
     }
 
+    /// Saturates at `-INFINITE`.
     pub fn prev(self) -> Self {
+        self.debug_assert_valid();
         Self((self.0 - 1).max(-Self::INFINITE.0))
     }
 
     pub fn to_relative(self, ply: Ply) -> Self {
+        self.debug_assert_valid();
         ScoreExpanded::from(self).to_relative(ply).into()
     }
 
-    pub fn to_absolute(self, ply: Ply) -> Self {
-        ScoreExpanded::from(self).to_absolute(ply).into()
+    pub fn to_absolute(self, ply: Ply, ply_draw: Ply) -> Self {
+        self.debug_assert_valid();
+        ScoreExpanded::from(self).to_absolute(ply, ply_draw).into()
     }
 
     pub fn offset(self, offset: Eval) -> Self {
+        self.debug_assert_valid();
         ScoreExpanded::from(self).offset(offset).into()
     }
+
+    /// A forced win. Higher scores are faster wins.
+    pub fn is_winning(self) -> bool {
+        self >= Self::WIN_MAX_PLY
+    }
+
+    /// A forced loss. Higher scores are slower losses.
+    pub fn is_losing(self) -> bool {
+        self <= -Self::WIN_MAX_PLY
+    }
+
+    /// Plies from `ply` until the game is won (positive) or lost (negative).
+    ///
+    /// `None` for evals. The score must be absolute, see `to_absolute`.
+    pub fn mate_distance(self, ply: Ply) -> Option<i32> {
+        match ScoreExpanded::from(self) {
+            ScoreExpanded::Win(p) => Some(i32::from(p) - i32::from(ply)),
+            ScoreExpanded::Loss(p) => Some(i32::from(ply) - i32::from(p)),
+            ScoreExpanded::Eval(_) => None,
+        }
+    }
+
+    fn debug_assert_valid(self) {
+        debug_assert!(
+            (-Self::INFINITE.0..=Self::INFINITE.0).contains(&self.0),
+            "Score out of range: {}",
+            self.0
+        );
+    }
+
+    pub(crate) fn to_bits(self) -> u32 {
+        self.0 as u32
+    }
+
+    /// Inverse of `to_bits`, clamped to valid scores.
+    pub(crate) fn from_bits(bits: u32) -> Self {
+        Self((bits as Eval).clamp(-Self::INFINITE.0, Self::INFINITE.0))
+    }
 }
 
 impl Neg for Score {
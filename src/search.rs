@@ -1,26 +1,71 @@
 use crate::{
+    book,
     constants::{
-        Depth, Eval, Hyperparameters, Ply, CHECK_TIMEOUT_NODES, DEPTH_INCREMENT, MAX_SEARCH_DEPTH,
+        Depth, Eval, Hyperparameters, Ply, DEPTH_INCREMENT, MAX_KILLER_MOVES, MAX_SEARCH_DEPTH,
         NUM_KILLER_MOVES, ONE_PLY, PLY_DRAW,
     },
     either::Either,
+    enums::EnumMap,
     history::History,
     log, movegen,
     smallvec::SmallVec,
     ttable::{TTable, TTableEntry, TTableScoreType},
     variation::LongVariation,
-    Color, EmptyVariation, EvaluatedPosition, Evaluator, ExtendableVariation, Move,
-    NonEmptyVariation, OneMoveVariation, PVTable, Position, Score, ScoreExpanded, SetupMove, Stage,
-    Variation,
+    Color, ColoredPiece, EmptyVariation, EvalCache, EvalCacheStats, EvaluatedPosition, Evaluator,
+    ExtendableVariation, InvalidMove, Move, NonEmptyVariation, OneMoveVariation, PVTable,
+    PVTableStats, Position, Score, ScoreExpanded, SetupMove, Square, Stage, Variation,
 };
-use std::{cmp::Reverse, iter, sync::Arc, time::Instant};
+use std::{
+    cell::Cell,
+    cmp::Reverse,
+    fmt::{self, Display, Formatter},
+    iter, mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Memory of a default `Search` besides its tables: the killer moves and history scores.
+pub(crate) const SEARCH_OVERHEAD_BYTES: usize = PLY_DRAW as usize
+    * mem::size_of::<[Option<Move>; NUM_KILLER_MOVES]>()
+    + mem::size_of::<HistoryScores>();
+
+/// Bound on the number of captures in a position: 16 pieces with at most 8 moves each.
+const MAX_CAPTURES: usize = 128;
+
+/// Bound on the number of quiet moves in a position: drops of 4 kinds of pieces on 64 squares,
+/// and 16 pieces with at most 8 jumps each.
+const MAX_QUIET_MOVES: usize = 4 * 64 + 128;
 
-pub struct Search<E> {
+/// Bound on the number of captures or check evasions in a position.
+#[cfg(feature = "make-unmake")]
+const MAX_QUIESCENCE_MOVES: usize = 256;
+
+/// The position passed to `quiescence_search`. With make/unmake, moves are made on it in place.
+#[cfg(not(feature = "make-unmake"))]
+type QuiescencePosition<'a, 'e, E> = &'a EvaluatedPosition<'e, E>;
+#[cfg(feature = "make-unmake")]
+type QuiescencePosition<'a, 'e, E> = &'a mut EvaluatedPosition<'e, E>;
+
+/// `KILLERS` is the number of killer moves per ply, between 1 and `MAX_KILLER_MOVES`.
+pub struct Search<E, const KILLERS: usize = NUM_KILLER_MOVES> {
     hyperparameters: Hyperparameters,
     evaluator: Arc<E>,
-    ttable: TTable,
+    ttable: Arc<TTable>,
     pvtable: PVTable,
-    killer_moves: Vec<[Option<Move>; NUM_KILLER_MOVES]>,
+    eval_cache: Option<EvalCache>,
+    killer_moves: Vec<[Option<Move>; KILLERS]>,
+    history_scores: HistoryScores,
+    stop: Option<Arc<AtomicBool>>,
+    must_win: bool,
+    cumulative_stats: CumulativeStats,
+    /// `search_threads - 1` searches sharing `ttable`, run on other threads by `search`.
+    helpers: Vec<Search<E, KILLERS>>,
+    /// Stops the helpers once the main search is done.
+    stop_helpers: Arc<AtomicBool>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -34,15 +79,110 @@ pub struct Deadlines {
 
 impl<E: Evaluator> Search<E> {
     pub fn new(hyperparameters: &Hyperparameters, evaluator: &Arc<E>) -> Self {
+        Self::new_with_killers(hyperparameters, evaluator)
+    }
+}
+
+impl<E: Evaluator, const KILLERS: usize> Search<E, KILLERS> {
+    /// Like `new`, with `KILLERS` killer moves per ply rather than `NUM_KILLER_MOVES`.
+    ///
+    /// `new` only makes the default so that `Search::new(..)` needs no type annotations.
+    pub fn new_with_killers(hyperparameters: &Hyperparameters, evaluator: &Arc<E>) -> Self {
+        let ttable = Arc::new(TTable::new(hyperparameters.ttable_size));
+        Self::with_ttable(hyperparameters, evaluator, &ttable)
+    }
+
+    /// Like `new`, but uses a transposition table that may be shared with other searches,
+    /// including on other threads. `hyperparameters.ttable_size` is ignored.
+    pub fn with_ttable(
+        hyperparameters: &Hyperparameters,
+        evaluator: &Arc<E>,
+        ttable: &Arc<TTable>,
+    ) -> Self {
+        const { assert!(KILLERS >= 1 && KILLERS <= MAX_KILLER_MOVES) };
+        if let Err(err) = hyperparameters.validate() {
+            panic!("Invalid hyperparameters: {err}");
+        }
+        let stop_helpers = Arc::new(AtomicBool::new(false));
+        let helper_hyperparameters = Hyperparameters {
+            search_threads: 1,
+            ..hyperparameters.clone()
+        };
+        let helpers = (1..hyperparameters.search_threads)
+            .map(|_| {
+                let mut helper = Self::with_ttable(&helper_hyperparameters, evaluator, ttable);
+                helper.set_stop_flag(Some(Arc::clone(&stop_helpers)));
+                helper
+            })
+            .collect();
         Self {
             hyperparameters: hyperparameters.clone(),
             evaluator: Arc::clone(evaluator),
-            ttable: TTable::new(hyperparameters.ttable_size),
+            ttable: Arc::clone(ttable),
             pvtable: PVTable::new(hyperparameters.pvtable_size),
-            killer_moves: vec![[None; NUM_KILLER_MOVES]; PLY_DRAW as usize],
+            eval_cache: (hyperparameters.eval_cache_size > 0)
+                .then(|| EvalCache::new(hyperparameters.eval_cache_size)),
+            killer_moves: vec![[None; KILLERS]; PLY_DRAW as usize],
+            history_scores: HistoryScores::new(),
+            stop: None,
+            must_win: false,
+            cumulative_stats: CumulativeStats::default(),
+            helpers,
+            stop_helpers,
         }
     }
 
+    /// Once `stop` is set, searches end as if the hard deadline had passed.
+    ///
+    /// This lets another thread stop a search that has no deadlines.
+    pub fn set_stop_flag(&mut self, stop: Option<Arc<AtomicBool>>) {
+        self.stop = stop;
+    }
+
+    /// When `must_win` is set, root moves leading to a draw score as losses, so a move that keeps
+    /// any winning chances is preferred to a safe draw. It is stronger than contempt, but only
+    /// affects the choice of root move: scores inside the tree are unchanged.
+    ///
+    /// A root move counts as a draw if its score is exactly `Score::DRAW`. Off by default.
+    pub fn set_must_win(&mut self, must_win: bool) {
+        self.must_win = must_win;
+    }
+
+    /// Bytes used by the transposition table, PV table, eval cache, killer moves and history
+    /// scores, including the tables of helper threads.
+    ///
+    /// A transposition table shared with other searches is counted in full.
+    pub fn memory_bytes(&self) -> usize {
+        self.ttable.memory_bytes()
+            + self.pvtable.memory_bytes()
+            + self.eval_cache.as_ref().map_or(0, EvalCache::memory_bytes)
+            + self.killer_moves.len() * mem::size_of::<[Option<Move>; KILLERS]>()
+            + mem::size_of::<HistoryScores>()
+            + self
+                .helpers
+                .iter()
+                .map(|helper| helper.memory_bytes() - helper.ttable.memory_bytes())
+                .sum::<usize>()
+    }
+
+    /// Totals over all searches since creation or the last `reset_cumulative_stats`.
+    pub fn cumulative_stats(&self) -> CumulativeStats {
+        self.cumulative_stats
+    }
+
+    pub fn reset_cumulative_stats(&mut self) {
+        self.cumulative_stats = CumulativeStats::default();
+    }
+
+    /// The transposition table, for sharing with `with_ttable`.
+    pub fn ttable(&self) -> &Arc<TTable> {
+        &self.ttable
+    }
+
+    /// With `search_threads > 1`, helper threads search the same position until this search
+    /// is done, filling the shared transposition table. Every other helper searches one ply
+    /// deeper than the main search, so they don't all repeat its work. The result is always that
+    /// of the calling thread.
     pub fn search(
         &mut self,
         position: &Position,
@@ -52,6 +192,126 @@ impl<E: Evaluator> Search<E> {
         is_score_important: bool,
         history: &History,
     ) -> SearchResult {
+        let mut helpers = mem::take(&mut self.helpers);
+        // One new epoch for all threads, before any of them stores entries.
+        if !helpers.is_empty() {
+            self.ttable.new_epoch();
+        }
+        self.stop_helpers.store(false, Ordering::Relaxed);
+        let result = thread::scope(|scope| {
+            let helper_threads: Vec<_> = helpers
+                .iter_mut()
+                .enumerate()
+                .map(|(index, helper)| {
+                    let depth_offset = if index % 2 == 0 { ONE_PLY } else { 0 };
+                    scope.spawn(move || {
+                        helper.helper_search(
+                            position,
+                            max_depth,
+                            deadlines,
+                            is_score_important,
+                            history,
+                            depth_offset,
+                        )
+                    })
+                })
+                .collect();
+            let mut instance = SearchInstance::new(
+                self,
+                position,
+                max_depth,
+                deadlines,
+                multi_move_threshold,
+                history,
+            );
+            instance.new_ttable_epoch = helper_threads.is_empty();
+            let mut result = instance.search(is_score_important, RootMoveFilter::All);
+            drop(instance);
+            self.stop_helpers.store(true, Ordering::Relaxed);
+            for helper_thread in helper_threads {
+                let helper_result = helper_thread.join().expect("Helper search panicked");
+                result.helper_nodes += helper_result.nodes;
+            }
+            result
+        });
+        self.helpers = helpers;
+        result
+    }
+
+    /// `search` on a helper thread. The main search has already started a new epoch of the
+    /// transposition table.
+    fn helper_search(
+        &mut self,
+        position: &Position,
+        max_depth: Option<Depth>,
+        deadlines: Option<Deadlines>,
+        is_score_important: bool,
+        history: &History,
+        depth_offset: Depth,
+    ) -> SearchResult {
+        let mut instance = SearchInstance::new(self, position, max_depth, deadlines, None, history);
+        instance.new_ttable_epoch = false;
+        instance.depth_offset = depth_offset;
+        instance.search(is_score_important, RootMoveFilter::All)
+    }
+
+    /// The transposition table entry stored for `position`, if any.
+    ///
+    /// Read-only: doesn't affect which entries get replaced.
+    pub fn probe_tt(&self, position: &Position) -> Option<TTableEntry> {
+        self.ttable.probe(position.hash())
+    }
+
+    /// Like `search`, but only considers the given moves at the root.
+    ///
+    /// Fails if `root_moves` is empty or contains a move that isn't legal.
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_root_moves(
+        &mut self,
+        position: &Position,
+        root_moves: &[Move],
+        max_depth: Option<Depth>,
+        deadlines: Option<Deadlines>,
+        multi_move_threshold: Option<i32>,
+        is_score_important: bool,
+        history: &History,
+    ) -> Result<SearchResult, InvalidMove> {
+        if root_moves.is_empty()
+            || !root_moves
+                .iter()
+                .all(|&mov| movegen::is_legal(position, mov))
+        {
+            return Err(InvalidMove);
+        }
+        let mut instance = SearchInstance::new(
+            self,
+            position,
+            max_depth,
+            deadlines,
+            multi_move_threshold,
+            history,
+        );
+        Ok(instance.search(is_score_important, RootMoveFilter::Only(root_moves)))
+    }
+
+    /// Like `search`, but never plays any of `excluded_moves` at the root.
+    ///
+    /// Useful for finding the best alternative to a move. Fails if every pseudo-legal move is
+    /// excluded.
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_excluding_moves(
+        &mut self,
+        position: &Position,
+        excluded_moves: &[Move],
+        max_depth: Option<Depth>,
+        deadlines: Option<Deadlines>,
+        multi_move_threshold: Option<i32>,
+        is_score_important: bool,
+        history: &History,
+    ) -> Result<SearchResult, InvalidMove> {
+        if movegen::pseudomoves(position).all(|mov| excluded_moves.contains(&mov)) {
+            return Err(InvalidMove);
+        }
         let mut instance = SearchInstance::new(
             self,
             position,
@@ -60,7 +320,75 @@ impl<E: Evaluator> Search<E> {
             multi_move_threshold,
             history,
         );
-        instance.search(is_score_important)
+        Ok(instance.search(is_score_important, RootMoveFilter::Exclude(excluded_moves)))
+    }
+
+    /// Deepens until the scores of two consecutive depths differ by less than `margin`.
+    ///
+    /// Stops at `max_depth` or the deadlines if the score doesn't stabilize. Win and loss
+    /// scores are stable only if they are the same.
+    pub fn search_until_stable(
+        &mut self,
+        position: &Position,
+        margin: Eval,
+        max_depth: Depth,
+        deadlines: Option<Deadlines>,
+        history: &History,
+    ) -> SearchResult {
+        let mut instance =
+            SearchInstance::new(self, position, Some(max_depth), deadlines, None, history);
+        instance.stable_margin = Some(margin);
+        instance.search(true, RootMoveFilter::All)
+    }
+
+    /// Yields a result for every completed iterative deepening depth.
+    ///
+    /// The search runs lazily on the calling thread: each call to `next` blocks while the next
+    /// depth is searched, and dropping the iterator stops the search. The iterator borrows the
+    /// `Search`, so to keep a UI responsive drive it from a worker thread and send the results
+    /// over a channel.
+    ///
+    /// The iterator ends after `max_depth`, when a deadline passes, or after a single result if
+    /// the position doesn't need a search (e.g. the game is over or the wazir can be captured).
+    /// A depth interrupted by the hard deadline is not yielded.
+    pub fn analyze<'a>(
+        &'a mut self,
+        position: &Position,
+        max_depth: Option<Depth>,
+        deadlines: Option<Deadlines>,
+        history: &History,
+    ) -> impl Iterator<Item = SearchResult> + 'a {
+        let instance = SearchInstance::new(self, position, max_depth, deadlines, None, history);
+        Analysis {
+            instance,
+            eposition: None,
+            done: false,
+        }
+    }
+
+    /// Searches for the best move, then searches again after it to find the opponent's threat.
+    ///
+    /// Returns the result for the side to move and the result for the opponent after the best
+    /// move, each scored from its own side's point of view. The second search reuses the hash
+    /// table warmed by the first. Both searches share `deadlines`, so the first one may leave
+    /// little time for the second. If the game is over, both results are for `position`.
+    pub fn analyze_with_threat(
+        &mut self,
+        position: &Position,
+        max_depth: Option<Depth>,
+        deadlines: Option<Deadlines>,
+        history: &History,
+    ) -> (SearchResult, SearchResult) {
+        let best = self.search(position, max_depth, deadlines, None, true, history);
+        let Some(mov) = best.pv.first() else {
+            let threat = self.search(position, max_depth, deadlines, None, true, history);
+            return (best, threat);
+        };
+        let position2 = position.make_move(mov).expect("Invalid PV move");
+        let mut history2 = history.clone();
+        history2.push_position(&position2);
+        let threat = self.search(&position2, max_depth, deadlines, None, true, &history2);
+        (best, threat)
     }
 
     pub fn search_blue_setup(
@@ -78,21 +406,73 @@ impl<E: Evaluator> Search<E> {
             SearchInstance::new(self, &position, max_depth, deadlines, None, &history);
         instance.search_blue_setup(possible_moves)
     }
+
+    /// Scores every blue setup in `book::blue_setup_moves` against `red`, best for blue first.
+    ///
+    /// Each setup gets an exact score from blue's point of view, from a search to `depth` of the
+    /// position after it. This is slower than `search_blue_setup`, which only needs to prove that
+    /// the other setups are worse than the best one. The searches share the transposition table.
+    pub fn score_all_blue_setups(
+        &mut self,
+        red: SetupMove,
+        depth: Depth,
+    ) -> Vec<(SetupMove, Score)> {
+        let mut position = Position::initial();
+        let mut history = History::new_from_position(&position);
+        position = position.make_setup_move(red).unwrap();
+        history.push_position_irreversible(&position);
+        let mut scores: Vec<(SetupMove, Score)> = book::blue_setup_moves()
+            .into_iter()
+            .map(|blue| {
+                let position2 = position.make_setup_move(blue).unwrap();
+                let mut history2 = history.clone();
+                history2.push_position_irreversible(&position2);
+                let result = self.search(&position2, Some(depth), None, None, true, &history2);
+                (blue, -result.score)
+            })
+            .collect();
+        scores.sort_by_key(|&(_, score)| Reverse(score));
+        scores
+    }
+
+    /// For diagnostics.
+    pub fn pvtable(&self) -> &PVTable {
+        &self.pvtable
+    }
 }
 
 /// This doesn't work for setup positions.
-struct SearchInstance<'a, E: Evaluator> {
+struct SearchInstance<'a, E: Evaluator, const KILLERS: usize> {
     hyperparameters: Hyperparameters,
     evaluator: &'a E,
-    ttable: &'a mut TTable,
+    ttable: &'a TTable,
     pvtable: &'a mut PVTable,
-    killer_moves: &'a mut [[Option<Move>; NUM_KILLER_MOVES]],
+    eval_cache: Option<&'a mut EvalCache>,
+    killer_moves: &'a mut [[Option<Move>; KILLERS]],
+    history_scores: &'a HistoryScores,
+    stop: Option<&'a AtomicBool>,
+    must_win: bool,
+    cumulative_stats: &'a mut CumulativeStats,
+    start_time: Instant,
     root_position: Position,
     max_depth: Depth,
     deadlines: Option<Deadlines>,
     multi_move_threshold: Option<i32>,
+    /// `SearchResult::top_moves` at the last completed depth.
+    top_moves: Vec<ScoredMove>,
+    stable_margin: Option<Eval>,
+    /// Whether to start a new transposition table epoch. Off for Lazy SMP searches, where
+    /// `Search::search` starts it once for all threads.
+    new_ttable_epoch: bool,
+    /// Extra depth of every iteration after the shallow search.
+    depth_offset: Depth,
     hard_deadline: Option<Instant>,
+    check_timeout_nodes: u64,
     nodes: u64,
+    timeouts: TimeoutStats,
+    aborted_reason: Option<TimeoutKind>,
+    // Whether the last depth considered all root moves.
+    completed: bool,
     root_moves: Vec<RootMove>,
     root_moves_setup: Vec<SetupMove>,
     depth: Depth,
@@ -105,18 +485,21 @@ struct SearchInstance<'a, E: Evaluator> {
     panic_eval_threshold: Eval,
     null_move_margin: Eval,
     futility_margin: Eval,
+    aspiration_window: Option<Eval>,
+    aspiration_researches: u32,
+    /// Null moves are not tried before this ply, while verifying a null move cutoff.
+    null_move_min_ply: Ply,
 }
 
-impl<'a, E: Evaluator> SearchInstance<'a, E> {
+impl<'a, E: Evaluator, const KILLERS: usize> SearchInstance<'a, E, KILLERS> {
     fn new(
-        search: &'a mut Search<E>,
+        search: &'a mut Search<E, KILLERS>,
         position: &Position,
         max_depth: Option<Depth>,
         deadlines: Option<Deadlines>,
         multi_move_threshold: Option<i32>,
         history: &History,
     ) -> Self {
-        assert!(multi_move_threshold.is_none() || deadlines.is_none());
         let contempt = (search.hyperparameters.contempt * search.evaluator.scale()) as Eval;
         let red_contempt = match position.to_move() {
             Color::Red => contempt,
@@ -125,15 +508,29 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         Self {
             hyperparameters: search.hyperparameters.clone(),
             evaluator: &search.evaluator,
-            ttable: &mut search.ttable,
+            ttable: &search.ttable,
             pvtable: &mut search.pvtable,
+            eval_cache: search.eval_cache.as_mut(),
             killer_moves: &mut search.killer_moves,
+            history_scores: &search.history_scores,
+            stop: search.stop.as_deref(),
+            must_win: search.must_win,
+            cumulative_stats: &mut search.cumulative_stats,
+            start_time: Instant::now(),
             root_position: *position,
             max_depth: max_depth.unwrap_or(MAX_SEARCH_DEPTH),
             deadlines,
             multi_move_threshold,
+            top_moves: Vec::new(),
+            stable_margin: None,
+            new_ttable_epoch: true,
+            depth_offset: 0,
             hard_deadline: None,
+            check_timeout_nodes: search.hyperparameters.check_timeout_nodes,
             nodes: 0,
+            timeouts: TimeoutStats::default(),
+            aborted_reason: None,
+            completed: false,
             root_moves: Vec::new(),
             root_moves_setup: Vec::new(),
             depth: 0,
@@ -149,32 +546,37 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                 as Eval,
             futility_margin: (search.hyperparameters.futility_margin * search.evaluator.scale())
                 as Eval,
+            aspiration_window: search
+                .hyperparameters
+                .aspiration_window
+                .map(|window| ((window * search.evaluator.scale()) as Eval).max(1)),
+            aspiration_researches: 0,
+            null_move_min_ply: 0,
+        }
+    }
+
+    fn search(&mut self, is_score_important: bool, filter: RootMoveFilter) -> SearchResult {
+        if self.prepare_root(is_score_important, filter) {
+            let eposition = EvaluatedPosition::new(self.evaluator, self.root_position);
+            // Ignore timeout.
+            _ = self.iterative_deepening(&eposition);
         }
+        self.result()
     }
 
-    fn search(&mut self, is_score_important: bool) -> SearchResult {
+    fn result(&mut self) -> SearchResult {
         let score = match self.root_position.stage() {
-            Stage::Setup => panic!("SearchInstance::search does not support setup"),
-            Stage::Regular => {
-                self.search_root(is_score_important);
-                self.root_moves[0].score
-            }
+            Stage::Setup => unreachable!(),
+            Stage::Regular => self.root_moves[0].score,
             Stage::End(outcome) => outcome.to_score(self.root_position.ply()),
         };
+        self.pv.truncate_illegal(&self.root_position);
 
-        let top_moves = match self.multi_move_threshold {
-            Some(multi_move_threshold) => {
-                let threshold = score.offset(-multi_move_threshold);
-                self.root_moves[..self.root_moves_exact_score]
-                    .iter()
-                    .take_while(|root_move| root_move.score >= threshold)
-                    .map(|root_move| ScoredMove {
-                        mov: root_move.mov,
-                        score: root_move.score,
-                    })
-                    .collect()
-            }
-            None => Vec::new(),
+        // An interrupted depth usually has an exact score for the best move only.
+        let top_moves = if self.completed {
+            self.top_moves_within_threshold(score)
+        } else {
+            self.top_moves.clone()
         };
 
         SearchResult {
@@ -185,27 +587,76 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
             root_moves_considered: self.root_moves_considered,
             num_root_moves: self.root_moves.len(),
             nodes: self.nodes,
+            helper_nodes: 0,
+            branching_factor: effective_branching_factor(self.nodes, self.depth),
+            pvtable_stats: self.pvtable.take_stats(),
+            eval_cache_stats: self.take_eval_cache_stats(),
+            timeouts: self.timeouts,
+            aspiration_researches: self.aspiration_researches,
+            completed: self.completed,
+            aborted_reason: self.aborted_reason,
         }
     }
 
-    fn search_root(&mut self, is_score_important: bool) {
-        self.generate_root_captures_of_wazir();
+    /// Root moves with exact scores within `multi_move_threshold` of `score`. Empty if there is
+    /// no threshold.
+    fn top_moves_within_threshold(&self, score: Score) -> Vec<ScoredMove> {
+        let Some(multi_move_threshold) = self.multi_move_threshold else {
+            return Vec::new();
+        };
+        let threshold = score.offset(-multi_move_threshold);
+        self.root_moves[..self.root_moves_exact_score]
+            .iter()
+            .filter(|root_move| root_move.score >= threshold)
+            .map(|root_move| ScoredMove {
+                mov: root_move.mov,
+                score: root_move.score,
+            })
+            .collect()
+    }
+
+    /// Generates root moves. Returns whether iterative deepening is needed.
+    fn prepare_root(&mut self, is_score_important: bool, filter: RootMoveFilter) -> bool {
+        match self.root_position.stage() {
+            Stage::Setup => panic!("SearchInstance::search does not support setup"),
+            Stage::Regular => {}
+            Stage::End(_) => {
+                self.completed = true;
+                return false;
+            }
+        }
+
+        self.generate_root_captures_of_wazir(filter);
         if let Some(root_move) = self.root_moves.first() {
             self.depth = Depth::MAX;
             self.pv = LongVariation::empty().add_front(root_move.mov);
-            return;
+            self.completed = true;
+            return false;
         }
 
-        self.generate_root_moves();
+        match filter {
+            RootMoveFilter::All | RootMoveFilter::Exclude(_) => self.generate_root_moves(filter),
+            RootMoveFilter::Only(root_moves) => {
+                for &mov in root_moves {
+                    self.root_moves.push(RootMove {
+                        mov,
+                        score: Score::DRAW,
+                        futile: false,
+                        repetition: false,
+                    });
+                }
+            }
+        }
 
         if self.root_moves.is_empty() {
-            self.generate_root_suicides();
+            self.generate_root_suicides(filter);
             let Some(root_move) = self.root_moves.first() else {
                 panic!("Stalemate");
             };
             self.depth = Depth::MAX;
             self.pv = LongVariation::empty().add_front(root_move.mov);
-            return;
+            self.completed = true;
+            return false;
         }
 
         if self.root_moves.len() == 1 && !is_score_important {
@@ -214,32 +665,38 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
             self.root_moves_considered = 1;
             self.root_moves_exact_score = 1;
             self.pv = LongVariation::empty_truncated().add_front(self.root_moves[0].mov);
-            return;
+            self.completed = true;
+            return false;
         }
 
-        self.ttable.new_epoch();
+        if self.new_ttable_epoch {
+            self.ttable.new_epoch();
+        }
         self.pvtable.new_epoch();
-
-        let eposition = EvaluatedPosition::new(self.evaluator, self.root_position);
-
-        // Ignore timeout.
-        _ = self.iterative_deepening(&eposition);
+        self.history_scores.age();
+        // In case we can't finish depth 1 search for a single move, use the first generated move.
+        self.pv = LongVariation::empty().add_front(self.root_moves[0].mov);
+        true
     }
 
-    fn generate_root_captures_of_wazir(&mut self) {
+    fn generate_root_captures_of_wazir(&mut self, filter: RootMoveFilter) {
         let score = ScoreExpanded::Win(self.root_position.ply() + 1).into();
         for mov in movegen::captures_of_wazir(&self.root_position) {
+            if !filter.allows(mov) {
+                continue;
+            }
             self.root_moves.push(RootMove {
                 mov,
                 score,
                 futile: false,
+                repetition: false,
             });
         }
         self.root_moves_considered = self.root_moves.len();
         self.root_moves_exact_score = self.root_moves.len();
     }
 
-    fn generate_root_moves(&mut self) {
+    fn generate_root_moves(&mut self, filter: RootMoveFilter) {
         let in_check = movegen::in_check(&self.root_position, self.root_position.to_move());
         let mut futile = false;
         for move_candidate in
@@ -247,10 +704,14 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         {
             match move_candidate {
                 MoveCandidate::Move { mov, extra: _extra } => {
+                    if !filter.allows(mov) {
+                        continue;
+                    }
                     self.root_moves.push(RootMove {
                         mov,
                         score: Score::DRAW,
                         futile,
+                        repetition: false,
                     });
                 }
                 MoveCandidate::Futility => {
@@ -263,19 +724,23 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         }
     }
 
-    fn generate_root_suicides(&mut self) {
+    fn generate_root_suicides(&mut self, filter: RootMoveFilter) {
         let loss_ply = self.root_position.ply() + 2;
-        let score = if loss_ply <= PLY_DRAW {
+        let score = if loss_ply <= self.root_position.ply_draw() {
             ScoreExpanded::Loss(loss_ply).into()
         } else {
             Score::DRAW
         };
 
         for mov in movegen::pseudomoves(&self.root_position) {
+            if !filter.allows(mov) {
+                continue;
+            }
             self.root_moves.push(RootMove {
                 mov,
                 score,
                 futile: false,
+                repetition: false,
             });
         }
         self.root_moves_considered = self.root_moves.len();
@@ -283,35 +748,48 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
     }
 
     fn iterative_deepening(&mut self, eposition: &EvaluatedPosition<E>) -> Result<(), Timeout> {
-        // In case we can't finish depth 1 search for a single move, use the first generated move.
-        self.pv = LongVariation::empty().add_front(self.root_moves[0].mov);
         self.search_shallow(eposition)?;
-        while self.depth < self.max_depth {
-            if let Some(ds) = self.deadlines.as_ref() {
-                if Instant::now() >= ds.start_next_depth {
-                    log::info!("ndto"); // next depth timeout
+        self.depth = self.depth.saturating_add(self.depth_offset);
+        while self.should_deepen() {
+            let prev_score = self.root_moves[0].score;
+            self.iterative_deepening_iteration(eposition)?;
+            if let Some(margin) = self.stable_margin {
+                if is_stable(prev_score, self.root_moves[0].score, margin) {
                     break;
                 }
             }
-            self.iterative_deepening_iteration(eposition)?;
         }
         Ok(())
     }
 
+    fn should_deepen(&mut self) -> bool {
+        if self.depth >= self.max_depth {
+            return false;
+        }
+        if let Some(ds) = self.deadlines.as_ref() {
+            if Instant::now() >= ds.start_next_depth {
+                self.timeout(TimeoutKind::NextDepth);
+                return false;
+            }
+        }
+        true
+    }
+
     fn search_shallow(&mut self, eposition: &EvaluatedPosition<E>) -> Result<(), Timeout> {
-        self.hard_deadline = self.deadlines.as_ref().map(|ds| ds.hard);
+        self.completed = false;
+        self.set_hard_deadline(self.deadlines.as_ref().map(|ds| ds.hard));
         self.depth = ONE_PLY;
         self.root_moves_considered = 0;
         self.root_moves_exact_score = 0;
         while self.root_moves_considered < self.root_moves.len() {
             if let Some(ds) = self.deadlines.as_ref() {
                 if Instant::now() >= ds.soft {
-                    log::info!("ssto"); // shallow soft timeout
+                    self.timeout(TimeoutKind::ShallowSoft);
                     return Err(Timeout);
                 }
             }
             let mov = self.root_moves[self.root_moves_considered].mov;
-            let epos2 = eposition.make_move(mov).unwrap();
+            let epos2 = eposition.make_move_unchecked(mov);
             self.history.push_position(epos2.position());
             let result = self.search_alpha_beta::<LongVariation>(
                 &epos2,
@@ -321,10 +799,13 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                 NodeType::PV,
             )?;
             self.history.pop();
-            let score = -result.score;
+            let score = self.root_score(-result.score);
             let root_move = &mut self.root_moves[self.root_moves_considered];
             root_move.score = score;
-            if self.root_moves_considered == 0 || score > self.root_moves[0].score {
+            root_move.repetition = result.repetition_ply != Ply::MAX;
+            if self.root_moves_considered == 0
+                || self.root_moves[self.root_moves_considered].key() > self.root_moves[0].key()
+            {
                 self.root_moves[0..=self.root_moves_considered].rotate_right(1);
                 self.pv = result.pv.add_front(mov).truncate();
             }
@@ -332,18 +813,31 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
             self.root_moves_exact_score = self.root_moves_considered;
         }
         self.sort_root_moves();
+        self.completed = true;
+        self.top_moves = self.top_moves_within_threshold(self.root_moves[0].score);
         Ok(())
     }
 
+    /// The score of a root move given the score of the position after it. In must-win mode, a
+    /// draw counts as the slowest possible loss.
+    fn root_score(&self, score: Score) -> Score {
+        if self.must_win && score == Score::DRAW {
+            ScoreExpanded::Loss(self.root_position.ply_draw()).into()
+        } else {
+            score
+        }
+    }
+
     fn sort_root_moves(&mut self) {
         self.root_moves[1..self.root_moves_exact_score]
-            .sort_by_key(|root_move| Reverse(root_move.score));
+            .sort_by_key(|root_move| Reverse(root_move.key()));
     }
 
     fn iterative_deepening_iteration(
         &mut self,
         eposition: &EvaluatedPosition<E>,
     ) -> Result<(), Timeout> {
+        self.completed = false;
         let panic_threshold = match ScoreExpanded::from(self.root_moves[0].score) {
             ScoreExpanded::Win(_) => Score::WIN_MAX_PLY,
             ScoreExpanded::Loss(_) => -Score::INFINITE,
@@ -362,16 +856,16 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                     self.root_moves_considered != 0 && self.root_moves[0].score < panic_threshold;
                 let soft_deadline = if is_panic { ds.panic_soft } else { ds.soft };
                 if self.root_moves_considered != 0 && Instant::now() >= soft_deadline {
-                    log::info!("sto"); // soft timeout
+                    self.timeout(TimeoutKind::Soft);
                     return Err(Timeout);
                 }
-                self.hard_deadline = Some(if is_panic { ds.panic_hard } else { ds.hard });
+                self.set_hard_deadline(Some(if is_panic { ds.panic_hard } else { ds.hard }));
             } else {
-                self.hard_deadline = None;
+                self.set_hard_deadline(None);
             }
 
             let mov = self.root_moves[self.root_moves_considered].mov;
-            let epos2 = eposition.make_move(mov).unwrap();
+            let epos2 = eposition.make_move_unchecked(mov);
             self.history.push_position(epos2.position());
 
             'consider_move: {
@@ -383,6 +877,13 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                             .score
                             .offset(-multi_move_threshold)
                             .prev(),
+                        // When winning only through a line that allows a repetition, also look
+                        // for a move with the same score that avoids it.
+                        None if self.root_moves[0].score > Score::DRAW
+                            && self.root_moves[0].repetition =>
+                        {
+                            self.root_moves[0].score.prev()
+                        }
                         None => self.root_moves[0].score,
                     }
                 };
@@ -405,9 +906,13 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                         self.depth.saturating_sub(depth_diff),
                         NodeType::Cut,
                     )?;
+                    // A bound, so `root_score` isn't applied. A fail-high `DRAW` mapped to a loss
+                    // would skip the re-search, and since `root_score` only lowers scores, a
+                    // fail-low bound still bounds the mapped score.
                     let score = -result.score;
                     if score <= alpha {
                         self.root_moves[self.root_moves_considered].score = score;
+                        self.root_moves[self.root_moves_considered].repetition = false;
                         completed_depth =
                             completed_depth.min(result.depth.saturating_add(depth_diff));
                         break 'consider_move;
@@ -429,28 +934,56 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
 
                     if score <= alpha {
                         self.root_moves[self.root_moves_considered].score = score;
+                        self.root_moves[self.root_moves_considered].repetition = false;
                         completed_depth =
                             completed_depth.min(result.depth.saturating_add(depth_diff));
                         break 'consider_move;
                     }
                 }
 
-                // Full window search.
-                let result = self.search_alpha_beta::<LongVariation>(
-                    &epos2,
-                    -Score::INFINITE,
-                    -alpha,
-                    self.depth.saturating_sub(depth_diff),
-                    NodeType::PV,
-                )?;
-                let score = -result.score;
+                // Full window search, first trying an aspiration window for the first move.
+                let mut window = if alpha == -Score::INFINITE {
+                    self.aspiration_window()
+                } else {
+                    None
+                };
+                let mut researches = 0;
+                let (result, score) = loop {
+                    let (low, high) = window.unwrap_or((alpha, Score::INFINITE));
+                    let result = self.search_alpha_beta::<LongVariation>(
+                        &epos2,
+                        -high,
+                        -low,
+                        self.depth.saturating_sub(depth_diff),
+                        NodeType::PV,
+                    )?;
+                    let score = -result.score;
+                    match window {
+                        Some((low, high)) if score <= low || score >= high => {
+                            self.aspiration_researches += 1;
+                            researches += 1;
+                            window = self.widen_aspiration_window(low, high, score, researches);
+                        }
+                        _ => break (result, score),
+                    }
+                };
+                // Only exact scores, above `alpha`, are mapped.
+                let score = if score > alpha {
+                    self.root_score(score)
+                } else {
+                    score
+                };
                 self.root_moves[self.root_moves_considered].score = score;
+                self.root_moves[self.root_moves_considered].repetition =
+                    result.repetition_ply != Ply::MAX;
                 completed_depth = completed_depth.min(result.depth.saturating_add(depth_diff));
                 if score > alpha {
                     self.root_moves[self.root_moves_exact_score..=self.root_moves_considered]
                         .rotate_right(1);
                     self.root_moves_exact_score += 1;
-                    if score > self.root_moves[0].score {
+                    if self.root_moves[self.root_moves_exact_score - 1].key()
+                        > self.root_moves[0].key()
+                    {
                         self.root_moves[0..self.root_moves_exact_score].rotate_right(1);
                         self.pv = result.pv.add_front(mov);
                     }
@@ -461,9 +994,50 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         }
         self.depth = completed_depth;
         self.sort_root_moves();
+        self.completed = true;
+        self.top_moves = self.top_moves_within_threshold(self.root_moves[0].score);
         Ok(())
     }
 
+    /// The initial aspiration window for the first root move, around the previous depth's score.
+    fn aspiration_window(&self) -> Option<(Score, Score)> {
+        let width = self.aspiration_window?;
+        // `root_score` changes draw scores, so bounds around them don't hold.
+        if self.depth < self.hyperparameters.aspiration_min_depth || self.must_win {
+            return None;
+        }
+        let ScoreExpanded::Eval(eval) = ScoreExpanded::from(self.root_moves[0].score) else {
+            return None;
+        };
+        Some((
+            ScoreExpanded::Eval(eval.saturating_sub(width)).into(),
+            ScoreExpanded::Eval(eval.saturating_add(width)).into(),
+        ))
+    }
+
+    /// The window after `score` fell outside `low..high`, or `None` for a full window.
+    fn widen_aspiration_window(
+        &self,
+        low: Score,
+        high: Score,
+        score: Score,
+        researches: u32,
+    ) -> Option<(Score, Score)> {
+        const MAX_RESEARCHES: u32 = 2;
+        if researches >= MAX_RESEARCHES {
+            return None;
+        }
+        let ScoreExpanded::Eval(eval) = ScoreExpanded::from(score) else {
+            return None;
+        };
+        let width = self.aspiration_window?.saturating_mul(1 << researches);
+        if score <= low {
+            Some((ScoreExpanded::Eval(eval.saturating_sub(width)).into(), high))
+        } else {
+            Some((low, ScoreExpanded::Eval(eval.saturating_add(width)).into()))
+        }
+    }
+
     /// Recursive search function.
     fn search_alpha_beta<V: ExtendableVariation>(
         &mut self,
@@ -479,7 +1053,7 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
 
         // Prune guaranteed draws or endgames (including lower/upper bounds)
         let earliest_win = ply + 3; // if we deliver checkmate this move
-        let best_possible = if earliest_win > PLY_DRAW {
+        let best_possible = if earliest_win > position.ply_draw() {
             Score::DRAW
         } else {
             ScoreExpanded::Win(earliest_win).into()
@@ -491,7 +1065,7 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         } else {
             ply + 4 // if we get checkmated next move (ignore zugzwang)
         };
-        let worst_possible = if earliest_loss > PLY_DRAW {
+        let worst_possible = if earliest_loss > position.ply_draw() {
             Score::DRAW
         } else {
             ScoreExpanded::Loss(earliest_loss).into()
@@ -531,7 +1105,10 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         }
 
         if depth == 0 {
+            #[cfg(not(feature = "make-unmake"))]
             return self.quiescence_search::<V>(eposition, alpha, beta);
+            #[cfg(feature = "make-unmake")]
+            return self.quiescence_search::<V>(&mut eposition.clone(), alpha, beta);
         }
 
         self.new_node()?;
@@ -543,7 +1120,7 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
             if let Some(ttentry) = self.ttable.get(hash) {
                 // Transposition table cutoff.
                 if ttentry.depth >= depth {
-                    let score = ttentry.score.to_absolute(ply);
+                    let score = ttentry.score.to_absolute(ply, position.ply_draw());
                     let cutoff = match ttentry.score_type {
                         TTableScoreType::None => false,
                         TTableScoreType::Exact => true,
@@ -577,14 +1154,15 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         let mov = result.pv.first();
         let pv = result.pv.truncate();
 
-        // Store killer move if beta cutoff and not a capture.
+        // Store killer move and history score if beta cutoff and not a capture.
         if result.score >= beta {
             if let Some(mov) = mov {
                 if mov.captured.is_none() {
+                    self.history_scores.add_cutoff(mov, depth);
                     let killer_moves = &mut self.killer_moves[ply as usize];
-                    let index = (0..NUM_KILLER_MOVES - 1)
+                    let index = (0..KILLERS - 1)
                         .find(|&index| killer_moves[index] == Some(mov))
-                        .unwrap_or(NUM_KILLER_MOVES - 1);
+                        .unwrap_or(KILLERS - 1);
                     killer_moves[index] = Some(mov);
                     killer_moves[0..=index].rotate_right(1);
                 }
@@ -679,10 +1257,11 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
             tt_move = result.pv.first();
         }
 
+        let use_null_move = position.ply() >= self.null_move_min_ply;
         let mut move_candidates =
-            self.generate_move_candidates(position, in_check, true, tt_move, true);
+            self.generate_move_candidates(position, in_check, use_null_move, tt_move, true);
 
-        let mut extra_moves = SmallVec::<Move, { 1 + NUM_KILLER_MOVES }>::new();
+        let mut extra_moves = SmallVec::<Move, { 1 + MAX_KILLER_MOVES }>::new();
 
         let mut move_index = 0;
         let mut enable_late_move_reduction = false;
@@ -696,15 +1275,12 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                         return Ok(());
                     }
 
-                    let Ok(epos2) = eposition.make_move(mov) else {
-                        // Illegal move. Could be a hash collision in the transposition table
-                        // or invalid killer move.
-                        if extra {
-                            return Ok(());
-                        } else {
-                            panic!("Illegal move in search_alpha_beta_deeper");
-                        }
-                    };
+                    if extra && !movegen::is_pseudo_legal(position, mov) {
+                        // Could be a hash collision in the transposition table or invalid
+                        // killer move.
+                        return Ok(());
+                    }
+                    let epos2 = eposition.make_move_unchecked(mov);
 
                     if extra {
                         if movegen::in_check(epos2.position(), position.to_move()) {
@@ -752,8 +1328,12 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                     }
                     let depth2 = depth.saturating_sub(depth_diff);
 
-                    // Try null window.
-                    if alpha2 > immediately_checkmated && beta > alpha2.next() {
+                    // Try null window. Not for the first move, which is expected to be best, so that
+                    // a PV node with a finite `alpha` doesn't search it twice.
+                    if cur_move_index != 0
+                        && alpha2 > immediately_checkmated
+                        && beta > alpha2.next()
+                    {
                         let result2 = self.search_alpha_beta::<EmptyVariation>(
                             &epos2,
                             -alpha2.next(),
@@ -808,7 +1388,7 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                         ScoreExpanded::Loss(_) => true,
                         ScoreExpanded::Eval(beta_eval) => {
                             if lazy_eval.is_none() {
-                                lazy_eval = Some(eposition.evaluate());
+                                lazy_eval = Some(self.static_eval(eposition));
                             }
                             lazy_eval.unwrap() >= beta_eval + self.null_move_margin
                         }
@@ -827,9 +1407,43 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                     )?;
                     self.history.pop();
                     if -result2.score >= beta {
+                        let mut cutoff_depth = result2.depth.saturating_add(depth_diff);
+                        if self
+                            .hyperparameters
+                            .null_verify_depth
+                            .is_some_and(|verify_depth| depth >= verify_depth)
+                        {
+                            // Verify with a reduced search where we have to move, in case
+                            // passing is what made the position look good. Null moves stay
+                            // off at this node (the `+ 1`) and for the first three quarters of
+                            // the verification's plies, where a zugzwang matters most. The last
+                            // quarter may pass again, which keeps the verification cheap; a
+                            // zugzwang that deep is verified at a later iteration.
+                            let verify_plies = u32::from((depth - depth_diff) / ONE_PLY);
+                            let saved_min_ply = self.null_move_min_ply;
+                            self.null_move_min_ply =
+                                Ply::try_from(u32::from(position.ply()) + 3 * verify_plies / 4 + 1)
+                                    .unwrap_or(Ply::MAX);
+                            let verify_result = self.search_alpha_beta_deeper::<OneMoveVariation>(
+                                eposition,
+                                beta.prev(),
+                                beta,
+                                depth - depth_diff,
+                                in_check,
+                                tt_move,
+                                NodeType::Cut,
+                            );
+                            self.null_move_min_ply = saved_min_ply;
+                            let verify_result = verify_result?;
+                            if verify_result.score < beta {
+                                return Ok(());
+                            }
+                            cutoff_depth =
+                                cutoff_depth.min(verify_result.depth.saturating_add(depth_diff));
+                        }
                         result = SearchResultInternal {
                             score: beta,
-                            depth: result2.depth.saturating_add(depth_diff),
+                            depth: cutoff_depth,
                             pv: V::empty_truncated(),
                             // Repetitions don't count accross null move.
                             repetition_ply: Ply::MAX,
@@ -844,7 +1458,7 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                             ScoreExpanded::Loss(_) => false,
                             ScoreExpanded::Eval(alpha_eval) => {
                                 if lazy_eval.is_none() {
-                                    lazy_eval = Some(eposition.evaluate());
+                                    lazy_eval = Some(self.static_eval(eposition));
                                 }
                                 lazy_eval.unwrap() <= alpha_eval - self.futility_margin
                             }
@@ -872,10 +1486,44 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         Ok(result)
     }
 
+    /// The evaluator's eval, scaled toward a draw near the move limit if `draw_blend_plies` is
+    /// set.
+    fn static_eval(&mut self, eposition: &EvaluatedPosition<E>) -> Eval {
+        let position = eposition.position();
+        // The eval doesn't depend on the ply, so positions that differ only in the ply share an
+        // entry.
+        let eval = match &mut self.eval_cache {
+            Some(eval_cache) => {
+                let hash = position.hash_for_repetition();
+                eval_cache.get(hash).unwrap_or_else(|| {
+                    let eval = eposition.evaluate();
+                    eval_cache.set(hash, eval);
+                    eval
+                })
+            }
+            None => eposition.evaluate(),
+        };
+        let remaining = position.ply_draw().saturating_sub(position.ply());
+        match self.hyperparameters.draw_blend_plies {
+            Some(blend_plies) if remaining < blend_plies => {
+                (i64::from(eval) * i64::from(remaining) / i64::from(blend_plies)) as Eval
+            }
+            _ => eval,
+        }
+    }
+
+    fn take_eval_cache_stats(&mut self) -> EvalCacheStats {
+        self.eval_cache
+            .as_mut()
+            .map_or_else(EvalCacheStats::default, |eval_cache| {
+                eval_cache.take_stats()
+            })
+    }
+
     /// Quiescence search.
     fn quiescence_search<V: ExtendableVariation>(
         &mut self,
-        eposition: &EvaluatedPosition<E>,
+        eposition: QuiescencePosition<'_, '_, E>,
         alpha: Score,
         beta: Score,
     ) -> Result<SearchResultInternal<V>, Timeout> {
@@ -893,15 +1541,17 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
 
         let position = eposition.position();
         let ply = position.ply();
+        let ply_draw = position.ply_draw();
         let in_check = movegen::in_check(position, position.to_move());
 
         let mut result;
+        #[cfg_attr(feature = "make-unmake", allow(unused_mut))]
         let mut moves;
 
         if in_check {
             // Fastest loss is at ply+2 if we are checkmated.
             // Fastest win is at ply+3 (checkmate in 1).
-            if ply + 2 > PLY_DRAW || ply + 3 > PLY_DRAW && alpha >= Score::DRAW {
+            if ply + 2 > ply_draw || ply + 3 > ply_draw && alpha >= Score::DRAW {
                 return Ok(SearchResultInternal {
                     score: Score::DRAW,
                     depth: 0,
@@ -921,7 +1571,7 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         } else {
             // Fastest win is at ply+3 (checkmate in 1).
             // Fastest loss is at ply+4 (we get checkmated next move).
-            if ply + 3 > PLY_DRAW || ply + 4 > PLY_DRAW && beta <= Score::DRAW {
+            if ply + 3 > ply_draw || ply + 4 > ply_draw && beta <= Score::DRAW {
                 return Ok(SearchResultInternal {
                     score: Score::DRAW,
                     depth: 0,
@@ -945,7 +1595,7 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                 Color::Red => self.red_contempt,
                 Color::Blue => -self.red_contempt,
             };
-            let eval = eposition.evaluate() + contempt;
+            let eval = self.static_eval(eposition) + contempt;
             result = SearchResultInternal {
                 score: ScoreExpanded::Eval(eval).into(),
                 depth: 0,
@@ -955,19 +1605,34 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
             if result.score >= beta {
                 return Ok(result);
             }
+            // Captures that lose material by `movegen::see` are pruned unless they give check.
             moves = Either::Case1(
-                movegen::captures_checks(eposition.position())
-                    .chain(movegen::captures_non_checks(eposition.position())),
+                movegen::captures_checks(position).chain(
+                    movegen::captures_non_checks(position)
+                        .filter(|&mov| movegen::see(position, mov) >= 0),
+                ),
             );
         }
 
-        let iteration_result = moves.try_for_each_result(|mov| {
-            let epos2 = eposition
-                .make_move(mov)
-                .expect("Illegal move in quiescence search");
+        // The move generators borrow the position, so with make/unmake collect the moves first.
+        #[cfg(feature = "make-unmake")]
+        let moves: SmallVec<Move, MAX_QUIESCENCE_MOVES> = moves.collect();
 
+        let search_move = |mov: Move| {
             let alpha2 = alpha.max(result.score);
-            let result2 = self.quiescence_search::<V>(&epos2, -beta, -alpha2)?;
+            #[cfg(not(feature = "make-unmake"))]
+            let result2 = {
+                let epos2 = eposition.make_move_unchecked(mov);
+                self.quiescence_search::<V>(&epos2, -beta, -alpha2)
+            };
+            #[cfg(feature = "make-unmake")]
+            let result2 = {
+                let undo = eposition.make_move_in_place(mov);
+                let result2 = self.quiescence_search::<V>(eposition, -beta, -alpha2);
+                eposition.unmake_move(undo);
+                result2
+            };
+            let result2 = result2?;
             let score = -result2.score;
             if score > result.score {
                 result.score = score;
@@ -979,7 +1644,11 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                 }
             }
             Ok(())
-        });
+        };
+        #[cfg(not(feature = "make-unmake"))]
+        let iteration_result = moves.try_for_each_result(search_move);
+        #[cfg(feature = "make-unmake")]
+        let iteration_result = moves.into_iter().try_for_each(search_move);
         if let Err(TimeoutOrBreak::Timeout) = iteration_result {
             return Err(Timeout);
         }
@@ -987,11 +1656,37 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         Ok(result)
     }
 
+    /// Sets the hard deadline, checking it more often when it is close.
+    fn set_hard_deadline(&mut self, deadline: Option<Instant>) {
+        self.hard_deadline = deadline;
+        self.check_timeout_nodes = match deadline {
+            Some(deadline)
+                if deadline.saturating_duration_since(Instant::now())
+                    < self.hyperparameters.low_latency_time =>
+            {
+                self.hyperparameters.low_latency_check_timeout_nodes
+            }
+            _ => self.hyperparameters.check_timeout_nodes,
+        };
+    }
+
+    fn timeout(&mut self, kind: TimeoutKind) {
+        self.timeouts.add(kind);
+        self.aborted_reason = Some(kind);
+    }
+
     fn new_node(&mut self) -> Result<(), Timeout> {
         self.nodes += 1;
-        if let Some(deadline) = self.hard_deadline {
-            if self.nodes % CHECK_TIMEOUT_NODES == 0 && Instant::now() >= deadline {
-                log::info!("hto"); // hard timeout
+        if self.nodes % self.check_timeout_nodes == 0 {
+            if self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed)) {
+                self.timeout(TimeoutKind::Stopped);
+                return Err(Timeout);
+            }
+            if self
+                .hard_deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                self.timeout(TimeoutKind::Hard);
                 return Err(Timeout);
             }
         }
@@ -1008,7 +1703,10 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
     ) -> Either<
         impl Iterator<Item = MoveCandidate> + 'pos,
         impl Iterator<Item = MoveCandidate> + 'pos,
-    > {
+    >
+    where
+        'a: 'pos,
+    {
         let tt_move = tt_move.into_iter().map(MoveCandidate::extra);
         if in_check {
             Either::Case0(tt_move.chain(movegen::check_evasions(position).map(MoveCandidate::new)))
@@ -1020,11 +1718,7 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
             }
             .into_iter();
 
-            let captures = movegen::captures_checks(position)
-                .chain(movegen::captures_non_checks(position))
-                .map(MoveCandidate::new);
-
-            let futility = iter::once(MoveCandidate::Futility);
+            let captures_checks = movegen::captures_checks(position).map(MoveCandidate::new);
 
             let killers = if use_killers {
                 Some(
@@ -1043,20 +1737,53 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                 .chain(movegen::jumps_checks(position))
                 .map(MoveCandidate::new);
 
-            let quiet_moves = movegen::drops_check_threats(position)
-                .chain(movegen::drops_attack_escape(position))
-                .chain(movegen::jumps_check_threats(position))
-                .chain(movegen::jumps_attack_escape(position))
-                .chain(movegen::jumps_boring(position))
-                .chain(movegen::drops_boring(position))
+            // Captures that lose material by `movegen::see` wait until after the killers and
+            // checks, unless they give check themselves. `see` is computed once per capture: the
+            // losing ones are set aside as the others are generated.
+            let mut captures_non_checks = movegen::captures_non_checks(position).fuse();
+            let mut killers_and_checks = killers.chain(checks);
+            let mut losing_captures: SmallVec<Move, MAX_CAPTURES> = SmallVec::new();
+            let mut num_losing_captures_generated = 0;
+            let captures_to_losing_captures = iter::from_fn(move || {
+                for mov in captures_non_checks.by_ref() {
+                    if movegen::see(position, mov) >= 0 {
+                        return Some(MoveCandidate::new(mov));
+                    }
+                    losing_captures.push(mov);
+                }
+                if let Some(candidate) = killers_and_checks.next() {
+                    return Some(candidate);
+                }
+                let &mov = losing_captures.get(num_losing_captures_generated)?;
+                num_losing_captures_generated += 1;
+                Some(MoveCandidate::new(mov))
+            });
+
+            let futility = iter::once(MoveCandidate::Futility);
+
+            // Generated only when reached, then ordered by history score. The sort is stable, so
+            // moves without a history keep the generation order.
+            let history_scores = self.history_scores;
+            let quiet_moves = iter::once(())
+                .flat_map(move |()| {
+                    let mut moves: SmallVec<Move, MAX_QUIET_MOVES> =
+                        movegen::drops_check_threats(position)
+                            .chain(movegen::drops_attack_escape(position))
+                            .chain(movegen::jumps_check_threats(position))
+                            .chain(movegen::jumps_attack_escape(position))
+                            .chain(movegen::jumps_boring(position))
+                            .chain(movegen::drops_boring(position))
+                            .collect();
+                    moves.sort_by_key(|&mov| Reverse(history_scores.get(mov)));
+                    moves
+                })
                 .map(MoveCandidate::new);
 
             Either::Case1(
                 null_move
                     .chain(tt_move)
-                    .chain(captures)
-                    .chain(killers)
-                    .chain(checks)
+                    .chain(captures_checks)
+                    .chain(captures_to_losing_captures)
                     .chain(futility)
                     .chain(quiet_moves),
             )
@@ -1069,8 +1796,14 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         self.root_moves_setup = possible_moves.to_vec();
         self.ttable.new_epoch();
         self.pvtable.new_epoch();
+        self.history_scores.age();
         let eposition = EvaluatedPosition::new(self.evaluator, self.root_position);
         _ = self.blue_setup_iterative_deepening(&eposition);
+        let position_after_setup = self
+            .root_position
+            .make_setup_move(self.root_moves_setup[0])
+            .unwrap();
+        self.pv.truncate_illegal(&position_after_setup);
         SearchResultBlueSetup {
             score: self.blue_setup_score,
             mov: self.root_moves_setup[0],
@@ -1079,6 +1812,12 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
             root_moves_considered: self.root_moves_considered,
             num_root_moves: self.root_moves_setup.len(),
             nodes: self.nodes,
+            branching_factor: effective_branching_factor(self.nodes, self.depth),
+            pvtable_stats: self.pvtable.take_stats(),
+            eval_cache_stats: self.take_eval_cache_stats(),
+            timeouts: self.timeouts,
+            completed: self.completed,
+            aborted_reason: self.aborted_reason,
         }
     }
 
@@ -1090,7 +1829,7 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         while self.depth < self.max_depth {
             if let Some(ds) = self.deadlines.as_ref() {
                 if Instant::now() >= ds.start_next_depth {
-                    log::info!("ndto"); // next depth timeout
+                    self.timeout(TimeoutKind::NextDepth);
                     break;
                 }
             }
@@ -1103,6 +1842,7 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
         &mut self,
         eposition: &EvaluatedPosition<E>,
     ) -> Result<(), Timeout> {
+        self.completed = false;
         let panic_threshold = match ScoreExpanded::from(self.blue_setup_score) {
             ScoreExpanded::Win(_) => Score::WIN_MAX_PLY,
             ScoreExpanded::Loss(_) => -Score::INFINITE,
@@ -1120,12 +1860,12 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
                     self.root_moves_considered != 0 && self.blue_setup_score < panic_threshold;
                 let soft_deadline = if is_panic { ds.panic_soft } else { ds.soft };
                 if self.root_moves_considered != 0 && Instant::now() >= soft_deadline {
-                    log::info!("sto"); // soft timeout
+                    self.timeout(TimeoutKind::Soft);
                     return Err(Timeout);
                 }
-                self.hard_deadline = Some(if is_panic { ds.panic_hard } else { ds.hard });
+                self.set_hard_deadline(Some(if is_panic { ds.panic_hard } else { ds.hard }));
             } else {
-                self.hard_deadline = None;
+                self.set_hard_deadline(None);
             }
             let mov = self.root_moves_setup[self.root_moves_considered];
             let epos2 = eposition.make_setup_move(mov).unwrap();
@@ -1192,19 +1932,99 @@ impl<'a, E: Evaluator> SearchInstance<'a, E> {
             self.root_moves_considered += 1;
         }
         // Other moves.
+        self.completed = true;
         Ok(())
     }
 }
 
+fn is_stable(prev_score: Score, score: Score, margin: Eval) -> bool {
+    match (ScoreExpanded::from(prev_score), ScoreExpanded::from(score)) {
+        (ScoreExpanded::Eval(prev_eval), ScoreExpanded::Eval(eval)) => {
+            (eval - prev_eval).abs() < margin
+        }
+        _ => prev_score == score,
+    }
+}
+
+/// Iterator returned by `Search::analyze`.
+struct Analysis<'a, E: Evaluator, const KILLERS: usize> {
+    instance: SearchInstance<'a, E, KILLERS>,
+    // Set once iterative deepening has started.
+    eposition: Option<EvaluatedPosition<'a, E>>,
+    done: bool,
+}
+
+impl<E: Evaluator, const KILLERS: usize> Iterator for Analysis<'_, E, KILLERS> {
+    type Item = SearchResult;
+
+    fn next(&mut self) -> Option<SearchResult> {
+        if self.done {
+            return None;
+        }
+        let instance = &mut self.instance;
+        let completed = match &self.eposition {
+            None => {
+                if !instance.prepare_root(true, RootMoveFilter::All) {
+                    self.done = true;
+                    return Some(instance.result());
+                }
+                let eposition = EvaluatedPosition::new(instance.evaluator, instance.root_position);
+                let completed = instance.search_shallow(&eposition);
+                self.eposition = Some(eposition);
+                completed
+            }
+            Some(eposition) => {
+                if !instance.should_deepen() {
+                    self.done = true;
+                    return None;
+                }
+                instance.iterative_deepening_iteration(eposition)
+            }
+        };
+        match completed {
+            Ok(()) => Some(instance.result()),
+            Err(Timeout) => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+impl<E: Evaluator, const KILLERS: usize> Drop for SearchInstance<'_, E, KILLERS> {
+    fn drop(&mut self) {
+        self.cumulative_stats.searches += 1;
+        self.cumulative_stats.nodes += self.nodes;
+        self.cumulative_stats.time += self.start_time.elapsed();
+        self.cumulative_stats.aspiration_researches += u64::from(self.aspiration_researches);
+    }
+}
+
 pub struct SearchResult {
     pub score: Score,
     pub pv: LongVariation,
-    // Only used for multi-move searches.
+    // Only used for multi-move searches. If the last depth was interrupted, the moves from the
+    // last completed depth.
     pub top_moves: Vec<ScoredMove>,
     pub depth: Depth,
     pub root_moves_considered: usize,
     pub num_root_moves: usize,
     pub nodes: u64,
+    /// Nodes searched by the helper threads of `Search::search`, not included in `nodes`.
+    pub helper_nodes: u64,
+    /// `nodes^(1/plies)`. `None` if no depth was searched.
+    pub branching_factor: Option<f64>,
+    pub pvtable_stats: PVTableStats,
+    /// All zero if the eval cache is disabled.
+    pub eval_cache_stats: EvalCacheStats,
+    pub timeouts: TimeoutStats,
+    /// How many times the score fell outside the aspiration window, over all depths.
+    pub aspiration_researches: u32,
+    /// Whether the last depth finished all root moves. Otherwise some root move scores come from
+    /// the previous depth.
+    pub completed: bool,
+    /// The deadline that stopped the search before `max_depth`, if any.
+    pub aborted_reason: Option<TimeoutKind>,
 }
 
 pub struct SearchResultBlueSetup {
@@ -1215,8 +2035,112 @@ pub struct SearchResultBlueSetup {
     pub root_moves_considered: usize,
     pub num_root_moves: usize,
     pub nodes: u64,
+    /// `nodes^(1/plies)`. `None` if no depth was searched.
+    pub branching_factor: Option<f64>,
+    pub pvtable_stats: PVTableStats,
+    /// All zero if the eval cache is disabled.
+    pub eval_cache_stats: EvalCacheStats,
+    pub timeouts: TimeoutStats,
+    /// Whether the last depth finished all root moves. Otherwise some root move scores come from
+    /// the previous depth.
+    pub completed: bool,
+    /// The deadline that stopped the search before `max_depth`, if any.
+    pub aborted_reason: Option<TimeoutKind>,
+}
+
+/// Totals over many searches, e.g. a whole game.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CumulativeStats {
+    pub searches: u64,
+    pub nodes: u64,
+    pub time: Duration,
+    /// See `SearchResult::aspiration_researches`.
+    pub aspiration_researches: u64,
+}
+
+impl CumulativeStats {
+    /// `None` if no time was spent searching.
+    pub fn nodes_per_second(&self) -> Option<f64> {
+        let seconds = self.time.as_secs_f64();
+        (seconds > 0.0).then(|| self.nodes as f64 / seconds)
+    }
+}
+
+/// Which deadline, or the stop flag, stopped a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutKind {
+    /// `start_next_depth` prevented starting another depth. The last depth is complete.
+    NextDepth,
+    /// `soft` passed during the shallow search.
+    ShallowSoft,
+    /// `soft` or `panic_soft` passed between root moves.
+    Soft,
+    /// `hard` or `panic_hard` passed in the middle of the search.
+    Hard,
+    /// The stop flag was set.
+    Stopped,
 }
 
+/// How many times each deadline, or the stop flag, stopped the search.
+///
+/// Displayed as the fired timeouts with their counts, e.g. `sto=1,hto=1`, or `none`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutStats {
+    /// `start_next_depth` prevented starting another depth.
+    pub next_depth: u32,
+    /// `soft` passed during the shallow search.
+    pub shallow_soft: u32,
+    /// `soft` or `panic_soft` passed between root moves.
+    pub soft: u32,
+    /// `hard` or `panic_hard` passed in the middle of the search.
+    pub hard: u32,
+    /// The stop flag was set.
+    pub stopped: u32,
+}
+
+impl TimeoutStats {
+    pub fn add(&mut self, kind: TimeoutKind) {
+        match kind {
+            TimeoutKind::NextDepth => self.next_depth += 1,
+            TimeoutKind::ShallowSoft => self.shallow_soft += 1,
+            TimeoutKind::Soft => self.soft += 1,
+            TimeoutKind::Hard => self.hard += 1,
+            TimeoutKind::Stopped => self.stopped += 1,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.next_depth + self.shallow_soft + self.soft + self.hard + self.stopped
+    }
+}
+
+impl Display for TimeoutStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let counts = [
+            ("ndto", self.next_depth),
+            ("ssto", self.shallow_soft),
+            ("sto", self.soft),
+            ("hto", self.hard),
+            ("stop", self.stopped),
+        ];
+        let mut first = true;
+        for (name, count) in counts {
+            if count != 0 {
+                if !first {
+                    write!(f, ",")?;
+                }
+                write!(f, "{name}={count}")?;
+                first = false;
+            }
+        }
+        if first {
+            write!(f, "none")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
 pub struct ScoredMove {
     pub mov: Move,
     pub score: Score,
@@ -1226,6 +2150,84 @@ struct RootMove {
     mov: Move,
     score: Score,
     futile: bool,
+    // The score depends on a repetition within the search.
+    repetition: bool,
+}
+
+impl RootMove {
+    /// Sort key: higher is better.
+    ///
+    /// When winning, prefers lines that don't allow a repetition draw.
+    fn key(&self) -> (Score, bool) {
+        (self.score, self.score > Score::DRAW && !self.repetition)
+    }
+}
+
+/// The branching factor of a uniform tree with `nodes` nodes and the given depth.
+fn effective_branching_factor(nodes: u64, depth: Depth) -> Option<f64> {
+    if depth == 0 || depth == Depth::MAX || nodes == 0 {
+        return None;
+    }
+    let plies = f64::from(depth) / f64::from(ONE_PLY);
+    Some((nodes as f64).powf(plies.recip()))
+}
+
+/// Which root moves a search considers.
+#[derive(Clone, Copy)]
+enum RootMoveFilter<'a> {
+    All,
+    Only(&'a [Move]),
+    Exclude(&'a [Move]),
+}
+
+impl RootMoveFilter<'_> {
+    fn allows(self, mov: Move) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(moves) => moves.contains(&mov),
+            Self::Exclude(moves) => !moves.contains(&mov),
+        }
+    }
+}
+
+/// History heuristic: how often quiet moves of each piece to each square caused beta cutoffs,
+/// weighted by depth.
+struct HistoryScores {
+    // `Cell` so that move ordering can read the scores while the search updates them.
+    scores: EnumMap<ColoredPiece, EnumMap<Square, Cell<i32>>>,
+}
+
+impl HistoryScores {
+    /// Scores are halved when one gets this large.
+    const MAX: i32 = 1 << 24;
+
+    fn new() -> Self {
+        Self {
+            scores: EnumMap::from_fn(|_| EnumMap::from_fn(|_| Cell::new(0))),
+        }
+    }
+
+    fn get(&self, mov: Move) -> i32 {
+        self.scores[mov.colored_piece][mov.to].get()
+    }
+
+    fn add_cutoff(&self, mov: Move, depth: Depth) {
+        let plies = i32::from(depth / ONE_PLY);
+        let score = &self.scores[mov.colored_piece][mov.to];
+        score.set(score.get() + plies);
+        if score.get() >= Self::MAX {
+            self.age();
+        }
+    }
+
+    /// Halves all scores, so that older searches count less.
+    fn age(&self) {
+        for (_, scores) in self.scores.iter() {
+            for (_, score) in scores.iter() {
+                score.set(score.get() / 2);
+            }
+        }
+    }
 }
 
 enum MoveCandidate {
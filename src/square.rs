@@ -21,6 +21,10 @@ pub enum Square {
 
 unsafe_simple_enum!(Square, 64);
 
+const _: () = assert!(Coord::WIDTH * Coord::HEIGHT == Square::COUNT);
+// Coordinates are written as a single letter and a single digit.
+const _: () = assert!(Coord::HEIGHT <= 26 && Coord::WIDTH <= 9);
+
 impl Square {
     pub const fn add(self, direction: Direction) -> Option<Self> {
         match Coord::from_square(self).add(direction) {
@@ -29,6 +33,14 @@ impl Square {
         }
     }
 
+    /// The square `dx` columns and `dy` rows away, if on the board.
+    pub const fn offset(self, dx: isize, dy: isize) -> Option<Self> {
+        match Coord::from_square(self).offset(dx, dy) {
+            None => None,
+            Some(coord2) => Some(Square::from_coord(coord2)),
+        }
+    }
+
     pub const fn from_coord(coord: Coord) -> Self {
         let index = coord.y() * Coord::WIDTH + coord.x();
         unsafe { Self::from_index_unchecked(index) }
@@ -73,6 +85,30 @@ impl Coord {
         }
     }
 
+    /// Like `new`, but returns `None` outside the board.
+    pub const fn try_new(x: usize, y: usize) -> Option<Self> {
+        if x < Self::WIDTH && y < Self::HEIGHT {
+            Some(Self::new(x, y))
+        } else {
+            None
+        }
+    }
+
+    /// All coordinates, in `Square` order.
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..Self::HEIGHT).flat_map(Self::row)
+    }
+
+    /// Coordinates with the given `y`, by increasing `x`.
+    pub fn row(y: usize) -> impl Iterator<Item = Self> {
+        (0..Self::WIDTH).map(move |x| Self::new(x, y))
+    }
+
+    /// Coordinates with the given `x`, by increasing `y`.
+    pub fn column(x: usize) -> impl Iterator<Item = Self> {
+        (0..Self::HEIGHT).map(move |y| Self::new(x, y))
+    }
+
     pub const fn x(self) -> usize {
         self.x as usize
     }
@@ -99,14 +135,25 @@ impl Coord {
         }
     }
 
+    /// The coordinate `dx` columns and `dy` rows away, if on the board.
+    pub const fn offset(self, dx: isize, dy: isize) -> Option<Self> {
+        match (
+            (self.x as usize).checked_add_signed(dx),
+            (self.y as usize).checked_add_signed(dy),
+        ) {
+            (Some(x), Some(y)) => Self::try_new(x, y),
+            _ => None,
+        }
+    }
+
     pub fn parser() -> impl Parser<Output = Self> {
         parser::byte()
-            .try_map(|b| match b {
-                b'a'..=b'h' => Ok(b - b'a'),
+            .try_map(|b| match b.checked_sub(b'a') {
+                Some(y) if usize::from(y) < Coord::HEIGHT => Ok(y),
                 _ => Err(ParseError),
             })
-            .and(parser::byte().try_map(|b| match b {
-                b'1'..=b'8' => Ok(b - b'1'),
+            .and(parser::byte().try_map(|b| match b.checked_sub(b'1') {
+                Some(x) if usize::from(x) < Coord::WIDTH => Ok(x),
                 _ => Err(ParseError),
             }))
             .map(|(y, x)| Coord { x, y })
@@ -1,4 +1,4 @@
-use crate::{enums::EnumMap, unsafe_simple_enum, Color, Coord, Piece, SetupMove, Square};
+use crate::{enums::EnumMap, unsafe_simple_enum, Color, Coord, SetupMove, Square};
 use std::fmt::{self, Display, Formatter};
 
 /// Apply FlipX, FlipY and SwapXY in that order.
@@ -17,7 +17,13 @@ pub enum Symmetry {
 
 unsafe_simple_enum!(Symmetry, 8);
 
+// SwapXY requires a square board, normalization requires an even size.
+const _: () = assert!(Coord::WIDTH == Coord::HEIGHT && Coord::WIDTH % 2 == 0);
+
 impl Symmetry {
+    /// The symmetries supported by `apply_to_setup`.
+    pub const SETUP_SYMMETRIES: [Self; 2] = [Self::Identity, Self::FlipX];
+
     pub const fn from_bits(flip_x: bool, flip_y: bool, swap_xy: bool) -> Self {
         Self::from_index(flip_x as usize | (flip_y as usize) << 1 | (swap_xy as usize) << 2)
     }
@@ -65,21 +71,10 @@ impl Symmetry {
         }
     }
 
+    /// `SetupMove::normalize` for red setups.
     pub fn normalize_red_setup(setup: SetupMove) -> (Self, SetupMove) {
         assert_eq!(setup.color, Color::Red);
-        let wazir_square = setup
-            .pieces
-            .iter()
-            .position(|&piece| piece == Piece::Wazir)
-            .expect("Invalid setup");
-        let wazir_square = Square::from_index(wazir_square);
-        let wazir_coord = Coord::from_square(wazir_square);
-        let symmetry = if wazir_coord.x() < Coord::WIDTH / 2 {
-            Self::Identity
-        } else {
-            Self::FlipX
-        };
-        (symmetry, symmetry.apply_to_setup(setup))
+        setup.normalize()
     }
 }
 
@@ -111,6 +106,11 @@ pub enum NormalizedSquare {
 
 unsafe_simple_enum!(NormalizedSquare, 10);
 
+const _: () = assert!({
+    let half = Coord::WIDTH / 2;
+    NormalizedSquare::COUNT == half * (half + 1) / 2
+});
+
 impl NormalizedSquare {
     pub const fn try_from_square(square: Square) -> Option<Self> {
         match square {
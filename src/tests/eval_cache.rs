@@ -0,0 +1,20 @@
+use crate::{EvalCache, EvalCacheStats};
+
+#[test]
+fn test_eval_cache() {
+    let mut eval_cache = EvalCache::new(1 << 14);
+    assert!(eval_cache.memory_bytes() <= 1 << 14);
+    let hash = 0x1234567890abcdef;
+    assert_eq!(eval_cache.get(hash), None);
+    eval_cache.set(hash, -17);
+    assert_eq!(eval_cache.get(hash), Some(-17));
+    // Same slot, different position.
+    assert_eq!(eval_cache.get(hash ^ 1 << 63), None);
+    eval_cache.set(hash ^ 1 << 63, 5);
+    assert_eq!(eval_cache.get(hash), None);
+    let stats = eval_cache.take_stats();
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 3);
+    assert_eq!(stats.hit_rate(), 0.25);
+    assert_eq!(eval_cache.take_stats(), EvalCacheStats::default());
+}
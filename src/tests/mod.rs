@@ -1,2 +1,4 @@
+mod eval_cache;
 mod pvtable;
 mod ttable;
+mod zobrist;
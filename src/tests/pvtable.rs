@@ -1,4 +1,4 @@
-use crate::{ExtendableVariation, LongVariation, Move, PVTable, Variation};
+use crate::{ExtendableVariation, LongVariation, Move, PVTable, PVTableStats, Variation};
 use std::str::FromStr;
 
 #[test]
@@ -10,3 +10,26 @@ fn test_ttable() {
     assert_eq!(ttable.get(hash).unwrap().to_string(), "A@a1");
     assert!(ttable.get(hash + 1).is_none());
 }
+
+#[test]
+fn test_pvtable_stats() {
+    let mut pvtable = PVTable::new(1 << 14);
+    assert!(pvtable.is_empty());
+    assert_eq!(pvtable.hashfull(), 0);
+    let variation = LongVariation::empty().add_front(Move::from_str("A@a1").unwrap());
+    // All in the same bucket, so the last one evicts one of the others.
+    for i in 1..=5 {
+        pvtable.set(i << 32 | 5, variation.clone());
+    }
+    assert_eq!(pvtable.len(), 4);
+    assert!(pvtable.hashfull() > 0);
+    assert!(pvtable.get(5 << 32 | 5).is_some());
+    assert!(pvtable.get(6 << 32 | 5).is_none());
+    let stats = pvtable.take_stats();
+    assert_eq!(stats.stores, 5);
+    assert_eq!(stats.evictions, 1);
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.hit_rate(), 0.5);
+    assert_eq!(pvtable.take_stats(), PVTableStats::default());
+}
@@ -1,12 +1,12 @@
 use crate::{
     ttable::{TTable, TTableEntry, TTableScoreType},
-    Move, ScoreExpanded,
+    Move, Score, ScoreExpanded,
 };
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc, thread};
 
 #[test]
 fn test_ttable() {
-    let mut ttable = TTable::new(1024);
+    let ttable = TTable::new(1024);
     let hash = 0x1234567890abcdef;
     let entry = TTableEntry {
         depth: 10,
@@ -18,3 +18,82 @@ fn test_ttable() {
     assert_eq!(ttable.get(hash), Some(entry));
     assert!(ttable.get(hash + 1).is_none());
 }
+
+#[test]
+fn test_ttable_get_refreshes_epoch() {
+    // One bucket.
+    let ttable = TTable::new(64);
+    let entry = |depth| TTableEntry {
+        depth,
+        mov: None,
+        score_type: TTableScoreType::LowerBound,
+        score: ScoreExpanded::Eval(0).into(),
+    };
+    for i in 0..4 {
+        ttable.set(i << 32, entry(if i == 0 { 1 } else { 10 }));
+    }
+    ttable.new_epoch();
+    // The shallowest entry would be replaced first, but reading it makes it current.
+    assert_eq!(ttable.get(0), Some(entry(1)));
+    for i in 4..7 {
+        ttable.set(i << 32, entry(10));
+    }
+    assert_eq!(ttable.get(0), Some(entry(1)));
+    assert!(ttable.get(1 << 32).is_none());
+}
+
+#[test]
+fn test_ttable_entry_round_trip() {
+    let ttable = TTable::new(1024);
+    let moves = [None, Some("Wa2xnb2"), Some("Fb3-a4"), Some("n@h8")];
+    let scores = [
+        ScoreExpanded::Eval(-100).into(),
+        ScoreExpanded::Win(5).into(),
+        ScoreExpanded::Loss(8).into(),
+        Score::DRAW,
+    ];
+    let score_types = [
+        TTableScoreType::None,
+        TTableScoreType::Exact,
+        TTableScoreType::LowerBound,
+        TTableScoreType::UpperBound,
+    ];
+    for (i, ((mov, score), score_type)) in moves.iter().zip(scores).zip(score_types).enumerate() {
+        let hash = (i as u64 + 1) << 40;
+        let entry = TTableEntry {
+            depth: 1000 + i as u16,
+            mov: mov.map(|mov| Move::from_str(mov).unwrap()),
+            score_type,
+            score,
+        };
+        ttable.set(hash, entry);
+        assert_eq!(ttable.get(hash), Some(entry));
+    }
+}
+
+#[test]
+fn test_ttable_shared() {
+    let ttable = Arc::new(TTable::new(1 << 16));
+    let entry = |i: u64| TTableEntry {
+        depth: i as u16,
+        mov: None,
+        score_type: TTableScoreType::LowerBound,
+        score: ScoreExpanded::Eval(i as i32).into(),
+    };
+    let threads: Vec<_> = (0..4)
+        .map(|t| {
+            let ttable = Arc::clone(&ttable);
+            thread::spawn(move || {
+                for i in (t + 1..=256).step_by(4) {
+                    ttable.set(i << 32 | i, entry(i));
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    for i in 1..=256 {
+        assert_eq!(ttable.get(i << 32 | i), Some(entry(i)));
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{enums::SimpleEnumExt, zobrist, Color, ColoredPiece, Move, Position, Square};
+use std::{collections::HashSet, str::FromStr};
+
+#[test]
+fn test_test_keys() {
+    let mut keys = HashSet::new();
+    for color in Color::all() {
+        assert!(keys.insert(zobrist::to_move(color)));
+    }
+    for cpiece in ColoredPiece::all() {
+        for square in Square::all() {
+            assert!(keys.insert(zobrist::colored_piece_square(cpiece, square)));
+        }
+    }
+}
+
+#[test]
+fn test_test_key_hashes() {
+    // Pinned so that changes to hashing are noticed, independent of the generated tables.
+    assert_eq!(Position::initial().hash(), 0x89a0fa429d58212b);
+
+    let position = Position::from_str(
+        "\
+regular
+100
+AFf
+.W.A.D.D
+AaFA.DDA
+n.A.A.A.
+......A.
+...a.a.d
+..d...N.
+a.a...f.
+add.w..a
+",
+    )
+    .unwrap();
+    assert_eq!(position.hash(), 0x82548437545dfafa);
+    let after = position
+        .make_move(Move::from_str("Wa2-a3").unwrap())
+        .unwrap();
+    assert_eq!(after.hash(), 0x66ac7cdc35354837);
+    // Incremental updates agree with hashing from scratch.
+    let reparsed = Position::from_str(&after.to_string()).unwrap();
+    assert_eq!(reparsed.hash(), after.hash());
+}
@@ -1,9 +1,17 @@
-use crate::{constants::Depth, Move, Score};
-use std::{cmp::Reverse, mem};
+use crate::{constants::Depth, ColoredPiece, Move, Piece, Score, Square};
+use std::{
+    cmp::Reverse,
+    mem,
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+};
 
+/// Transposition table that can be shared between threads.
+///
+/// Entries are written without locks. A torn write is detected on read
+/// because the key is stored xored with the data.
 pub struct TTable {
     buckets: Vec<Bucket>,
-    epoch: u8,
+    epoch: AtomicU8,
 }
 
 impl TTable {
@@ -12,44 +20,66 @@ impl TTable {
         assert!(num_buckets > 0);
         let num_buckets = 1 << num_buckets.ilog2();
         Self {
-            buckets: vec![Bucket::default(); num_buckets],
-            epoch: 1,
+            buckets: (0..num_buckets).map(|_| Bucket::default()).collect(),
+            epoch: AtomicU8::new(1),
         }
     }
 
-    pub fn new_epoch(&mut self) {
-        self.epoch = if self.epoch == u8::MAX {
-            1
-        } else {
-            self.epoch + 1
-        };
+    /// Bytes allocated, at most the size passed to `new`.
+    pub fn memory_bytes(&self) -> usize {
+        self.buckets.len() * mem::size_of::<Bucket>()
+    }
+
+    pub(crate) fn new_epoch(&self) {
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        let epoch = if epoch == u8::MAX { 1 } else { epoch + 1 };
+        self.epoch.store(epoch, Ordering::Relaxed);
+    }
+
+    pub(crate) fn get(&self, hash: u64) -> Option<TTableEntry> {
+        let (hash, bucket_idx) = self.split_hash(hash);
+        let bucket = &self.buckets[bucket_idx];
+        let (slot, entry) = bucket
+            .entries
+            .iter()
+            .map(|slot| (slot, slot.load()))
+            .find(|(_, entry)| entry.hash == hash)?;
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        if entry.epoch != epoch {
+            slot.refresh_epoch(&entry, epoch);
+        }
+        Some(entry.into())
     }
 
-    pub fn get(&mut self, hash: u64) -> Option<TTableEntry> {
+    /// Like `get`, but doesn't refresh the entry's epoch, so replacement is unaffected.
+    pub fn probe(&self, hash: u64) -> Option<TTableEntry> {
         let (hash, bucket_idx) = self.split_hash(hash);
-        let bucket = &mut self.buckets[bucket_idx];
-        let entry = bucket
+        self.buckets[bucket_idx]
             .entries
-            .iter_mut()
-            .find(|bucket| bucket.hash == hash)?;
-        entry.epoch = self.epoch;
-        Some((&*entry).into())
+            .iter()
+            .map(AtomicEntry::load)
+            .find(|entry| entry.hash == hash)
+            .map(TTableEntry::from)
     }
 
-    pub fn set(&mut self, hash: u64, entry: TTableEntry) {
+    pub(crate) fn set(&self, hash: u64, entry: TTableEntry) {
         let (hash, bucket_idx) = self.split_hash(hash);
-        let bucket = &mut self.buckets[bucket_idx];
-        let best_entry = bucket
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        let bucket = &self.buckets[bucket_idx];
+        let (slot, _) = bucket
             .entries
-            .iter_mut()
-            .max_by_key(|e| (e.hash == hash, e.epoch != self.epoch, Reverse(e.depth)))
+            .iter()
+            .map(|slot| (slot, slot.load()))
+            .max_by_key(|(_, e)| (e.hash == hash, e.epoch != epoch, Reverse(e.depth)))
             .unwrap();
-        best_entry.hash = hash;
-        best_entry.epoch = self.epoch;
-        best_entry.depth = entry.depth;
-        best_entry.mov = entry.mov;
-        best_entry.score_type = entry.score_type;
-        best_entry.score = entry.score;
+        slot.store(&PhysicalEntry {
+            hash,
+            epoch,
+            depth: entry.depth,
+            mov: entry.mov,
+            score_type: entry.score_type,
+            score: entry.score,
+        });
     }
 
     fn split_hash(&self, hash: u64) -> (u32, usize) {
@@ -60,6 +90,10 @@ impl TTable {
     }
 }
 
+/// What a search stored about a position.
+///
+/// `score` is relative to the position the entry belongs to: win and loss distances are counted
+/// from it rather than from the search root.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct TTableEntry {
     pub depth: Depth,
@@ -68,8 +102,8 @@ pub struct TTableEntry {
     pub score: Score,
 }
 
-impl From<&PhysicalEntry> for TTableEntry {
-    fn from(entry: &PhysicalEntry) -> Self {
+impl From<PhysicalEntry> for TTableEntry {
+    fn from(entry: PhysicalEntry) -> Self {
         Self {
             depth: entry.depth,
             mov: entry.mov,
@@ -88,6 +122,10 @@ pub enum TTableScoreType {
     UpperBound,
 }
 
+impl TTableScoreType {
+    const ALL: [Self; 4] = [Self::None, Self::Exact, Self::LowerBound, Self::UpperBound];
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 struct PhysicalEntry {
     hash: u32,
@@ -98,12 +136,102 @@ struct PhysicalEntry {
     score: Score,
 }
 
-const _: () = assert!(mem::size_of::<PhysicalEntry>() == 16);
+/// A `PhysicalEntry` packed into two words: `key ^ data` and `data`.
+///
+/// key: hash (32 bits), depth (16 bits), epoch (8 bits), score type (8 bits).
+/// data: move (32 bits), score (32 bits).
+#[derive(Debug, Default)]
+struct AtomicEntry {
+    key_xor_data: AtomicU64,
+    data: AtomicU64,
+}
 
-#[derive(Debug, Copy, Clone, Default)]
+impl AtomicEntry {
+    fn load(&self) -> PhysicalEntry {
+        let data = self.data.load(Ordering::Relaxed);
+        let key = self.key_xor_data.load(Ordering::Relaxed) ^ data;
+        let entry = PhysicalEntry {
+            hash: (key >> 32) as u32,
+            depth: (key >> 16) as Depth,
+            epoch: (key >> 8) as u8,
+            score_type: TTableScoreType::ALL[key as usize % TTableScoreType::ALL.len()],
+            mov: decode_move(data as u32),
+            score: Score::from_bits((data >> 32) as u32),
+        };
+        // A move that doesn't decode means a torn write, ignore the entry.
+        if entry.mov.is_none() && data as u32 != 0 {
+            return PhysicalEntry::default();
+        }
+        entry
+    }
+
+    fn store(&self, entry: &PhysicalEntry) {
+        let (key, data) = Self::encode(entry);
+        self.key_xor_data.store(key ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+
+    /// Sets the epoch of `entry`, as loaded from this slot, unless another thread has stored
+    /// something else there since.
+    fn refresh_epoch(&self, entry: &PhysicalEntry, epoch: u8) {
+        let (key, data) = Self::encode(entry);
+        let new_key = key & !(0xff << 8) | u64::from(epoch) << 8;
+        _ = self.key_xor_data.compare_exchange(
+            key ^ data,
+            new_key ^ data,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// The key and data words.
+    fn encode(entry: &PhysicalEntry) -> (u64, u64) {
+        let key = u64::from(entry.hash) << 32
+            | u64::from(entry.depth) << 16
+            | u64::from(entry.epoch) << 8
+            | entry.score_type as u64;
+        let data = u64::from(entry.score.to_bits()) << 32 | u64::from(encode_move(entry.mov));
+        (key, data)
+    }
+}
+
+const _: () = assert!(mem::size_of::<AtomicEntry>() == 16);
+
+#[derive(Debug, Default)]
 #[repr(align(64))]
 struct Bucket {
-    entries: [PhysicalEntry; 4],
+    entries: [AtomicEntry; 4],
 }
 
 const _: () = assert!(mem::size_of::<Bucket>() == 64);
+
+/// 0 for `None`. Otherwise: to (6 bits), from + 1 (7 bits), captured + 1 (3 bits),
+/// colored piece (4 bits), 1 (1 bit).
+fn encode_move(mov: Option<Move>) -> u32 {
+    let Some(mov) = mov else {
+        return 0;
+    };
+    let from = mov.from.map_or(0, |square| square.index() + 1);
+    let captured = mov.captured.map_or(0, |piece| piece.index() + 1);
+    (mov.to.index() | from << 6 | captured << 13 | mov.colored_piece.index() << 16 | 1 << 20) as u32
+}
+
+fn decode_move(bits: u32) -> Option<Move> {
+    let bits = bits as usize;
+    let to = bits & 0x3f;
+    let from = bits >> 6 & 0x7f;
+    let captured = bits >> 13 & 0x7;
+    let colored_piece = bits >> 16 & 0xf;
+    if bits >> 20 != 1 || from > Square::COUNT || captured > Piece::COUNT {
+        return None;
+    }
+    if colored_piece >= ColoredPiece::COUNT {
+        return None;
+    }
+    Some(Move {
+        colored_piece: ColoredPiece::from_index(colored_piece),
+        from: from.checked_sub(1).map(Square::from_index),
+        captured: captured.checked_sub(1).map(Piece::from_index),
+        to: Square::from_index(to),
+    })
+}
@@ -1,4 +1,4 @@
-use crate::{constants::MAX_VARIATION_LENGTH, smallvec::SmallVec, Move, PVTable};
+use crate::{constants::MAX_VARIATION_LENGTH, smallvec::SmallVec, Move, PVTable, Position};
 use std::{
     fmt::{self, Display, Formatter},
     ops::Deref,
@@ -57,6 +57,29 @@ impl Display for LongVariation {
     }
 }
 
+impl LongVariation {
+    /// Cuts the variation at the first move that can't be played from `position`.
+    ///
+    /// Such moves can come from hash collisions in the transposition table.
+    pub fn truncate_illegal(&mut self, position: &Position) {
+        let mut position = *position;
+        let mut num_legal = 0;
+        for &mov in self.moves.iter() {
+            let Ok(next) = position.make_move(mov) else {
+                break;
+            };
+            position = next;
+            num_legal += 1;
+        }
+        if num_legal < self.moves.len() {
+            while self.moves.len() > num_legal {
+                _ = self.moves.pop();
+            }
+            self.truncated = true;
+        }
+    }
+}
+
 impl Variation for LongVariation {
     fn empty() -> Self {
         Self {
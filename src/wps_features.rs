@@ -38,6 +38,10 @@ impl WPSFeatures {
 }
 
 impl Features for WPSFeatures {
+    fn name(self) -> &'static str {
+        "wps"
+    }
+
     fn count(self) -> usize {
         Self::COUNT
     }
@@ -1,21 +1,82 @@
+//! Zobrist keys.
+//!
+//! The tables are generated by `gen-zobrist`. Access goes through the functions below so that
+//! unit tests (`cfg(test)`) use a fixed key set derived from `TEST_SEED` instead: hash-dependent
+//! tests then don't change when the tables are regenerated.
+
 use crate::{
     captured_index,
-    constants::{PLY_AFTER_SETUP, PLY_DRAW},
+    constants::{Ply, PLY_AFTER_SETUP, PLY_DRAW},
     enums::EnumMap,
     Color, ColoredPiece, Square, NUM_CAPTURED_INDEXES,
 };
 
+pub fn to_move(color: Color) -> u64 {
+    key(Table::ToMove, color.index(), || TO_MOVE[color])
+}
+
+pub fn colored_piece_square(cpiece: ColoredPiece, square: Square) -> u64 {
+    key(
+        Table::ColoredPieceSquare,
+        cpiece.index() * Square::COUNT + square.index(),
+        || COLORED_PIECE_SQUARE[cpiece][square],
+    )
+}
+
 pub fn captured(cpiece: ColoredPiece, index: usize) -> u64 {
-    CAPTURED[cpiece.color()][captured_index(cpiece.piece(), index)]
+    let index = captured_index(cpiece.piece(), index);
+    key(
+        Table::Captured,
+        cpiece.color().index() * NUM_CAPTURED_INDEXES + index,
+        || CAPTURED[cpiece.color()][index],
+    )
+}
+
+pub fn null_move_counter(counter: u8) -> u64 {
+    let index = usize::from(counter);
+    key(Table::NullMoveCounter, index, || NULL_MOVE_COUNTER[index])
+}
+
+pub fn ply(ply: Ply) -> u64 {
+    let index = usize::from(ply);
+    key(Table::Ply, index, || PLY[index])
+}
+
+#[derive(Clone, Copy)]
+enum Table {
+    ToMove,
+    ColoredPieceSquare,
+    Captured,
+    NullMoveCounter,
+    Ply,
+}
+
+#[cfg(not(test))]
+#[inline]
+fn key(_table: Table, _index: usize, generated: impl FnOnce() -> u64) -> u64 {
+    generated()
+}
+
+#[cfg(test)]
+pub const TEST_SEED: u64 = 0x5eed_2a21_d809_0000;
+
+/// SplitMix64 of (`TEST_SEED`, table, index).
+#[cfg(test)]
+fn key(table: Table, index: usize, _generated: impl FnOnce() -> u64) -> u64 {
+    let mut z = TEST_SEED ^ (table as u64) << 32 ^ index as u64;
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
 
 #[rustfmt::skip]
-pub static TO_MOVE: EnumMap<Color, u64> = EnumMap::from_array([
+static TO_MOVE: EnumMap<Color, u64> = EnumMap::from_array([
     0x31ffe17fc33112df, 0x27d9bfbd42d338fb,
 ]);
 
 #[rustfmt::skip]
-pub static COLORED_PIECE_SQUARE: EnumMap<ColoredPiece, EnumMap<Square, u64>> = EnumMap::from_array([
+static COLORED_PIECE_SQUARE: EnumMap<ColoredPiece, EnumMap<Square, u64>> = EnumMap::from_array([
     EnumMap::from_array([
         0x00b69cf8708f193a, 0xa630106ff7b8befd, 0x578267ac080b038b, 0x286fc4b46dd68a85, 0xbc328574331f939d,
         0x790ce8c551525ca5, 0xc977db62eef6ecc7, 0x8dd05132362b0c9b, 0x4360c193ac5431ae, 0x90a2235c651b65ec,
@@ -191,7 +252,7 @@ static CAPTURED: EnumMap<Color, [u64; NUM_CAPTURED_INDEXES]> = EnumMap::from_arr
 ]);
 
 #[rustfmt::skip]
-pub static NULL_MOVE_COUNTER: [u64; (PLY_DRAW - PLY_AFTER_SETUP + 1) as usize] = [
+static NULL_MOVE_COUNTER: [u64; (PLY_DRAW - PLY_AFTER_SETUP + 1) as usize] = [
     0x2fd389b6d9a3440a, 0xe48bbc815bee36b0, 0x48108fd276b62e88, 0xccd8f1d7a2fd1616, 0x701d5819d3e7d744,
     0x90cb9db79bb912dc, 0x65e6f80493cb106a, 0x98d79b6b84c67028, 0xb78955ef394cf4d3, 0xeaaa31b211c27fc9,
     0x7c49bffdc5f92e68, 0xf6f8c4dc74d220bc, 0xed0026fd7e3bfad0, 0x2811f31d1f29ae48, 0xfd45a962c3521aa6,
@@ -216,7 +277,7 @@ pub static NULL_MOVE_COUNTER: [u64; (PLY_DRAW - PLY_AFTER_SETUP + 1) as usize] =
 ];
 
 #[rustfmt::skip]
-pub static PLY: [u64; (PLY_DRAW + 1) as usize] = [
+static PLY: [u64; (PLY_DRAW + 1) as usize] = [
     0x6a48f4409736d9da, 0x0ee397ae71057dbc, 0xc29e31a3f101cdf1, 0x1bb97f4fa753597b, 0xec84ccefa4cbbfeb,
     0x479e7ef1fae412d1, 0x3d8e6b3acb9f36b1, 0x57f62be6bb6655cd, 0x0106d24a8b39f897, 0xb012e60a779c2277,
     0xba15deb4e435bab2, 0x6b2abfeebd1be8da, 0x86976369b1e0be30, 0x873a920e2a417fbe, 0x87080b2d785228e4,
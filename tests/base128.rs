@@ -1,5 +1,5 @@
 use rand::{rngs::StdRng, Rng, SeedableRng};
-use wazir_drop::base128::{Base128Decoder, Base128Encoder};
+use wazir_drop::base128::{self, Base128Decoder, Base128Encoder};
 
 #[test]
 fn test_base128() {
@@ -51,3 +51,31 @@ fn test_varint() {
     }
     decoder.finish();
 }
+
+#[test]
+fn test_decode_iterators() {
+    let numbers = [0, -1, 5, i32::MIN, 100, i32::MAX, 0, 0];
+    for len in 0..=numbers.len() {
+        let mut encoder = Base128Encoder::new();
+        for &n in &numbers[..len] {
+            encoder.encode_varint(n);
+        }
+        let s = encoder.finish();
+        let decoded: Vec<i32> = base128::decode_varints(&s).collect();
+        assert_eq!(decoded, &numbers[..len]);
+    }
+
+    let mut rng = StdRng::seed_from_u64(403);
+    for n in 1..=32 {
+        for len in 0..20 {
+            let values: Vec<u32> = (0..len).map(|_| rng.random::<u32>() >> (32 - n)).collect();
+            let mut encoder = Base128Encoder::new();
+            for &value in &values {
+                encoder.encode_bits(n, value);
+            }
+            let s = encoder.finish();
+            let decoded: Vec<u32> = base128::decode_bits(&s, n).collect();
+            assert_eq!(decoded, values);
+        }
+    }
+}
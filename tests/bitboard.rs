@@ -1,4 +1,5 @@
-use wazir_drop::{Bitboard, Square};
+use std::str::FromStr;
+use wazir_drop::{enums::SimpleEnumExt, Bitboard, Square};
 
 #[test]
 fn test_empty() {
@@ -162,3 +163,26 @@ fn test_into_iter() {
     assert_eq!(iter.next(), Some(Square::B4));
     assert_eq!(iter.next(), None);
 }
+
+#[test]
+fn test_from_squares() {
+    assert_eq!(Bitboard::from_squares([]), Bitboard::EMPTY);
+    assert_eq!(
+        Bitboard::from_squares([Square::B3, Square::H8, Square::B3]),
+        Bitboard::single(Square::B3) | Bitboard::single(Square::H8)
+    );
+    assert_eq!(Bitboard::from_squares(Square::all()), Bitboard::ALL);
+}
+
+#[test]
+fn test_from_str() {
+    let bitboard = Bitboard::from_squares([Square::A1, Square::B3, Square::H8]);
+    assert_eq!(Bitboard::from_str(&bitboard.to_string()).unwrap(), bitboard);
+    assert_eq!(
+        Bitboard::from_str(&Bitboard::ALL.to_string()).unwrap(),
+        Bitboard::ALL
+    );
+    assert!(Bitboard::from_str("x.......\n").is_err());
+    assert!(Bitboard::from_str(&"x......\n".repeat(8)).is_err());
+    assert!(Bitboard::from_str(&"x...o...\n".repeat(8)).is_err());
+}
@@ -1,7 +1,8 @@
+use std::str::FromStr;
 use wazir_drop::{
     base128::{Base128Decoder, Base128Encoder},
-    book::{decode_setup_move, encode_setup_move},
-    movegen, Color,
+    book::{self, decode_setup_move, encode_setup_move, OpeningBook},
+    movegen, Color, SetupMove, Symmetry,
 };
 
 #[test]
@@ -16,3 +17,74 @@ fn test_encode_setup_move() {
     decoder.finish();
     assert_eq!(mov, setup_move);
 }
+
+#[test]
+fn test_book_setups() {
+    let red = book::red_setup();
+    assert_eq!(red.color, Color::Red);
+    red.validate_pieces().unwrap();
+    let blue_moves = book::blue_setup_moves();
+    assert!(!blue_moves.is_empty());
+    for mov in blue_moves {
+        assert_eq!(mov.color, Color::Blue);
+        mov.validate_pieces().unwrap();
+    }
+    if let Some(blue) = book::blue_setup(red) {
+        assert_eq!(blue.color, Color::Blue);
+        blue.validate_pieces().unwrap();
+    }
+}
+
+#[cfg(feature = "book")]
+#[test]
+fn test_openings() {
+    let openings = book::openings();
+    assert!(!openings.is_empty());
+    let (red, blue) = openings[0];
+    assert_eq!(red.color, Color::Red);
+    assert_eq!(blue.color, Color::Blue);
+    assert_eq!(book::blue_setup(red), Some(blue));
+    assert_eq!(
+        book::blue_setup(Symmetry::FlipX.apply_to_setup(red)),
+        Some(Symmetry::FlipX.apply_to_setup(blue))
+    );
+}
+
+#[test]
+fn test_opening_book() {
+    let book = OpeningBook::from_str(
+        "\
+NWAAAAAADFFDDDAA ddddffaaaaaaaawn
+AAAAAAAADDDDFFWN wnffddddaaaaaaaa
+",
+    )
+    .unwrap();
+    assert_eq!(book.len(), 2);
+
+    let red = SetupMove::from_str("NWAAAAAADFFDDDAA").unwrap();
+    let blue = SetupMove::from_str("ddddffaaaaaaaawn").unwrap();
+    assert_eq!(book.blue_setup(red), Some(blue));
+    assert_eq!(
+        book.blue_setup(Symmetry::FlipX.apply_to_setup(red)),
+        Some(Symmetry::FlipX.apply_to_setup(blue))
+    );
+    // Stored normalized, so the flipped red setup from the file is found as written.
+    let red2 = SetupMove::from_str("AAAAAAAADDDDFFWN").unwrap();
+    let blue2 = SetupMove::from_str("wnffddddaaaaaaaa").unwrap();
+    assert_eq!(book.blue_setup(red2), Some(blue2));
+    assert_eq!(
+        book.blue_setup(SetupMove::from_str("AWAAAAAADFFDDDAN").unwrap()),
+        None
+    );
+
+    let red_setup = book.red_setup().unwrap();
+    assert!(book.blue_setup(red_setup).is_some());
+    assert_eq!(book.blue_setup_moves().len(), 4);
+
+    assert!(OpeningBook::from_str("").unwrap().is_empty());
+    assert!(OpeningBook::from_str("").unwrap().red_setup().is_none());
+    // Wrong colors.
+    assert!(OpeningBook::from_str("ddddffaaaaaaaawn NWAAAAAADFFDDDAA\n").is_err());
+    // Wrong pieces.
+    assert!(OpeningBook::from_str("NNAAAAAADFFDDDAA ddddffaaaaaaaawn\n").is_err());
+}
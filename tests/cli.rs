@@ -1,13 +1,27 @@
-use std::str::FromStr;
-use wazir_drop::CliCommand;
+mod common;
+
+use common::position_after;
+use std::{
+    env, fs,
+    io::Write,
+    process::{Command, Stdio},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use wazir_drop::{movegen, CliCommand, DefaultEvaluator, Evaluator, ShortMove};
 
 #[test]
 fn test_cli_command_display_from_str() {
     let test_cases = [
         "Time 1000",
+        "MoveCap 250",
         "Opening WNAADADAFFAADDAA wnaadadaffaaddaa",
         "Start",
         "a1a2",
+        "LastInfo",
+        "Version",
+        "Ponder on",
+        "Ponder off",
         "Quit",
     ];
     for case in test_cases {
@@ -15,4 +29,237 @@ fn test_cli_command_display_from_str() {
         let command = CliCommand::from_str(case).unwrap();
         assert_eq!(command.to_string(), case);
     }
+
+    let position = position_after(&["WNAADADAFFAADDAA", "wnaadadaffaaddaa", "Aa3-c5"]);
+    let command = CliCommand::Position(position).to_string();
+    assert_eq!(command, format!("Position {}", position.to_fen()));
+    let CliCommand::Position(parsed) = CliCommand::from_str(&command).unwrap() else {
+        panic!("Expected Position command");
+    };
+    assert_eq!(parsed.to_string(), position.to_string());
+}
+
+#[test]
+fn test_position_command() {
+    let position = position_after(&["WNAADADAFFAADDAA", "wnaadadaffaaddaa", "Aa3-c5"]);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(
+            format!(
+                "Time 1000\n{}\nStart\nQuit\n",
+                CliCommand::Position(position)
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let short_move = ShortMove::from_str(stdout.trim_end()).unwrap();
+    assert!(movegen::any_move_from_short_move(&position, short_move).is_ok());
+}
+
+#[test]
+fn test_last_line_without_newline() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"Time 1000\nLastInfo")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"Info\n");
+}
+
+#[test]
+fn test_version() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"Version\nQuit\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let words: Vec<&str> = stdout.split_whitespace().collect();
+    assert_eq!(words.len(), 3);
+    assert_eq!(words[0], "Version");
+    assert_eq!(words[1], DefaultEvaluator::default().name());
+    assert_eq!(words[2].len(), 16);
+}
+
+#[test]
+fn test_record_and_replay() {
+    let dir = env::temp_dir().join(format!("wazir-drop-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let recording = dir.join("session.txt");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+        .arg("--record")
+        .arg(&recording)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"Time 1000\nOpening\nStart\nLastInfo\nQuit\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let sent: Vec<&str> = stdout.lines().collect();
+    assert_eq!(sent.len(), 2);
+
+    let recorded = fs::read_to_string(&recording).unwrap();
+    let lines: Vec<&str> = recorded.lines().collect();
+    assert_eq!(
+        lines[..4],
+        [
+            "< Time 1000",
+            "< Opening",
+            "< Start",
+            &format!("> {}", sent[0])
+        ]
+    );
+    assert_eq!(lines[4], "< LastInfo");
+    assert_eq!(lines[5], format!("> {}", sent[1]));
+    assert_eq!(lines[6..], ["< Quit"]);
+
+    // Replaying ignores stdin.
+    let output = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+        .arg("--replay")
+        .arg(&recording)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let replayed = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(replayed.lines().count(), 2);
+    assert!(replayed.lines().nth(1).unwrap().starts_with("Info"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+        .arg("--replay")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_ponder() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(
+            b"Time 1000\nOpening WNAADADAFFAADDAA wnaadadaffaaddaa\nPonder on\nStart\nPonder off\n\
+            Ponder on\nQuit\n",
+        )
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 1);
+}
+
+#[test]
+fn test_threads() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+        .args(["--threads", "2"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"Time 1000\nOpening WNAADADAFFAADDAA wnaadadaffaaddaa\nStart\nQuit\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 1);
+
+    for args in [&["--threads", "0"][..], &["--threads"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+    }
+}
+
+#[test]
+fn test_move_cap() {
+    // The clock alone would allow many seconds for the move.
+    let start = Instant::now();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(
+            b"Time 600000\nMoveCap 200\nOpening WNAADADAFFAADDAA wnaadadaffaaddaa\nStart\nQuit\n",
+        )
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 1);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wazir-drop"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"Time 1000\nOpening WNAADADAFFAADDAA wnaadadaffaaddaa\nStart\nMoveCap 200\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(!output.status.success());
 }
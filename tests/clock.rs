@@ -1,5 +1,5 @@
 use std::time::Duration;
-use wazir_drop::clock::{Stopwatch, Timer};
+use wazir_drop::clock::{Stopwatch, TimeControl, Timer};
 
 #[test]
 fn test_stopwatch() {
@@ -36,3 +36,18 @@ fn test_timer() {
     let t = timer.get();
     assert_eq!(t, Duration::ZERO);
 }
+
+#[test]
+fn test_timer_add() {
+    let mut timer = Timer::new(Duration::from_millis(100));
+    timer.add(Duration::from_millis(50));
+    assert_eq!(timer.get(), Duration::from_millis(150));
+}
+
+#[test]
+fn test_time_control_fixed() {
+    let time_control = TimeControl::fixed(Duration::from_secs(5));
+    assert_eq!(time_control.base, Duration::from_secs(5));
+    assert_eq!(time_control.increment, Duration::ZERO);
+    assert_eq!(time_control.move_cap, None);
+}
@@ -0,0 +1,56 @@
+//! Fixtures shared by the integration tests.
+
+// Each test crate uses only some of these.
+#![allow(dead_code)]
+
+pub mod round_trip;
+
+use std::str::FromStr;
+use wazir_drop::{constants::Hyperparameters, AnyMove, History, Position};
+
+/// Setup moves used by most tests.
+pub const SETUP_MOVES: [&str; 2] = ["WNAADADAFFAADDAA", "wnaadadaffaaddaa"];
+
+/// Parses `moves`, panicking on bad notation.
+pub fn parse_moves(moves: &[&str]) -> Vec<AnyMove> {
+    moves
+        .iter()
+        .map(|mov| AnyMove::from_str(mov).unwrap())
+        .collect()
+}
+
+/// The position after playing `moves` from the initial position.
+pub fn position_after(moves: &[&str]) -> Position {
+    Position::replay(&parse_moves(moves)).unwrap()
+}
+
+/// The position after `SETUP_MOVES`, with its history.
+pub fn start_position() -> (Position, History) {
+    let mut position = Position::initial();
+    let mut history = History::new_from_position(&position);
+    for mov in SETUP_MOVES {
+        position = position
+            .make_any_move(AnyMove::from_str(mov).unwrap())
+            .unwrap();
+        history.push_position_irreversible(&position);
+    }
+    (position, history)
+}
+
+/// A history for a position parsed from text, with no earlier positions to repeat.
+pub fn history_for(position: &Position) -> History {
+    let mut history = History::new_from_position(position);
+    for _ in 0..position.ply() {
+        history.push_position_irreversible(position);
+    }
+    history
+}
+
+/// Default hyperparameters with small hash tables, so tests don't allocate the default sizes.
+pub fn small_tables() -> Hyperparameters {
+    Hyperparameters {
+        ttable_size: 1 << 20,
+        pvtable_size: 1 << 16,
+        ..Hyperparameters::default()
+    }
+}
@@ -0,0 +1,34 @@
+//! Round trips through the `FromStr` parsers, shared with the cargo-fuzz target in `fuzz/`.
+
+use std::{fmt::Display, str::FromStr};
+use wazir_drop::{
+    AnyMove, Bitboard, CliCommand, Color, EndReason, Move, Outcome, Position, SetupMove, ShortMove,
+    Stage,
+};
+
+/// Parsing never panics, and anything parsed displays as a string that parses back to the same
+/// display.
+pub fn check_round_trip<T: FromStr + Display>(input: &str) {
+    if let Ok(value) = T::from_str(input) {
+        let displayed = value.to_string();
+        let Ok(reparsed) = T::from_str(&displayed) else {
+            panic!("{displayed:?} doesn't parse, parsed from {input:?}");
+        };
+        assert_eq!(reparsed.to_string(), displayed, "parsed from {input:?}");
+    }
+}
+
+/// `check_round_trip` for every type with a parser.
+pub fn check_all(input: &str) {
+    check_round_trip::<Position>(input);
+    check_round_trip::<Move>(input);
+    check_round_trip::<SetupMove>(input);
+    check_round_trip::<AnyMove>(input);
+    check_round_trip::<ShortMove>(input);
+    check_round_trip::<CliCommand>(input);
+    check_round_trip::<Outcome>(input);
+    check_round_trip::<EndReason>(input);
+    check_round_trip::<Stage>(input);
+    check_round_trip::<Color>(input);
+    check_round_trip::<Bitboard>(input);
+}
@@ -0,0 +1,133 @@
+use wazir_drop::{
+    constants::{
+        Hyperparameters, MAX_ASPIRATION_WINDOW, MAX_SEARCH_DEPTH, MIN_TABLE_SIZE, ONE_PLY,
+        PLY_DRAW, PLY_DRAWISH,
+    },
+    EvalCache,
+};
+
+#[test]
+fn test_hyperparameters_validate() {
+    assert_eq!(Hyperparameters::default().validate(), Ok(()));
+
+    let hyperparameters = Hyperparameters {
+        soft_time_fraction: 1.5,
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+
+    let hyperparameters = Hyperparameters {
+        futility_margin: -0.1,
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+
+    let hyperparameters = Hyperparameters {
+        late_move_reduction_start: 20,
+        late_move_reduction_start_2: 10,
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+
+    let hyperparameters = Hyperparameters {
+        time_reduction_per_move: f64::NAN,
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+
+    let hyperparameters = Hyperparameters {
+        null_verify_depth: Some(ONE_PLY),
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+    let hyperparameters = Hyperparameters {
+        draw_blend_plies: Some(0),
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+    let hyperparameters = Hyperparameters {
+        draw_blend_plies: Some(PLY_DRAW - PLY_DRAWISH + 1),
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+    let hyperparameters = Hyperparameters {
+        draw_blend_plies: Some(PLY_DRAW - PLY_DRAWISH),
+        ..Hyperparameters::default()
+    };
+    assert_eq!(hyperparameters.validate(), Ok(()));
+    let hyperparameters = Hyperparameters {
+        random_move_margin: Some(-0.1),
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+    let hyperparameters = Hyperparameters {
+        eval_cache_size: EvalCache::MIN_SIZE - 1,
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+    let hyperparameters = Hyperparameters {
+        eval_cache_size: EvalCache::MIN_SIZE,
+        ..Hyperparameters::default()
+    };
+    assert_eq!(hyperparameters.validate(), Ok(()));
+    let hyperparameters = Hyperparameters {
+        search_threads: 0,
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+    let hyperparameters = Hyperparameters {
+        aspiration_window: Some(0.0),
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+    let hyperparameters = Hyperparameters {
+        aspiration_window: Some(MAX_ASPIRATION_WINDOW),
+        ..Hyperparameters::default()
+    };
+    assert_eq!(hyperparameters.validate(), Ok(()));
+    let hyperparameters = Hyperparameters {
+        aspiration_window: Some(2.0 * MAX_ASPIRATION_WINDOW),
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+    let hyperparameters = Hyperparameters {
+        aspiration_min_depth: ONE_PLY,
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+    let hyperparameters = Hyperparameters {
+        aspiration_min_depth: MAX_SEARCH_DEPTH + ONE_PLY,
+        ..Hyperparameters::default()
+    };
+    assert!(hyperparameters.validate().is_err());
+}
+
+#[test]
+fn test_hyperparameters_rescale() {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+    let hyperparameters = Hyperparameters::default();
+    let rescaled = hyperparameters.rescale(1000.0, 4000.0);
+    assert!(close(rescaled.contempt * 4.0, hyperparameters.contempt));
+    assert!(close(
+        rescaled.null_move_margin * 4.0,
+        hyperparameters.null_move_margin
+    ));
+    assert!(close(
+        rescaled.futility_margin * 4.0,
+        hyperparameters.futility_margin
+    ));
+    assert!(close(
+        rescaled.panic_eval_threshold * 4.0,
+        hyperparameters.panic_eval_threshold
+    ));
+    assert_eq!(rescaled.panic_multiplier, hyperparameters.panic_multiplier);
+    assert_eq!(rescaled.validate(), Ok(()));
+}
+
+#[test]
+fn test_hyperparameters_from_memory_budget() {
+    let hyperparameters = Hyperparameters::from_memory_budget(0);
+    assert_eq!(hyperparameters.ttable_size, MIN_TABLE_SIZE);
+    assert_eq!(hyperparameters.pvtable_size, MIN_TABLE_SIZE);
+    assert_eq!(hyperparameters.validate(), Ok(()));
+}
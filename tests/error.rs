@@ -0,0 +1,28 @@
+use std::str::FromStr;
+use wazir_drop::{error::Error, Move, Position};
+
+fn play(position: &str, mov: &str) -> Result<Position, Error> {
+    let position = Position::from_str(position)?;
+    let mov = Move::from_str(mov)?;
+    Ok(position.make_move(mov)?)
+}
+
+#[test]
+fn test_question_mark_conversions() {
+    assert_eq!(play("garbage", "a1b1").unwrap_err(), Error::Parse);
+    assert_eq!(
+        play(&Position::initial().to_string(), "x").unwrap_err(),
+        Error::Parse
+    );
+    // Regular moves are not allowed during setup.
+    assert_eq!(
+        play(&Position::initial().to_string(), "A@a1").unwrap_err(),
+        Error::InvalidMove
+    );
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(Error::InvalidMove.to_string(), "invalid move");
+    assert_eq!(Error::Parse.to_string(), "parse error");
+}
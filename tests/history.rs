@@ -1,4 +1,5 @@
-use wazir_drop::History;
+use std::str::FromStr;
+use wazir_drop::{AnyMove, History, Position};
 
 #[test]
 fn test_history() {
@@ -15,3 +16,48 @@ fn test_history() {
     history.pop();
     assert_eq!(history.find_repetition(), Some(1));
 }
+
+#[test]
+fn test_history_new_from_start() {
+    let moves: Vec<AnyMove> = ["WNAADADAFFAADDAA", "wnaadadaffaaddaa", "Aa3-c5"]
+        .iter()
+        .map(|mov| AnyMove::from_str(mov).unwrap())
+        .collect();
+    let position = Position::replay(&moves).unwrap();
+    let history = History::new_from_start(&position);
+    assert_eq!(history.ply(), position.ply());
+    assert_eq!(history.find_repetition(), None);
+    assert!(history.last_move_irreversible());
+}
+
+#[test]
+fn test_history_truncate() {
+    let mut history = History::new(1);
+    history.push(2);
+    history.push_irreversible(3);
+    history.push(4);
+    history.push(3);
+    assert_eq!(history.find_repetition(), Some(2));
+
+    history.truncate(3);
+    assert_eq!(history.ply(), 3);
+    assert_eq!(history.find_repetition(), None);
+    history.truncate(2);
+    assert!(history.last_move_irreversible());
+    history.truncate(1);
+    assert!(!history.last_move_irreversible());
+    // The irreversible move is gone, so an earlier position repeats.
+    history.push(3);
+    history.push(2);
+    assert_eq!(history.find_repetition(), Some(1));
+
+    history.clear();
+    assert_eq!(history.ply(), 0);
+    assert!(history.last_move_irreversible());
+    assert_eq!(history.find_repetition(), None);
+    history.push(2);
+    assert_eq!(history.find_repetition(), None);
+    history.push(4);
+    history.push(2);
+    assert_eq!(history.find_repetition(), Some(1));
+}
@@ -0,0 +1,28 @@
+use std::str::FromStr;
+use wazir_drop::log::{Level, Verbosity};
+
+#[test]
+fn test_verbosity_from_str() {
+    assert_eq!(Verbosity::from_str("quiet").unwrap(), Verbosity::Quiet);
+    assert_eq!(Verbosity::from_str("normal").unwrap(), Verbosity::Normal);
+    assert_eq!(Verbosity::from_str("verbose").unwrap(), Verbosity::Verbose);
+    assert!(Verbosity::from_str("loud").is_err());
+}
+
+#[test]
+fn test_verbosity_from_args() {
+    assert_eq!(Verbosity::from_args(["--quiet", "other"]), Verbosity::Quiet);
+    assert_eq!(Verbosity::from_args(["--verbose"]), Verbosity::Verbose);
+    // `--verbose` wins.
+    assert_eq!(
+        Verbosity::from_args(["--quiet", "--verbose"]),
+        Verbosity::Verbose
+    );
+}
+
+#[test]
+fn test_verbosity_level() {
+    assert_eq!(Verbosity::Quiet.level(), Level::Always);
+    assert_eq!(Verbosity::Normal.level(), Level::Info);
+    assert_eq!(Verbosity::Verbose.level(), Level::Verbose);
+}
@@ -1,22 +1,30 @@
-use std::str::FromStr;
+use rand::{rngs::StdRng, seq::IndexedRandom, Rng, SeedableRng};
+use std::{collections::HashSet, str::FromStr};
 
 use wazir_drop::{
+    enums::SimpleEnumExt,
     movegen::{
-        any_move_from_short_move, attacked_by, captures, captures_checks, captures_non_checks,
-        captures_of_wazir, check_evasions_capture_attacker, double_move_bitboard, drops,
-        drops_attack_escape, drops_boring, drops_check_threats, drops_checks, in_check, jumps,
-        jumps_attack_escape, jumps_boring, jumps_check_threats, jumps_checks, move_bitboard,
-        pseudocaptures, pseudojumps, setup_moves, triple_move_bitboard, validate_from_to,
-        wazir_plus_double_move_bitboard, wazir_plus_move_bitboard,
+        any_legal_moves, any_move_from_short_move, any_pseudomoves, attack_counts, attack_map,
+        attacked_by, capture_race, captures, captures_checks, captures_non_checks,
+        captures_of_wazir, check_evasions, check_evasions_capture_attacker, double_move_bitboard,
+        drop_bitboard, drops, drops_attack_escape, drops_boring, drops_check_threats, drops_checks,
+        in_check, is_legal, is_legal_drop_square, is_pseudo_legal, jumps, jumps_attack_escape,
+        jumps_boring, jumps_check_threats, jumps_checks, legal_moves, move_bitboard, moves, perft,
+        perft_divide, pseudocaptures, pseudojumps, pseudomoves, see, setup_move_rank,
+        setup_move_unrank, setup_moves, setup_moves_from, triple_move_bitboard, validate_from_to,
+        wazir_plus_double_move_bitboard, wazir_plus_move_bitboard, winning_capture,
+        CAPTURE_RACE_WIN, NUM_SETUP_MOVES,
     },
-    Color, Move, Piece, Position, ShortMove, Square,
+    AnyMove, Bitboard, Color, ColoredPiece, Move, Outcome, Piece, Position, SetupMove, ShortMove,
+    Square, Stage,
 };
 
 #[test]
 fn test_move_bitboard() {
     assert_eq!(
-        move_bitboard(Piece::Alfil, Square::D4).to_string(),
-        "\
+        move_bitboard(Piece::Alfil, Square::D4),
+        Bitboard::from_str(
+            "\
 ........
 .x...x..
 ........
@@ -26,11 +34,14 @@ fn test_move_bitboard() {
 ........
 ........
 "
+        )
+        .unwrap()
     );
 
     assert_eq!(
-        move_bitboard(Piece::Dabbaba, Square::D4).to_string(),
-        "\
+        move_bitboard(Piece::Dabbaba, Square::D4),
+        Bitboard::from_str(
+            "\
 ........
 ...x....
 ........
@@ -40,11 +51,14 @@ fn test_move_bitboard() {
 ........
 ........
 "
+        )
+        .unwrap()
     );
 
     assert_eq!(
-        move_bitboard(Piece::Ferz, Square::D4).to_string(),
-        "\
+        move_bitboard(Piece::Ferz, Square::D4),
+        Bitboard::from_str(
+            "\
 ........
 ........
 ..x.x...
@@ -54,11 +68,14 @@ fn test_move_bitboard() {
 ........
 ........
 "
+        )
+        .unwrap()
     );
 
     assert_eq!(
-        move_bitboard(Piece::Knight, Square::D4).to_string(),
-        "\
+        move_bitboard(Piece::Knight, Square::D4),
+        Bitboard::from_str(
+            "\
 ........
 ..x.x...
 .x...x..
@@ -68,11 +85,14 @@ fn test_move_bitboard() {
 ........
 ........
 "
+        )
+        .unwrap()
     );
 
     assert_eq!(
-        move_bitboard(Piece::Wazir, Square::D4).to_string(),
-        "\
+        move_bitboard(Piece::Wazir, Square::D4),
+        Bitboard::from_str(
+            "\
 ........
 ........
 ...x....
@@ -82,12 +102,15 @@ fn test_move_bitboard() {
 ........
 ........
 "
+        )
+        .unwrap()
     );
 
     // From a corner.
     assert_eq!(
-        move_bitboard(Piece::Knight, Square::H8).to_string(),
-        "\
+        move_bitboard(Piece::Knight, Square::H8),
+        Bitboard::from_str(
+            "\
 ........
 ........
 ........
@@ -97,6 +120,8 @@ fn test_move_bitboard() {
 .....x..
 ........
 "
+        )
+        .unwrap()
     );
 }
 
@@ -104,8 +129,9 @@ fn test_move_bitboard() {
 fn test_double_move_bitboard() {
     // From a corner.
     assert_eq!(
-        double_move_bitboard(Piece::Knight, Square::E4).to_string(),
-        "\
+        double_move_bitboard(Piece::Knight, Square::E4),
+        Bitboard::from_str(
+            "\
 .x.x.x..
 x.x.x.x.
 ...x...x
@@ -115,6 +141,8 @@ x.x.x.x.
 ...x...x
 x.x.x.x.
 "
+        )
+        .unwrap()
     );
 }
 
@@ -122,8 +150,9 @@ x.x.x.x.
 fn test_triple_move_bitboard() {
     // From a corner.
     assert_eq!(
-        triple_move_bitboard(Piece::Ferz, Square::E4).to_string(),
-        "\
+        triple_move_bitboard(Piece::Ferz, Square::E4),
+        Bitboard::from_str(
+            "\
 ........
 x.x.x.x.
 ........
@@ -133,14 +162,17 @@ x.x.x.x.
 ........
 x.x.x.x.
 "
+        )
+        .unwrap()
     );
 }
 
 #[test]
 fn test_wazir_plus_move_bitboard() {
     assert_eq!(
-        wazir_plus_move_bitboard(Piece::Alfil, Square::A5).to_string(),
-        "\
+        wazir_plus_move_bitboard(Piece::Alfil, Square::A5),
+        Bitboard::from_str(
+            "\
 ........
 ........
 .x.x.x.x
@@ -150,14 +182,17 @@ fn test_wazir_plus_move_bitboard() {
 ........
 ........
 "
+        )
+        .unwrap()
     );
 }
 
 #[test]
 fn test_wazir_plus_double_move_bitboard() {
     assert_eq!(
-        wazir_plus_double_move_bitboard(Piece::Alfil, Square::A5).to_string(),
-        "\
+        wazir_plus_double_move_bitboard(Piece::Alfil, Square::A5),
+        Bitboard::from_str(
+            "\
 .x.x.x.x
 x...x...
 ........
@@ -167,6 +202,8 @@ x...x...
 ........
 ........
 "
+        )
+        .unwrap()
     );
 }
 
@@ -252,6 +289,38 @@ fn test_setup_moves() {
     }
     // 16! / (8! 4! 2! 1! 1!) = 10810800
     assert_eq!(count, 10810800);
+    assert_eq!(count, NUM_SETUP_MOVES);
+}
+
+#[test]
+fn test_setup_move_rank() {
+    for (rank, mov) in (0..).zip(setup_moves(Color::Blue).take(1000)) {
+        assert_eq!(setup_move_rank(mov), rank);
+        assert_eq!(setup_move_unrank(Color::Blue, rank), Some(mov));
+    }
+    for rank in (0..NUM_SETUP_MOVES).step_by(9973) {
+        let mov = setup_move_unrank(Color::Red, rank).unwrap();
+        mov.validate_pieces().unwrap();
+        assert_eq!(setup_move_rank(mov), rank);
+    }
+    let last = setup_move_unrank(Color::Red, NUM_SETUP_MOVES - 1).unwrap();
+    assert_eq!(last.to_string(), "WNFFDDDDAAAAAAAA");
+    assert_eq!(setup_move_rank(last), NUM_SETUP_MOVES - 1);
+    assert!(setup_move_unrank(Color::Red, NUM_SETUP_MOVES).is_none());
+}
+
+#[test]
+fn test_setup_moves_from() {
+    let all: Vec<SetupMove> = setup_moves(Color::Red).take(1010).collect();
+    for skip in [0, 1, 17, 1000] {
+        let from: Vec<SetupMove> = setup_moves_from(Color::Red, skip).take(10).collect();
+        assert_eq!(from[..], all[skip as usize..skip as usize + 10]);
+    }
+    let from: Vec<SetupMove> = setup_moves_from(Color::Red, 5_000_000).take(3).collect();
+    assert_eq!(setup_move_rank(from[0]), 5_000_000);
+    assert_eq!(setup_move_rank(from[2]), 5_000_002);
+    assert_eq!(setup_moves_from(Color::Red, NUM_SETUP_MOVES - 1).count(), 1);
+    assert_eq!(setup_moves_from(Color::Red, NUM_SETUP_MOVES + 5).count(), 0);
 }
 
 #[test]
@@ -317,6 +386,130 @@ add....a
     assert_eq!(&moves, &["Ab4xwd6", "Ab8xwd6", "Db6xwd6", "Nf7xwd6"]);
 }
 
+#[test]
+fn test_capture_race() {
+    let board = "\
+Af
+FW.A.D.D
+AfFA.DDA
+..A.A.A.
+.....wA.
+...a..ad
+..d..nN.
+a.a...a.
+add....a
+";
+    let red_to_move = Position::from_str(&format!("regular\n4\n{board}")).unwrap();
+    let blue_to_move = Position::from_str(&format!("regular\n5\n{board}")).unwrap();
+    let race = |position: &Position, square: &str| {
+        capture_race(position, Square::from_str(square).unwrap())
+    };
+
+    // Wazir capture.
+    assert_eq!(race(&red_to_move, "d6"), CAPTURE_RACE_WIN);
+    // Undefended ferz.
+    assert_eq!(race(&red_to_move, "b2"), 2);
+    // Own piece, empty square.
+    assert_eq!(race(&red_to_move, "a1"), 0);
+    assert_eq!(race(&red_to_move, "a3"), 0);
+    // Ferz for ferz.
+    assert_eq!(race(&blue_to_move, "a1"), 0);
+    // Undefended alfil.
+    assert_eq!(race(&blue_to_move, "c3"), 1);
+    // Not attacked.
+    assert_eq!(race(&blue_to_move, "a2"), 0);
+    assert_eq!(race(&Position::initial(), "a1"), 0);
+}
+
+#[test]
+fn test_see() {
+    let board = "\
+Af
+FW.A.D.D
+AfFA.DDA
+..A.A.A.
+.....wA.
+...a..ad
+..d..nN.
+a.a...a.
+add....a
+";
+    let red_to_move = Position::from_str(&format!("regular\n4\n{board}")).unwrap();
+    let blue_to_move = Position::from_str(&format!("regular\n5\n{board}")).unwrap();
+    let see_str = |position: &Position, mov: &str| see(position, Move::from_str(mov).unwrap());
+
+    // Wazir capture.
+    assert_eq!(see_str(&red_to_move, "Nf7xwd6"), CAPTURE_RACE_WIN);
+    // Undefended ferz, by a ferz or by the wazir.
+    assert_eq!(see_str(&red_to_move, "Fa1xfb2"), 2);
+    assert_eq!(see_str(&red_to_move, "Wa2xfb2"), 2);
+    // Ferz for ferz.
+    assert_eq!(see_str(&blue_to_move, "fb2xFa1"), 0);
+    // Knight for alfil loses material, although capturing on the square with something else
+    // wouldn't.
+    assert_eq!(see_str(&blue_to_move, "nf6xAd7"), -1);
+    assert_eq!(capture_race(&blue_to_move, Square::D7), 0);
+    // Not a capture.
+    let jump = jumps(&red_to_move).next().unwrap();
+    assert_eq!(see(&red_to_move, jump), 0);
+
+    // Forcing the first capture can only make the outcome worse.
+    for position in [red_to_move, blue_to_move] {
+        for mov in captures(&position) {
+            let see = see(&position, mov);
+            assert!(see <= capture_race(&position, mov.to), "{mov}");
+        }
+    }
+}
+
+#[test]
+fn test_winning_capture() {
+    let position = Position::from_str(
+        "\
+regular
+4
+Af
+FW.A.D.D
+AfFA.DDA
+..A.A.A.
+.....wA.
+...a..ad
+..d..nN.
+a.a...a.
+add....a
+",
+    )
+    .unwrap();
+    assert_eq!(
+        winning_capture(&position).map(|mov| mov.to_string()),
+        Some("Ab4xwd6".to_string())
+    );
+    assert!(winning_capture(&Position::initial()).is_none());
+
+    let position = Position::from_str(
+        "\
+regular
+100
+AFf
+.W.A.D.D
+AaFA.DDA
+n.A.A.A.
+......A.
+...a.a.d
+..d...N.
+a.a...f.
+add.w..a
+",
+    )
+    .unwrap();
+    assert!(winning_capture(&position).is_none());
+    let position = position.make_null_move().unwrap();
+    assert_eq!(
+        winning_capture(&position).map(|mov| mov.to_string()),
+        Some("nc1xWa2".to_string())
+    );
+}
+
 #[test]
 fn test_check_evasion_capture_attacker() {
     let position = Position::from_str(
@@ -363,6 +556,56 @@ a.dw...a
     assert!(moves.is_empty());
 }
 
+#[test]
+fn test_wazir_captures_defended_wazir() {
+    // The blue alfil on c5 defends the blue wazir.
+    let position = Position::from_str(
+        "\
+regular
+4
+Aff
+FWwA.D.D
+AF.AD.DA
+..A.a.A.
+....A.A.
+...a..ad
+..d..nN.
+a.a...a.
+add.....
+",
+    )
+    .unwrap();
+    let wazir_capture = Move::from_str("Wa2xwa3").unwrap();
+    assert!(check_evasions(&position).any(|mov| mov == wazir_capture));
+    assert!(moves(&position).any(|mov| mov == wazir_capture));
+    assert!(any_legal_moves(&position).any(|mov| mov == AnyMove::Regular(wazir_capture)));
+    assert_eq!(perft(&position, 1), 2);
+    assert_eq!(
+        position.make_move(wazir_capture).unwrap().stage(),
+        Stage::End(Outcome::RedWin)
+    );
+
+    // Double check by the wazir and a ferz.
+    let position = Position::from_str(
+        "\
+regular
+4
+Af
+FWwA.D.D
+AFfAD.DA
+..A.a.A.
+....A.A.
+...a..ad
+..d..nN.
+a.a...a.
+add.....
+",
+    )
+    .unwrap();
+    let moves: Vec<Move> = check_evasions(&position).collect();
+    assert_eq!(moves, [wazir_capture]);
+}
+
 #[test]
 fn test_jumps() {
     let position = Position::from_str(
@@ -534,3 +777,401 @@ add.w..a
     assert!(in_check(&position, Color::Red));
     assert!(!in_check(&position, Color::Blue));
 }
+
+#[test]
+fn test_drop_squares() {
+    // Under the current rules, pieces may be dropped on any empty square.
+    for cpiece in ColoredPiece::all() {
+        assert_eq!(drop_bitboard(cpiece), Bitboard::ALL);
+        for square in Square::all() {
+            assert!(is_legal_drop_square(cpiece, square));
+        }
+    }
+}
+
+#[test]
+fn test_is_pseudo_legal() {
+    let position = Position::from_str(
+        "\
+regular
+4
+AFf
+.W.A.D.D
+AaFA.DDA
+..A.A.A.
+......A.
+...a.a.d
+..d..nN.
+a.a...f.
+add.w..a
+",
+    )
+    .unwrap();
+
+    for mov in pseudomoves(&position) {
+        assert!(is_pseudo_legal(&position, mov));
+    }
+
+    // Moves from a different position.
+    let position2 = position
+        .make_move(Move::from_str("Wa2xab2").unwrap())
+        .unwrap()
+        .make_null_move()
+        .unwrap();
+    for mov in pseudomoves(&position2) {
+        assert_eq!(
+            is_pseudo_legal(&position, mov),
+            position.make_move(mov).is_ok()
+        );
+    }
+
+    for mov in ["A@a1", "Wa2-a3", "Wa2xab2"] {
+        assert!(is_pseudo_legal(&position, Move::from_str(mov).unwrap()));
+    }
+    for mov in [
+        "A@a2", "N@a1", "f@a1", "Wa2-c2", "Wa2-b2", "Wa2xnb2", "Fb3-a4", "Wa3-a4",
+    ] {
+        assert!(!is_pseudo_legal(&position, Move::from_str(mov).unwrap()));
+    }
+}
+
+#[test]
+fn test_any_moves() {
+    let position = Position::initial();
+    let expected: Vec<AnyMove> = setup_moves(Color::Red)
+        .take(1000)
+        .map(AnyMove::from)
+        .collect();
+    let pseudo: Vec<AnyMove> = any_pseudomoves(&position).take(1000).collect();
+    let legal: Vec<AnyMove> = any_legal_moves(&position).take(1000).collect();
+    assert_eq!(pseudo, expected);
+    assert_eq!(legal, expected);
+    assert!(legal_moves(&position).take(1000).eq(expected));
+
+    // In check, so some pseudomoves are suicides.
+    let position = Position::from_str(
+        "\
+regular
+100
+AFf
+.W.A.D.D
+AaFA.DDA
+n.A.A.A.
+......A.
+...a.a.d
+..d...N.
+a.a...f.
+add.w..a
+",
+    )
+    .unwrap();
+    let pseudo: Vec<AnyMove> = any_pseudomoves(&position).collect();
+    let legal: Vec<AnyMove> = any_legal_moves(&position).collect();
+    assert_eq!(
+        pseudo,
+        pseudomoves(&position)
+            .map(AnyMove::from)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        legal,
+        moves(&position).map(AnyMove::from).collect::<Vec<_>>()
+    );
+    assert!(legal_moves(&position).eq(legal.iter().copied()));
+    assert!(legal.len() < pseudo.len());
+}
+
+fn ended_game() -> Position {
+    let position = Position::from_str(
+        "\
+regular
+100
+AFf
+.W.A.D.D
+AaFA.DDA
+n.A.A.A.
+......A.
+...a.a.d
+..d...N.
+a.a...f.
+add.w..a
+",
+    )
+    .unwrap();
+    let position = position.make_null_move().unwrap().make_null_move().unwrap();
+    assert!(position.end().is_some());
+    position
+}
+
+#[test]
+#[should_panic(expected = "End of game")]
+fn test_any_pseudomoves_at_end() {
+    _ = any_pseudomoves(&ended_game()).count();
+}
+
+#[test]
+#[should_panic(expected = "End of game")]
+fn test_any_legal_moves_at_end() {
+    _ = any_legal_moves(&ended_game()).count();
+}
+
+#[test]
+#[should_panic(expected = "End of game")]
+fn test_legal_moves_at_end() {
+    _ = legal_moves(&ended_game()).count();
+}
+
+#[test]
+fn test_attack_map() {
+    let position = Position::from_str(
+        "\
+regular
+4
+Af
+FW.A.D.D
+AfFA.DDA
+..A.A.A.
+.....wA.
+...a..ad
+..d..nN.
+a.a...a.
+add....a
+",
+    )
+    .unwrap();
+    for color in Color::all() {
+        let map = attack_map(&position, color);
+        let counts = attack_counts(&position, color);
+        for square in Square::all() {
+            let attackers = attacked_by(&position, square, color);
+            assert_eq!(map.contains(square), !attackers.is_empty());
+            assert_eq!(usize::from(counts[square]), attackers.count());
+        }
+    }
+}
+
+/// Moves that win or don't leave the mover's wazir attacked, found by trying every pseudomove.
+///
+/// A suicide on the last ply draws rather than loses, but `moves` leaves it out like any other.
+fn brute_force_moves(position: &Position) -> HashSet<Move> {
+    let me = position.to_move();
+    pseudomoves(position)
+        .filter(|&mov| {
+            let new_position = position.make_move(mov).unwrap();
+            new_position.stage() == Stage::End(Outcome::win(me)) || !in_check(&new_position, me)
+        })
+        .collect()
+}
+
+fn move_set(moves: impl Iterator<Item = Move>) -> HashSet<Move> {
+    let moves: Vec<Move> = moves.collect();
+    let set: HashSet<Move> = moves.iter().copied().collect();
+    assert_eq!(set.len(), moves.len(), "duplicate moves");
+    set
+}
+
+#[test]
+fn test_moves_complete() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut num_positions = 0;
+    for _ in 0..50 {
+        let mut position = Position::initial();
+        for color in [Color::Red, Color::Blue] {
+            let rank = rng.random_range(0..NUM_SETUP_MOVES);
+            let mov = setup_move_unrank(color, rank).unwrap();
+            position = position.make_setup_move(mov).unwrap();
+        }
+        while position.stage() == Stage::Regular {
+            num_positions += 1;
+            let legal = brute_force_moves(&position);
+            let moves: Vec<Move> = moves(&position).collect();
+            assert_eq!(move_set(moves.iter().copied()), legal, "{position}");
+            for &mov in &moves {
+                assert!(is_pseudo_legal(&position, mov));
+                assert!(position.make_move(mov).is_ok());
+            }
+            for mov in pseudomoves(&position) {
+                assert_eq!(is_legal(&position, mov), legal.contains(&mov), "{mov}");
+            }
+
+            if !in_check(&position, position.to_move()) {
+                let union: HashSet<Move> = captures_checks(&position)
+                    .chain(captures_non_checks(&position))
+                    .chain(jumps_checks(&position))
+                    .chain(jumps_check_threats(&position))
+                    .chain(jumps_attack_escape(&position))
+                    .chain(jumps_boring(&position))
+                    .chain(drops_checks(&position))
+                    .chain(drops_check_threats(&position))
+                    .chain(drops_attack_escape(&position))
+                    .chain(drops_boring(&position))
+                    .collect();
+                assert_eq!(union, legal, "{position}");
+            }
+
+            let Some(&mov) = moves.choose(&mut rng) else {
+                break;
+            };
+            position = position.make_move(mov).unwrap();
+        }
+    }
+    assert!(num_positions > 1000);
+}
+
+#[test]
+fn test_check_evasions_complete() {
+    let positions = [
+        // Single check by a ferz.
+        "\
+regular
+4
+Af
+FW.A.D.D
+AFfAD.DA
+..A...A.
+....A.A.
+...a..ad
+..d..nN.
+a.a...a.
+addw...a
+",
+        // Double check by a ferz and a dabbaba.
+        "\
+regular
+4
+Af
+FW.A.D.D
+AFfAD.DA
+.dA...A.
+....A.A.
+...a..ad
+..d..nN.
+a.a...a.
+a.dw...a
+",
+        // Check by a knight.
+        "\
+regular
+4
+Aff
+FW.A.D.D
+AF.AD.DA
+n.A...A.
+....A.A.
+...a..ad
+..d...N.
+a.a...a.
+addw...a
+",
+        // Check by the opponent's wazir.
+        "\
+regular
+4
+Aff
+FWwA.D.D
+AF.AD.DA
+..A...A.
+....A.A.
+...a..ad
+..d..nN.
+a.a...a.
+add....a
+",
+        // Check by the opponent's wazir, which is defended.
+        "\
+regular
+4
+Aff
+FWwA.D.D
+AF.AD.DA
+..A.a.A.
+....A.A.
+...a..ad
+..d..nN.
+a.a...a.
+add.....
+",
+    ];
+    for position in positions {
+        let position = Position::from_str(position).unwrap();
+        assert!(in_check(&position, position.to_move()), "{position}");
+        assert_eq!(
+            move_set(check_evasions(&position)),
+            brute_force_moves(&position),
+            "{position}"
+        );
+    }
+}
+
+fn brute_force_perft(position: &Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    match position.stage() {
+        Stage::Setup => unreachable!("perft starts after setup"),
+        Stage::Regular => brute_force_moves(position)
+            .into_iter()
+            .map(|mov| brute_force_perft(&position.make_move(mov).unwrap(), depth - 1))
+            .sum(),
+        Stage::End(_) => 0,
+    }
+}
+
+#[test]
+fn test_perft() {
+    assert_eq!(perft(&Position::initial(), 0), 1);
+    assert_eq!(perft(&Position::initial(), 1), u64::from(NUM_SETUP_MOVES));
+    assert_eq!(perft(&ended_game(), 0), 1);
+    assert_eq!(perft(&ended_game(), 2), 0);
+
+    let opening = Position::replay(&[
+        AnyMove::from_str("WNAADADAFFAADDAA").unwrap(),
+        AnyMove::from_str("wnaadadaffaaddaa").unwrap(),
+    ])
+    .unwrap();
+    // Red has 13 alfil, 4 dabbaba, 3 ferz and 2 knight jumps. The sides are too far apart to
+    // interact, so blue has the same 21 replies to each.
+    assert_eq!(perft(&opening, 1), 22);
+    assert_eq!(perft(&opening, 2), 22 * 21);
+    assert_eq!(perft(&opening, 3), 10626);
+    assert_eq!(perft(&opening, 4), 237621);
+
+    // Blue has only the wazir left.
+    let midgame =
+        Position::from_fen("regular 2 AAAAAAAADDDDFFN WNAADADA/AFFAADDA/8/8/8/8/8/7w").unwrap();
+    assert_eq!(perft(&midgame, 1), 211);
+    assert_eq!(perft(&midgame, 2), 406);
+    assert_eq!(perft(&midgame, 3), 81180);
+
+    let divide = perft_divide(&midgame, 3);
+    assert_eq!(divide.len(), 211);
+    assert_eq!(divide.iter().map(|&(_, count)| count).sum::<u64>(), 81180);
+}
+
+#[test]
+fn test_perft_brute_force() {
+    let mut rng = StdRng::seed_from_u64(502);
+    for _ in 0..20 {
+        let mut position = Position::initial();
+        for color in [Color::Red, Color::Blue] {
+            let rank = rng.random_range(0..NUM_SETUP_MOVES);
+            let mov = setup_move_unrank(color, rank).unwrap();
+            position = position.make_setup_move(mov).unwrap();
+        }
+        for _ in 0..rng.random_range(0..40) {
+            let moves: Vec<Move> = moves(&position).collect();
+            let Some(&mov) = moves.choose(&mut rng) else {
+                break;
+            };
+            position = position.make_move(mov).unwrap();
+            if position.stage() != Stage::Regular {
+                break;
+            }
+        }
+        assert_eq!(
+            perft(&position, 2),
+            brute_force_perft(&position, 2),
+            "{position}"
+        );
+    }
+}
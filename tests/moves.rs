@@ -1,5 +1,8 @@
 use std::str::FromStr;
-use wazir_drop::{enums::SimpleEnumExt, AnyMove, Color, Move, Piece, SetupMove, ShortMove};
+use wazir_drop::{
+    enums::SimpleEnumExt, movegen::NUM_SETUP_MOVES, AnyMove, Color, Move, Piece, Position,
+    SetupMove, ShortMove, Symmetry,
+};
 
 #[test]
 fn test_setup_move_size_matches_piece_initial_count() {
@@ -18,6 +21,59 @@ fn test_setup_move_display_from_str() {
     assert!(SetupMove::from_str("AWNAADADAFFAADDa").is_err());
 }
 
+#[test]
+fn test_setup_move_rank() {
+    let first = SetupMove::from_str("AAAAAAAADDDDFFNW").unwrap();
+    assert_eq!(first.rank(), 0);
+    assert_eq!(SetupMove::from_rank(Color::Red, 0), first);
+    let last = SetupMove::from_str("WNFFDDDDAAAAAAAA").unwrap();
+    assert_eq!(last.rank(), NUM_SETUP_MOVES - 1);
+
+    let ranks = (0..NUM_SETUP_MOVES)
+        .step_by(1009)
+        .chain(NUM_SETUP_MOVES - 100..NUM_SETUP_MOVES);
+    for rank in ranks {
+        for color in [Color::Red, Color::Blue] {
+            let mov = SetupMove::from_rank(color, rank);
+            assert_eq!(mov.color, color);
+            assert!(mov.validate_pieces().is_ok());
+            assert_eq!(mov.rank(), rank);
+        }
+    }
+    // Ranks don't depend on the color.
+    let mov = SetupMove::from_str("AWNAADADAFFAADDA").unwrap();
+    assert_eq!(mov.with_color(Color::Blue).rank(), mov.rank());
+}
+
+#[test]
+fn test_setup_move_is_valid() {
+    assert!(SetupMove::from_str("AWNAADADAFFAADDA").unwrap().is_valid());
+    assert!(!SetupMove::from_str("AWNAADADAFFAADDD").unwrap().is_valid());
+}
+
+#[test]
+fn test_setup_move_normalize() {
+    let normalized = SetupMove::from_str("AWNAADADAFFAADDA").unwrap();
+    let flipped = SetupMove::from_str("DADAANWAADDAAFFA").unwrap();
+    for color in Color::all() {
+        let normalized = normalized.with_color(color);
+        let flipped = flipped.with_color(color);
+        assert_eq!(normalized.normalize(), (Symmetry::Identity, normalized));
+        assert_eq!(flipped.normalize(), (Symmetry::FlipX, normalized));
+        let variants: Vec<SetupMove> = flipped.symmetric_variants().collect();
+        assert_eq!(variants, [flipped, normalized]);
+        assert!(variants
+            .iter()
+            .all(|variant| variant.normalize().1 == normalized));
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_setup_move_from_rank_out_of_range() {
+    _ = SetupMove::from_rank(Color::Red, NUM_SETUP_MOVES);
+}
+
 #[test]
 fn test_move_display_from_str() {
     let mov = Move::from_str("A@a1").unwrap();
@@ -31,6 +87,31 @@ fn test_move_display_from_str() {
     assert!(Move::from_str("Da1xNa3").is_err());
 }
 
+#[test]
+fn test_any_move_accessors() {
+    let setup = SetupMove::from_str("wnaadadaffaaddaa").unwrap();
+    let mov = AnyMove::from(setup);
+    assert_eq!(mov, AnyMove::Setup(setup));
+    assert_eq!(AnyMove::from_str("wnaadadaffaaddaa").unwrap(), mov);
+    assert!(mov.is_setup());
+    assert!(!mov.is_regular());
+    assert!(mov.is_irreversible());
+    assert_eq!(mov.color(), Color::Blue);
+
+    let regular = Move::from_str("Da1xna3").unwrap();
+    let mov = AnyMove::from(regular);
+    assert_eq!(mov, AnyMove::Regular(regular));
+    assert_eq!(AnyMove::from_str("Da1xna3").unwrap(), mov);
+    assert!(!mov.is_setup());
+    assert!(mov.is_regular());
+    assert!(!mov.is_irreversible());
+    assert_eq!(mov.color(), Color::Red);
+
+    let drop = AnyMove::from_str("d@a3").unwrap();
+    assert_eq!(drop.color(), Color::Blue);
+    assert!(!drop.is_irreversible());
+}
+
 #[test]
 fn test_any_move_to_short_move() {
     let mov = AnyMove::from_str("AWNAADADAFFAADDA").unwrap();
@@ -70,3 +151,46 @@ fn test_setup_move_with_color() {
     assert_eq!(mov.with_color(Color::Red), mov);
     assert_eq!(mov.with_color(Color::Blue).to_string(), "addaaffadadaanwa");
 }
+
+#[test]
+fn test_move_to_notation() {
+    let position = Position::from_str(
+        "\
+regular
+4
+Af
+FW.A.D.D
+AfFA.DDA
+..A.A.A.
+.....wA.
+...a..ad
+..d..nN.
+a.a...a.
+add....a
+",
+    )
+    .unwrap();
+    let notation = |mov: &str| Move::from_str(mov).unwrap().to_notation(&position);
+    assert_eq!(notation("A@f8"), "A@f8+");
+    assert_eq!(notation("Ab4xwd6"), "Ab4xwd6#");
+
+    let position = Position::from_str(
+        "\
+regular
+4
+AAAAAAAAddFf
+.W......
+..Ff..D.
+......A.
+.......D
+...a..ad
+..d..nN.
+a.a...a.
+add...wa
+",
+    )
+    .unwrap();
+    let notation = |mov: &str| Move::from_str(mov).unwrap().to_notation(&position);
+    assert_eq!(notation("Nf7-g5"), "Nf7-g5+");
+    assert_eq!(notation("Wa2-a1"), "Wa2-a1");
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "nnue")]
+
+use wazir_drop::{format_layer, Features, Nnue, WPSFeatures, WeightStats};
+
+#[test]
+fn test_layers() {
+    let layers = Nnue::layers();
+    let names: Vec<&str> = layers.iter().map(|layer| layer.name).collect();
+    assert_eq!(names, ["embedding", "hidden_0", "hidden_1", "final"]);
+    assert_eq!(layers[0].shape[0], WPSFeatures.count());
+    // Each layer's outputs feed the next one; the embedding is applied to both sides.
+    assert_eq!(layers[1].shape[1], 2 * layers[0].shape[1]);
+    assert_eq!(layers[2].shape[1], layers[1].shape[0]);
+    assert_eq!(layers[3].shape, [1, layers[2].shape[0]]);
+    for layer in &layers {
+        assert_eq!(layer.weights.len(), layer.shape[0] * layer.shape[1]);
+        assert!(format_layer(layer).starts_with(layer.name));
+    }
+}
+
+#[test]
+fn test_weight_stats() {
+    assert!(WeightStats::new(&[], 4).is_none());
+    let stats = WeightStats::new(&[-2, -1, 0, 1, 5], 4).unwrap();
+    assert_eq!(stats.min, -2);
+    assert_eq!(stats.max, 5);
+    assert!((stats.mean - 0.6).abs() < 1e-9);
+    assert_eq!(stats.histogram, [2, 2, 0, 1]);
+    assert_eq!(stats.bucket_start(0), -2.0);
+    let stats = WeightStats::new(&[3, 3], 2).unwrap();
+    assert_eq!(stats.histogram, [2, 0]);
+}
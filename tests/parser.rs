@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use wazir_drop::parser::{self, Parser, ParserExt};
 
 #[test]
@@ -52,6 +53,17 @@ fn test_endl() {
     assert!(p.parse(b"abc\n").is_err());
 }
 
+#[test]
+fn test_endl_or_end() {
+    let p = parser::endl_or_end();
+    let result = p.parse(b"\nabc").unwrap();
+    assert_eq!(result.remaining, b"abc");
+    let result = p.parse(b"").unwrap();
+    assert_eq!(result.remaining, b"");
+
+    assert!(p.parse(b"abc").is_err());
+}
+
 #[test]
 fn test_u8() {
     let p = parser::u8();
@@ -116,6 +128,40 @@ fn test_or() {
     assert!(p.parse(b"xxx").is_err());
 }
 
+#[test]
+fn test_any_of() {
+    let p = parser::any_of![
+        parser::exact(b"ab").map(|_| 1),
+        parser::exact(b"abc").map(|_| 2),
+        parser::exact(b"d").map(|_| 3),
+    ];
+
+    // The first match wins, even if a later one is longer.
+    let result = p.parse(b"abcxyz").unwrap();
+    assert_eq!(result.value, 1);
+    assert_eq!(result.remaining, b"cxyz");
+
+    let result = p.parse(b"dxyz").unwrap();
+    assert_eq!(result.value, 3);
+    assert_eq!(result.remaining, b"xyz");
+
+    assert!(p.parse(b"xxx").is_err());
+}
+
+#[test]
+fn test_any_of_lazy() {
+    let constructed = Cell::new(0);
+    let counted = |s: &'static [u8]| {
+        constructed.set(constructed.get() + 1);
+        parser::exact(s)
+    };
+    let p = parser::any_of![counted(b"a"), counted(b"b"), counted(b"c")];
+    assert!(p.parse(b"a").is_ok());
+    assert_eq!(constructed.get(), 1);
+    assert!(p.parse(b"c").is_ok());
+    assert_eq!(constructed.get(), 4);
+}
+
 #[test]
 fn test_map() {
     let p = parser::byte().map(|b| b + 1);
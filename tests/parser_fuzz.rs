@@ -0,0 +1,71 @@
+//! Feeds mutated inputs into the `FromStr` parsers. The same checks run under cargo-fuzz in
+//! `fuzz/`, with coverage guidance.
+
+mod common;
+
+use common::{parse_moves, round_trip::check_all};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::time::Duration;
+use wazir_drop::{CliCommand, Color, Position};
+
+fn corpus() -> Vec<String> {
+    let opening = parse_moves(&["WNAADADAFFAADDAA", "wnaadadaffaaddaa", "Aa3-c5"]);
+    let mut corpus: Vec<String> = [
+        "A@a1",
+        "Aa3-c5",
+        "nc1xWa2",
+        "a3-c5",
+        "Time 1000",
+        "Start",
+        "LastInfo",
+        "Version",
+        "Ponder on",
+        "Quit",
+        "red",
+        "red_win",
+        "end draw",
+        "time_forfeit",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    corpus.push(CliCommand::TimeLimit(Duration::from_millis(4000)).to_string());
+    corpus.push(CliCommand::MoveCap(Duration::from_millis(500)).to_string());
+    corpus.push(CliCommand::Opening(opening.clone()).to_string());
+    corpus.push(Position::initial().to_string());
+    corpus.push(Position::replay(&opening).unwrap().to_string());
+    corpus.push(Position::initial().occupied_by(Color::Red).to_string());
+    corpus
+}
+
+fn mutate(rng: &mut StdRng, input: &str) -> String {
+    let mut bytes = input.as_bytes().to_vec();
+    for _ in 0..rng.random_range(1..=4) {
+        let index = rng.random_range(0..=bytes.len());
+        let byte = match rng.random_range(0..4) {
+            0 => rng.random_range(b'0'..=b'9'),
+            1 => rng.random_range(b'a'..=b'z'),
+            2 => b" \n-x@.A"[rng.random_range(0..7)],
+            _ => rng.random(),
+        };
+        match rng.random_range(0..3) {
+            0 if index < bytes.len() => bytes[index] = byte,
+            1 if index < bytes.len() => _ = bytes.remove(index),
+            _ => bytes.insert(index, byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[test]
+fn test_parsers_mutated_inputs() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let corpus = corpus();
+    for input in &corpus {
+        check_all(input);
+    }
+    for _ in 0..20000 {
+        let input = &corpus[rng.random_range(0..corpus.len())];
+        check_all(&mutate(&mut rng, input));
+    }
+}
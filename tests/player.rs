@@ -0,0 +1,236 @@
+mod common;
+
+use common::{parse_moves, small_tables, start_position, SETUP_MOVES};
+use std::{
+    collections::HashSet,
+    str::FromStr,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use wazir_drop::{
+    book::{self, OpeningBook},
+    clock::{TimeControl, Timer},
+    constants::{Eval, Hyperparameters, ONE_PLY},
+    movegen, AnyMove, Color, DefaultEvaluator, Evaluator, MainPlayerFactory, PlayerFactory,
+    Position, Score, ScoreExpanded, Search, SearchInfo, SetupMove, Symmetry,
+};
+
+#[test]
+fn test_search_info_display() {
+    let info = SearchInfo {
+        depth: 400,
+        score: ScoreExpanded::Eval(-12).into(),
+        nodes: 12345,
+        time: Duration::from_millis(100),
+        pv: ["Aa3-c5", "ah7-f5"]
+            .iter()
+            .map(|s| AnyMove::from_str(s).unwrap())
+            .collect(),
+    };
+    assert_eq!(
+        info.to_string(),
+        "depth=400 score=-12 nodes=12345 time=100 pv=Aa3-c5,ah7-f5"
+    );
+
+    let info = SearchInfo {
+        depth: 0,
+        score: Score::DRAW,
+        nodes: 0,
+        time: Duration::ZERO,
+        pv: Vec::new(),
+    };
+    assert_eq!(info.to_string(), "depth=0 score=0 nodes=0 time=0 pv=");
+}
+
+#[test]
+fn test_main_player_factory_builder_book() {
+    let red = SetupMove::from_str("NWAAAAAADFFDDDAA").unwrap();
+    let blue = SetupMove::from_str("ddddffaaaaaaaawn").unwrap();
+    let factory = MainPlayerFactory::builder()
+        .hyperparameters(&small_tables())
+        .book(OpeningBook::new(&[(red, blue)]).unwrap())
+        .build();
+    let mut timer = Timer::new(Duration::from_secs(10));
+    timer.start();
+
+    let position = Position::initial();
+    let mut red_player = factory.create("", Color::Red, &Position::initial(), &[], None);
+    let AnyMove::Setup(red_move) = red_player.make_move(&position, &timer) else {
+        panic!("Expected setup move");
+    };
+    assert!(red_move == red || red_move == Symmetry::FlipX.apply_to_setup(red));
+
+    let opening = [AnyMove::Setup(red_move)];
+    let position = position.make_any_move(opening[0]).unwrap();
+    let mut blue_player = factory.create("", Color::Blue, &Position::initial(), &opening, None);
+    let AnyMove::Setup(blue_move) = blue_player.make_move(&position, &timer) else {
+        panic!("Expected setup move");
+    };
+    let expected = if red_move == red {
+        blue
+    } else {
+        Symmetry::FlipX.apply_to_setup(blue)
+    };
+    assert_eq!(blue_move, expected);
+    assert!(blue_player.last_search_info().is_none());
+}
+
+#[test]
+fn test_main_player_ponder() {
+    let hyperparameters = small_tables();
+    let factory = MainPlayerFactory::builder()
+        .hyperparameters(&hyperparameters)
+        .build();
+    let opening = parse_moves(&SETUP_MOVES);
+    let mut position = Position::replay(&opening).unwrap();
+    let time_control = TimeControl::fixed(Duration::from_secs(10));
+    let mut player = factory.create(
+        "",
+        Color::Red,
+        &Position::initial(),
+        &opening,
+        Some(time_control),
+    );
+    let mut timer = Timer::new(Duration::from_secs(10));
+    timer.start();
+
+    for _ in 0..3 {
+        let mov = player.make_move(&position, &timer);
+        position = position.make_any_move(mov).unwrap();
+        let info = player.last_search_info().unwrap();
+
+        player.start_ponder(&position);
+        thread::sleep(Duration::from_millis(20));
+
+        // The expected reply if there is one, otherwise any legal move.
+        let reply = match info.pv.get(1) {
+            Some(&reply) => reply,
+            None => AnyMove::Regular(movegen::moves(&position).next().unwrap()),
+        };
+        let start = Instant::now();
+        player.opponent_move(&position, reply, &timer);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        position = position.make_any_move(reply).unwrap();
+    }
+
+    // A miss: not the expected reply. Start again from the opening, where every move leaves
+    // several replies.
+    let mut player = factory.create(
+        "",
+        Color::Red,
+        &Position::initial(),
+        &opening,
+        Some(time_control),
+    );
+    let mut timer = Timer::new(Duration::from_secs(10));
+    timer.start();
+    let mut position = Position::replay(&opening).unwrap();
+    let mov = player.make_move(&position, &timer);
+    position = position.make_any_move(mov).unwrap();
+    let expected = player.last_search_info().unwrap().pv.get(1).copied();
+    player.start_ponder(&position);
+    let reply = movegen::moves(&position)
+        .map(AnyMove::Regular)
+        .find(|&reply| Some(reply) != expected)
+        .unwrap();
+    player.opponent_move(&position, reply, &timer);
+    position = position.make_any_move(reply).unwrap();
+    assert!(position
+        .make_any_move(player.make_move(&position, &timer))
+        .is_ok());
+
+    // Dropping the player stops pondering.
+    player.start_ponder(&position);
+    drop(player);
+}
+
+#[test]
+fn test_main_player_move_cap() {
+    let factory = MainPlayerFactory::builder()
+        .hyperparameters(&small_tables())
+        .build();
+    let opening = parse_moves(&SETUP_MOVES);
+    let position = Position::replay(&opening).unwrap();
+    let time_control = TimeControl {
+        base: Duration::from_secs(1000),
+        increment: Duration::from_secs(10),
+        move_cap: Some(Duration::from_millis(200)),
+    };
+    let mut player = factory.create(
+        "",
+        Color::Red,
+        &Position::initial(),
+        &opening,
+        Some(time_control),
+    );
+    let mut timer = Timer::new(time_control.base);
+    timer.start();
+    let start = Instant::now();
+    let mov = player.make_move(&position, &timer);
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert!(position.make_any_move(mov).is_ok());
+}
+
+#[test]
+fn test_main_player_random_move() {
+    let margin = 0.05;
+    let depth = 3 * ONE_PLY;
+    let hyperparameters = Hyperparameters {
+        random_move_margin: Some(margin),
+        random_move_seed: 481,
+        ..small_tables()
+    };
+    let factory = MainPlayerFactory::builder()
+        .hyperparameters(&hyperparameters)
+        .max_depth(depth)
+        .build();
+    let opening = parse_moves(&SETUP_MOVES);
+    let (position, history) = start_position();
+
+    // The moves within the margin of the best at the same depth.
+    let evaluator = Arc::new(DefaultEvaluator::default());
+    let threshold = (margin * evaluator.scale()) as Eval;
+    let result = Search::new(&hyperparameters, &evaluator).search(
+        &position,
+        Some(depth),
+        None,
+        Some(threshold),
+        false,
+        &history,
+    );
+    let top_moves: Vec<AnyMove> = result.top_moves.iter().map(|m| m.mov.into()).collect();
+    assert!(top_moves.len() > 1);
+
+    let play = |game_id: usize| {
+        let mut player = factory.create(
+            &game_id.to_string(),
+            Color::Red,
+            &Position::initial(),
+            &opening,
+            None,
+        );
+        let mut timer = Timer::new(Duration::from_secs(1000));
+        timer.start();
+        player.make_move(&position, &timer)
+    };
+    let moves: Vec<AnyMove> = (0..8).map(play).collect();
+    assert!(moves.iter().all(|mov| top_moves.contains(mov)));
+    assert!(moves.iter().collect::<HashSet<_>>().len() > 1);
+    // The same seed and game id give the same move.
+    assert_eq!((0..8).map(play).collect::<Vec<_>>(), moves);
+}
+
+#[test]
+fn test_main_player_start_position() {
+    let factory = MainPlayerFactory::default();
+    let start = Position::initial()
+        .make_setup_move(book::red_setup())
+        .unwrap();
+    let mut player = factory.create("", Color::Blue, &start, &[], None);
+    let mut timer = Timer::new(Duration::from_secs(10));
+    timer.start();
+    let mov = player.make_move(&start, &timer);
+    assert!(mov.is_setup());
+    assert!(start.make_any_move(mov).is_ok());
+}
@@ -1,5 +1,13 @@
+mod common;
+
+use common::{parse_moves, position_after, SETUP_MOVES};
+use rand::{rngs::StdRng, seq::IndexedRandom, Rng, SeedableRng};
 use std::str::FromStr;
-use wazir_drop::{AnyMove, Outcome, Position, Stage};
+use wazir_drop::{
+    constants::{PLY_AFTER_SETUP, PLY_DRAW},
+    movegen::{self, NUM_SETUP_MOVES},
+    AnyMove, Color, EndReason, Move, MoveEffect, Outcome, Piece, Position, SetupMove, Stage,
+};
 
 #[test]
 fn test_outcome_display_round_trip() {
@@ -8,6 +16,19 @@ fn test_outcome_display_round_trip() {
     }
 }
 
+#[test]
+fn test_end_reason_display_round_trip() {
+    for r in [
+        EndReason::WazirCaptured,
+        EndReason::MoveLimit,
+        EndReason::Resignation,
+        EndReason::TimeForfeit,
+        EndReason::Adjudication,
+    ] {
+        assert_eq!(EndReason::from_str(&r.to_string()).unwrap(), r);
+    }
+}
+
 #[test]
 fn test_stage_display_round_trip() {
     for s in [
@@ -396,6 +417,11 @@ add.w..a
     let position2 = position
         .make_any_move(AnyMove::from_str("nc1xWa2").unwrap())
         .unwrap();
+    assert_eq!(position.end(), None);
+    assert_eq!(
+        position2.end(),
+        Some((Outcome::BlueWin, EndReason::WazirCaptured))
+    );
     assert_eq!(
         position2.to_string(),
         "\
@@ -489,6 +515,7 @@ add.w..a
     );
 
     let position3 = position2.make_null_move().unwrap();
+    assert_eq!(position3.end(), Some((Outcome::Draw, EndReason::MoveLimit)));
     assert_eq!(
         position3.to_string(),
         "\
@@ -508,3 +535,392 @@ add.w..a
 
     assert!(position3.make_null_move().is_err());
 }
+
+#[test]
+fn test_ply_draw() {
+    assert_eq!(Position::initial().ply_draw(), PLY_DRAW);
+    assert!(Position::initial_with_ply_draw(PLY_AFTER_SETUP).is_err());
+    assert!(Position::initial_with_ply_draw(PLY_DRAW + 1).is_err());
+
+    let mut position = Position::initial_with_ply_draw(6).unwrap();
+    for mov in ["WNAADADAFFAADDAA", "wnaadadaffaaddaa", "Aa3-c5", "ag3-e5"] {
+        position = position
+            .make_any_move(AnyMove::from_str(mov).unwrap())
+            .unwrap();
+        assert_eq!(position.ply_draw(), 6);
+    }
+    assert!(position.with_ply_draw(4).is_err());
+
+    // Both text formats record the draw ply if it isn't `PLY_DRAW`.
+    let text = position.to_string();
+    assert_eq!(text.lines().nth(1), Some("4/6"));
+    let parsed = Position::from_str(&text).unwrap();
+    assert_eq!(parsed.ply_draw(), 6);
+    assert_eq!(parsed.to_string(), text);
+    let fen = position.to_fen();
+    assert!(fen.starts_with("regular 4/6 "));
+    assert_eq!(Position::from_fen(&fen).unwrap().ply_draw(), 6);
+    let fen = position.with_ply_draw(PLY_DRAW).unwrap().to_fen();
+    assert!(fen.starts_with("regular 4 "));
+    assert_eq!(Position::from_fen(&fen).unwrap().ply_draw(), PLY_DRAW);
+    assert_eq!(
+        position.with_ply_draw(PLY_DRAW).unwrap().ply_draw(),
+        PLY_DRAW
+    );
+
+    let position = position.make_null_move().unwrap();
+    assert_eq!(position.stage(), Stage::Regular);
+    let position = position.make_null_move().unwrap();
+    assert_eq!(position.ply(), 6);
+    assert_eq!(position.end(), Some((Outcome::Draw, EndReason::MoveLimit)));
+    assert!(position.with_ply_draw(PLY_DRAW).is_err());
+}
+
+#[test]
+fn test_make_any_move_with_effect() {
+    let make = |position: &Position, mov: &str| {
+        position
+            .make_any_move_with_effect(AnyMove::from_str(mov).unwrap())
+            .unwrap()
+    };
+
+    let (position, effect) = make(&Position::initial(), "WNAADADAFFAADDAA");
+    assert_eq!(
+        effect,
+        MoveEffect {
+            previous_stage: None,
+            captured: None,
+            outcome: None,
+            irreversible: true,
+        }
+    );
+    let (position, effect) = make(&position, "wnaadadaffaaddaa");
+    assert_eq!(effect.previous_stage, Some(Stage::Setup));
+    assert_eq!(position.stage(), Stage::Regular);
+    assert!(effect.irreversible);
+    let (_, effect) = make(&position, "Aa3-c5");
+    assert_eq!(
+        effect,
+        MoveEffect {
+            previous_stage: None,
+            captured: None,
+            outcome: None,
+            irreversible: false,
+        }
+    );
+
+    let position = Position::from_str(
+        "\
+regular
+101
+AFf
+.W.A.D.D
+AaFA.DDA
+n.A.A.A.
+......A.
+...a.a.d
+..d...N.
+a.a...f.
+add.w..a
+",
+    )
+    .unwrap();
+    let (position, effect) = make(&position, "nc1xWa2");
+    assert_eq!(
+        effect,
+        MoveEffect {
+            previous_stage: Some(Stage::Regular),
+            captured: Some(Piece::Wazir),
+            outcome: Some(Outcome::BlueWin),
+            irreversible: false,
+        }
+    );
+    assert_eq!(position.stage(), Stage::End(Outcome::BlueWin));
+    assert!(position
+        .make_any_move_with_effect(AnyMove::from_str("Aa3-c5").unwrap())
+        .is_err());
+}
+
+#[test]
+fn test_generated_zobrist_keys() {
+    // Fails if the generated Zobrist tables change. Update after regenerating on purpose.
+    assert_eq!(Position::initial().hash(), 0x1e2c68c91a9256d5);
+}
+
+#[test]
+fn test_canonical_hash() {
+    let position = position_after(&SETUP_MOVES);
+    let mirrored = Position::replay(&[
+        AnyMove::from_str("ADADAANWAADDAAFF").unwrap(),
+        AnyMove::from_str("adadaanwaaddaaff").unwrap(),
+    ])
+    .unwrap();
+    assert_ne!(position.hash(), mirrored.hash());
+    assert_eq!(position.canonical_hash(), mirrored.canonical_hash());
+
+    let other = Position::replay(&[
+        AnyMove::from_str("NWAADADAFFAADDAA").unwrap(),
+        AnyMove::from_str("wnaadadaffaaddaa").unwrap(),
+    ])
+    .unwrap();
+    assert_ne!(position.canonical_hash(), other.canonical_hash());
+}
+
+#[test]
+fn test_replay() {
+    let moves = parse_moves(&["WNAADADAFFAADDAA", "wnaadadaffaaddaa", "Aa3-c5"]);
+    let position = Position::replay(&moves).unwrap();
+    assert_eq!(position.stage(), Stage::Regular);
+    assert_eq!(position.ply(), 3);
+
+    let mut position2 = Position::initial();
+    for &mov in &moves {
+        position2 = position2.make_any_move(mov).unwrap();
+    }
+    assert_eq!(position.to_string(), position2.to_string());
+
+    assert!(Position::replay(&moves[1..]).is_err());
+}
+
+#[test]
+fn test_after_null_move() {
+    assert!(Position::initial().after_null_move().is_none());
+    let position = position_after(&SETUP_MOVES);
+    let position2 = position.after_null_move().unwrap();
+    assert_eq!(position2.hash(), position.make_null_move().unwrap().hash());
+    assert_eq!(position2.ply(), position.ply() + 1);
+    assert_eq!(position2.to_move(), Color::Blue);
+    assert_ne!(position2.hash(), position.hash());
+
+    // Red wazir attacked by the knight.
+    let position = Position::from_str(
+        "\
+regular
+100
+AFf
+.W.A.D.D
+AaFA.DDA
+n.A.A.A.
+......A.
+...a.a.d
+..d...N.
+a.a...f.
+add.w..a
+",
+    )
+    .unwrap();
+    assert!(position.after_null_move().is_none());
+    assert!(position.make_null_move().is_ok());
+}
+
+#[test]
+fn test_validate_random_games() {
+    let mut rng = StdRng::seed_from_u64(433);
+    for _ in 0..50 {
+        let mut position = Position::initial();
+        position.validate().unwrap();
+        loop {
+            let next = match position.stage() {
+                Stage::Setup => {
+                    let mov = SetupMove::from_rank(
+                        position.to_move(),
+                        rng.random_range(0..NUM_SETUP_MOVES),
+                    );
+                    position.make_any_move(AnyMove::Setup(mov)).unwrap()
+                }
+                Stage::Regular => {
+                    let null = if rng.random_bool(0.05) {
+                        position.make_null_move().ok()
+                    } else {
+                        None
+                    };
+                    null.unwrap_or_else(|| random_move(&position, &mut rng))
+                }
+                Stage::End(_) => break,
+            };
+            next.validate().unwrap();
+            position = next;
+        }
+    }
+}
+
+fn random_move(position: &Position, rng: &mut StdRng) -> Position {
+    let moves: Vec<_> = movegen::pseudomoves(position).collect();
+    position.make_move(*moves.choose(rng).unwrap()).unwrap()
+}
+
+#[test]
+fn test_unmake_random_games() {
+    let same = |a: &Position, b: &Position| a.to_string() == b.to_string() && a.hash() == b.hash();
+    let mut rng = StdRng::seed_from_u64(465);
+    for _ in 0..50 {
+        let mut position = Position::initial();
+        loop {
+            let next = match position.stage() {
+                Stage::Setup => {
+                    let mov = AnyMove::Setup(SetupMove::from_rank(
+                        position.to_move(),
+                        rng.random_range(0..NUM_SETUP_MOVES),
+                    ));
+                    let next = position.make_any_move(mov).unwrap();
+                    assert!(same(&next.unmake_any_move(mov).unwrap(), &position));
+                    next
+                }
+                Stage::Regular if rng.random_bool(0.05) => {
+                    let next = position.make_null_move().unwrap();
+                    assert!(same(&next.unmake_null_move().unwrap(), &position));
+                    next
+                }
+                Stage::Regular => {
+                    let moves: Vec<_> = movegen::pseudomoves(&position).collect();
+                    let mov = *moves.choose(&mut rng).unwrap();
+                    let next = position.make_move(mov).unwrap();
+                    assert!(same(&next.unmake_move(mov).unwrap(), &position));
+                    next
+                }
+                Stage::End(_) => break,
+            };
+            position = next;
+        }
+    }
+}
+
+#[test]
+fn test_unmake_wrong_move() {
+    let position = position_after(&SETUP_MOVES);
+    assert!(position
+        .unmake_any_move(AnyMove::from_str("WNAADADAFFAADDAA").unwrap())
+        .is_err());
+    assert!(position
+        .unmake_any_move(AnyMove::from_str("wnaadadaffaaddad").unwrap())
+        .is_err());
+    assert!(position.unmake_null_move().is_err());
+
+    let next = position
+        .make_any_move(AnyMove::from_str("Aa3-c5").unwrap())
+        .unwrap();
+    assert!(next
+        .unmake_any_move(AnyMove::from_str("Aa3-b4").unwrap())
+        .is_err());
+    assert!(next
+        .unmake_any_move(AnyMove::from_str("Ab4-c5").unwrap())
+        .is_err());
+}
+
+#[test]
+fn test_same_material_and_board() {
+    let position = position_after(&SETUP_MOVES);
+    let moved = position_after(&["WNAADADAFFAADDAA", "wnaadadaffaaddaa", "Aa3-c5"]);
+    let transposed = ["ag3-e5", "Ac5-a3", "ae5-g3"]
+        .iter()
+        .fold(moved, |position, mov| {
+            position.make_move(Move::from_str(mov).unwrap()).unwrap()
+        });
+    assert_eq!(transposed.ply(), position.ply() + 4);
+    assert!(transposed.same_material_and_board(&position));
+    assert_eq!(
+        transposed.hash_for_repetition(),
+        position.hash_for_repetition()
+    );
+    assert!(!moved.same_material_and_board(&position));
+    assert!(!position
+        .make_null_move()
+        .unwrap()
+        .same_material_and_board(&position));
+}
+
+#[test]
+fn test_describe() {
+    let position = Position::initial();
+    assert_eq!(position.describe(), "Setup, Red to move, move 1");
+    let position = position_after(&SETUP_MOVES);
+    assert_eq!(position.material_balance(), 0);
+    assert_eq!(
+        position.describe(),
+        "Regular, Red to move, +0 material, move 2"
+    );
+
+    // Red has captured all of blue's pieces except the wazir.
+    let position = Position::from_str(
+        "\
+regular
+2
+AAAAAAAADDDDFFN
+WNAADADA
+AFFAADDA
+........
+........
+........
+........
+........
+.......w
+",
+    )
+    .unwrap();
+    assert_eq!(position.material(Color::Red), 38);
+    assert_eq!(position.material(Color::Blue), 0);
+    assert_eq!(
+        position.describe(),
+        "Regular, Red to move, +38 material, move 2"
+    );
+    let position = position.make_move(Move::from_str("F@g7").unwrap()).unwrap();
+    assert_eq!(
+        position.describe(),
+        "Regular, Blue to move, -38 material, in check, move 2"
+    );
+}
+
+#[test]
+fn test_fen() {
+    let position = Position::initial();
+    assert_eq!(position.to_fen(), "setup 0 - 8/8/8/8/8/8/8/8");
+
+    let position = position_after(&["WNAADADAFFAADDAA", "wnaadadaffaaddaa", "Aa3-c5"]);
+    let fen = position.to_fen();
+    assert_eq!(
+        Position::from_fen(&fen).unwrap().to_string(),
+        position.to_string()
+    );
+
+    let fen = "regular 2 AAAAAAAADDDDFFN WNAADADA/AFFAADDA/8/8/8/8/8/7w";
+    let position = Position::from_fen(fen).unwrap();
+    assert_eq!(position.to_fen(), fen);
+    assert_eq!(
+        position.num_captured(Piece::Knight.with_color(Color::Red)),
+        1
+    );
+
+    // A missing captured knight.
+    assert!(Position::from_fen("regular 2 AAAAAAAADDDDFF WNAADADA/AFFAADDA/8/8/8/8/8/7w").is_err());
+    // A row that is too long.
+    assert!(
+        Position::from_fen("regular 2 AAAAAAAADDDDFFN WNAADADA/AFFAADDA/8/8/8/8/8/8w").is_err()
+    );
+    // Too few rows.
+    assert!(Position::from_fen("regular 2 AAAAAAAADDDDFFN WNAADADA/AFFAADDA/8/8/8/8/7w").is_err());
+}
+
+#[test]
+fn test_fen_random_games() {
+    let mut rng = StdRng::seed_from_u64(501);
+    for _ in 0..20 {
+        let mut position = Position::initial();
+        loop {
+            let fen = position.to_fen();
+            assert!(!fen.contains('\n'));
+            let parsed = Position::from_fen(&fen).unwrap();
+            assert_eq!(parsed.to_string(), position.to_string());
+            position = match position.stage() {
+                Stage::Setup => {
+                    let mov = SetupMove::from_rank(
+                        position.to_move(),
+                        rng.random_range(0..NUM_SETUP_MOVES),
+                    );
+                    position.make_any_move(AnyMove::Setup(mov)).unwrap()
+                }
+                Stage::Regular => random_move(&position, &mut rng),
+                Stage::End(_) => break,
+            };
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use wazir_drop::{Score, ScoreExpanded};
+use wazir_drop::{
+    constants::{Eval, Ply, PLY_DRAW},
+    Score, ScoreExpanded,
+};
 
 #[test]
 fn test_score_to_string() {
@@ -10,20 +13,31 @@ fn test_score_to_string() {
 #[test]
 fn test_score_to_absolute() {
     assert_eq!(
-        ScoreExpanded::Eval(17).to_absolute(3),
+        ScoreExpanded::Eval(17).to_absolute(3, PLY_DRAW),
         ScoreExpanded::Eval(17)
     );
-    assert_eq!(ScoreExpanded::Win(5).to_absolute(3), ScoreExpanded::Win(8));
     assert_eq!(
-        ScoreExpanded::Win(100).to_absolute(3),
+        ScoreExpanded::Win(5).to_absolute(3, PLY_DRAW),
+        ScoreExpanded::Win(8)
+    );
+    assert_eq!(
+        ScoreExpanded::Win(100).to_absolute(3, PLY_DRAW),
         ScoreExpanded::Eval(0)
     );
     assert_eq!(
-        ScoreExpanded::Loss(5).to_absolute(3),
+        ScoreExpanded::Loss(5).to_absolute(3, PLY_DRAW),
         ScoreExpanded::Loss(8)
     );
     assert_eq!(
-        ScoreExpanded::Loss(100).to_absolute(3),
+        ScoreExpanded::Loss(100).to_absolute(3, PLY_DRAW),
+        ScoreExpanded::Eval(0)
+    );
+    assert_eq!(
+        ScoreExpanded::Win(5).to_absolute(3, 8),
+        ScoreExpanded::Win(8)
+    );
+    assert_eq!(
+        ScoreExpanded::Win(5).to_absolute(3, 7),
         ScoreExpanded::Eval(0)
     );
 }
@@ -83,3 +97,134 @@ fn test_score_prev_next() {
         Score::from(ScoreExpanded::Loss(11))
     );
 }
+
+fn max_eval() -> Eval {
+    match ScoreExpanded::from(Score::WIN_MAX_PLY.prev()) {
+        ScoreExpanded::Eval(eval) => eval,
+        _ => unreachable!(),
+    }
+}
+
+fn sample_scores() -> Vec<Score> {
+    let mut scores = vec![
+        Score::DRAW,
+        Score::INFINITE,
+        -Score::INFINITE,
+        Score::WIN_MAX_PLY,
+        -Score::WIN_MAX_PLY,
+        Score::WIN_MAX_PLY.prev(),
+        (-Score::WIN_MAX_PLY).next(),
+    ];
+    for eval in [1, -1, 17, -17, 1000, -1000, 1_000_000, -1_000_000] {
+        scores.push(ScoreExpanded::Eval(eval).into());
+    }
+    for ply in [0, 1, 2, 50, PLY_DRAW - 1, PLY_DRAW, Ply::MAX] {
+        scores.push(ScoreExpanded::Win(ply).into());
+        scores.push(ScoreExpanded::Loss(ply).into());
+    }
+    scores
+}
+
+#[test]
+fn test_score_expanded_round_trip() {
+    for score in sample_scores() {
+        assert_eq!(Score::from(ScoreExpanded::from(score)), score);
+        assert_eq!(-(-score), score);
+    }
+    assert_eq!(ScoreExpanded::from(Score::INFINITE), ScoreExpanded::Win(0));
+    assert_eq!(
+        ScoreExpanded::from(-Score::INFINITE),
+        ScoreExpanded::Loss(0)
+    );
+    assert_eq!(
+        ScoreExpanded::from(Score::WIN_MAX_PLY),
+        ScoreExpanded::Win(Ply::MAX)
+    );
+}
+
+#[test]
+fn test_score_prev_next_bounds() {
+    for score in sample_scores() {
+        if score != Score::INFINITE {
+            assert_eq!(score.next().prev(), score);
+            assert!(score.next() > score);
+        }
+        if score != -Score::INFINITE {
+            assert_eq!(score.prev().next(), score);
+            assert!(score.prev() < score);
+        }
+    }
+    assert_eq!(Score::INFINITE.next(), Score::INFINITE);
+    assert_eq!((-Score::INFINITE).prev(), -Score::INFINITE);
+}
+
+#[test]
+fn test_score_offset_bounds() {
+    for score in sample_scores() {
+        for x in [0, 1, 100, 1_000_000] {
+            let shifted = score.offset(x);
+            // Offsets never turn evals into wins or losses.
+            assert_eq!(
+                matches!(ScoreExpanded::from(shifted), ScoreExpanded::Eval(_)),
+                matches!(ScoreExpanded::from(score), ScoreExpanded::Eval(_)),
+            );
+            if let ScoreExpanded::Eval(eval) = ScoreExpanded::from(score) {
+                if eval.abs() + x <= max_eval() {
+                    assert_eq!(shifted.offset(-x), score);
+                    assert_eq!(score.offset(-x).offset(x), score);
+                }
+            } else {
+                assert_eq!(shifted, score);
+            }
+        }
+    }
+    let max_score = Score::WIN_MAX_PLY.prev();
+    assert_eq!(Score::DRAW.offset(Eval::MAX), max_score);
+    assert_eq!(Score::DRAW.offset(Eval::MIN), -max_score);
+    assert_eq!(max_score.offset(1), max_score);
+}
+
+#[test]
+fn test_score_relative_absolute_bounds() {
+    for score in sample_scores() {
+        for ply in [0, 1, 2, 50, PLY_DRAW] {
+            match ScoreExpanded::from(score) {
+                ScoreExpanded::Eval(_) => {
+                    assert_eq!(score.to_relative(ply), score);
+                    assert_eq!(score.to_absolute(ply, PLY_DRAW), score);
+                }
+                ScoreExpanded::Win(p) | ScoreExpanded::Loss(p) => {
+                    if (ply..=PLY_DRAW).contains(&p) {
+                        assert_eq!(score.to_relative(ply).to_absolute(ply, PLY_DRAW), score);
+                    }
+                    if p.checked_add(ply).is_some_and(|p2| p2 <= PLY_DRAW) {
+                        assert_eq!(score.to_absolute(ply, PLY_DRAW).to_relative(ply), score);
+                    } else {
+                        assert_eq!(score.to_absolute(ply, PLY_DRAW), Score::DRAW);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_score_mate_preference() {
+    let win = |ply: Ply| Score::from(ScoreExpanded::Win(ply));
+    let loss = |ply: Ply| Score::from(ScoreExpanded::Loss(ply));
+    let eval = Score::from(ScoreExpanded::Eval(1000));
+
+    // Faster wins and slower losses are better.
+    assert!(win(10) > win(20));
+    assert!(loss(20) > loss(10));
+    assert!(win(PLY_DRAW) > eval && eval > loss(PLY_DRAW));
+
+    assert!(win(10).is_winning() && !win(10).is_losing());
+    assert!(loss(10).is_losing() && !loss(10).is_winning());
+    assert!(!eval.is_winning() && !eval.is_losing());
+    assert!(!Score::DRAW.is_winning() && !Score::DRAW.is_losing());
+
+    assert_eq!(win(10).mate_distance(4), Some(6));
+    assert_eq!(loss(10).mate_distance(4), Some(-6));
+    assert_eq!(eval.mate_distance(4), None);
+}
@@ -0,0 +1,752 @@
+mod common;
+
+use common::{history_for, small_tables, start_position};
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use wazir_drop::{
+    book,
+    constants::{Eval, Hyperparameters, Ply, ONE_PLY, PLY_DRAW},
+    movegen, CumulativeStats, Deadlines, DefaultEvaluator, EvalCacheStats, History, Move, Outcome,
+    Piece, Position, Score, Search, Stage, TTable, TimeoutKind, TimeoutStats,
+};
+
+#[test]
+fn test_winning_avoids_repetition() {
+    let mut position = Position::from_str(
+        "\
+regular
+2
+AAAAAAAADDDDFFN
+WNAADADA
+AFFAADDA
+........
+........
+........
+........
+........
+.......w
+",
+    )
+    .unwrap();
+    let mut history = History::new_from_position(&position);
+    history.push_position_irreversible(&position);
+    history.push_position_irreversible(&position);
+    for mov in ["Na2-c3", "wh8-h7", "Nc3-a2", "wh7-h8"] {
+        let mov = Move::from_str(mov).unwrap();
+        position = position.make_move(mov).unwrap();
+        history.push_position(&position);
+    }
+
+    let mut search = Search::new(&small_tables(), &Arc::new(DefaultEvaluator::default()));
+    let result = search.search(&position, Some(4), None, None, true, &history);
+    assert!(result.score > Score::DRAW);
+    assert_ne!(result.pv[0], Move::from_str("Na2-c3").unwrap());
+}
+
+#[test]
+fn test_must_win() {
+    let mut position = Position::from_str(
+        "\
+regular
+2
+AAAAAAAADDDDFFN
+WNAADADA
+AFFAADDA
+........
+........
+........
+........
+........
+.......w
+",
+    )
+    .unwrap();
+    let mut history = History::new_from_position(&position);
+    history.push_position_irreversible(&position);
+    history.push_position_irreversible(&position);
+    for mov in ["Na2-c3", "wh8-h7", "Nc3-a2"] {
+        let mov = Move::from_str(mov).unwrap();
+        position = position.make_move(mov).unwrap();
+        history.push_position(&position);
+    }
+    let draw = Move::from_str("wh7-h8").unwrap();
+
+    let mut search = Search::new(&small_tables(), &Arc::new(DefaultEvaluator::default()));
+    let result = search.search(&position, Some(4), None, None, true, &history);
+    assert_eq!(result.score, Score::DRAW);
+    assert_eq!(result.pv[0], draw);
+
+    search.set_must_win(true);
+    let result = search.search(&position, Some(4), None, None, true, &history);
+    assert_ne!(result.pv[0], draw);
+    assert!(result.score < Score::DRAW && !result.score.is_losing());
+}
+
+#[test]
+fn test_search_root_moves() {
+    let (position, history) = start_position();
+
+    let mut search = Search::new(&small_tables(), &Arc::new(DefaultEvaluator::default()));
+    let root_moves = [
+        Move::from_str("Aa3-c5").unwrap(),
+        Move::from_str("Aa4-c6").unwrap(),
+    ];
+    for len in 1..=root_moves.len() {
+        let result = search
+            .search_root_moves(
+                &position,
+                &root_moves[..len],
+                Some(300),
+                None,
+                None,
+                true,
+                &history,
+            )
+            .unwrap();
+        assert!(root_moves[..len].contains(&result.pv[0]));
+        assert_eq!(result.num_root_moves, len);
+    }
+
+    assert!(search
+        .search_root_moves(&position, &[], Some(300), None, None, true, &history)
+        .is_err());
+    let illegal = [Move::from_str("Aa3-c4").unwrap()];
+    assert!(search
+        .search_root_moves(&position, &illegal, Some(300), None, None, true, &history)
+        .is_err());
+
+    // Red is in check by the blue wazir, so the alfil jump is pseudo-legal but not legal.
+    let position = Position::from_str(
+        "\
+regular
+4
+Aff
+FWwA.D.D
+AF.AD.DA
+..A.a.A.
+....A.A.
+...a..ad
+..d..nN.
+a.a...a.
+add.....
+",
+    )
+    .unwrap();
+    let history = History::new_from_position(&position);
+    let non_evasion = Move::from_str("Aa4-c2").unwrap();
+    assert!(movegen::is_pseudo_legal(&position, non_evasion));
+    assert!(search
+        .search_root_moves(
+            &position,
+            &[non_evasion],
+            Some(300),
+            None,
+            None,
+            true,
+            &history
+        )
+        .is_err());
+}
+
+#[test]
+fn test_search_until_stable() {
+    let (position, history) = start_position();
+
+    let mut search = Search::new(&small_tables(), &Arc::new(DefaultEvaluator::default()));
+    // Any change is within a huge margin, so one iteration after the shallow search suffices.
+    let result = search.search_until_stable(&position, Eval::MAX, 600, None, &history);
+    assert_eq!(result.depth, 200);
+    // Nothing is within a zero margin, so it searches to the maximum depth.
+    let result = search.search_until_stable(&position, 0, 400, None, &history);
+    assert_eq!(result.depth, 400);
+}
+
+#[test]
+fn test_search_killer_counts() {
+    let (position, history) = start_position();
+
+    let hyperparameters = small_tables();
+    let evaluator = Arc::new(DefaultEvaluator::default());
+    let mut search = Search::<_, 3>::new_with_killers(&hyperparameters, &evaluator);
+    let result = search.search(&position, Some(400), None, None, false, &history);
+    assert_eq!(result.depth, 400);
+    assert!(!result.pv.moves.is_empty());
+
+    let ttable = Arc::new(TTable::new(hyperparameters.ttable_size));
+    let mut search = Search::<_, 1>::with_ttable(&hyperparameters, &evaluator, &ttable);
+    let result = search.search(&position, Some(400), None, None, false, &history);
+    assert_eq!(result.depth, 400);
+}
+
+#[test]
+fn test_analyze() {
+    let (position, history) = start_position();
+
+    let evaluator = Arc::new(DefaultEvaluator::default());
+    let mut search = Search::new(&small_tables(), &evaluator);
+    let results: Vec<_> = search
+        .analyze(&position, Some(400), None, &history)
+        .collect();
+    let depths: Vec<_> = results.iter().map(|result| result.depth).collect();
+    assert_eq!(depths, [100, 200, 300, 400]);
+
+    // Same as a blocking search with a fresh hash table.
+    let mut search2 = Search::new(&small_tables(), &evaluator);
+    let result = search2.search(&position, Some(400), None, None, true, &history);
+    let last = results.last().unwrap();
+    assert_eq!(last.score, result.score);
+    assert_eq!(last.pv.to_string(), result.pv.to_string());
+
+    // The caller can stop early.
+    assert_eq!(
+        search
+            .analyze(&position, None, None, &history)
+            .take(2)
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_analyze_with_threat() {
+    let (position, mut history) = start_position();
+
+    let evaluator = Arc::new(DefaultEvaluator::default());
+    let mut search = Search::new(&small_tables(), &evaluator);
+    let (best, threat) = search.analyze_with_threat(&position, Some(300), None, &history);
+    let mov = best.pv[0];
+
+    let position2 = position.make_move(mov).unwrap();
+    history.push_position(&position2);
+    let mut search2 = Search::new(&small_tables(), &evaluator);
+    let result = search2.search(&position2, Some(300), None, None, true, &history);
+    assert_eq!(threat.score, result.score);
+    assert!(!threat.pv.is_empty());
+    assert_eq!(threat.depth, 300);
+
+    // Game over.
+    let end = Position::from_str(
+        "\
+end draw
+102
+AFf
+.W.A.D.D
+AaFA.DDA
+n.A.A.A.
+......A.
+...a.a.d
+..d...N.
+a.a...f.
+add.w..a
+",
+    )
+    .unwrap();
+    let (best, threat) =
+        search.analyze_with_threat(&end, Some(300), None, &History::new_from_position(&end));
+    assert!(best.pv.is_empty());
+    assert!(threat.pv.is_empty());
+}
+
+#[test]
+fn test_timeout_stats() {
+    let (position, history) = start_position();
+    let evaluator = Arc::new(DefaultEvaluator::default());
+    let mut search = Search::new(&small_tables(), &evaluator);
+
+    let result = search.search(&position, Some(200), None, None, true, &history);
+    assert_eq!(result.timeouts, TimeoutStats::default());
+    assert_eq!(result.timeouts.to_string(), "none");
+    assert!(result.completed);
+    assert_eq!(result.aborted_reason, None);
+
+    let now = Instant::now();
+    let deadlines = Deadlines {
+        hard: now,
+        soft: now,
+        start_next_depth: now,
+        panic_hard: now,
+        panic_soft: now,
+    };
+    let result = search.search(&position, None, Some(deadlines), None, true, &history);
+    assert_eq!(result.timeouts.shallow_soft, 1);
+    assert_eq!(result.timeouts.total(), 1);
+    assert_eq!(result.timeouts.to_string(), "ssto=1");
+    assert!(!result.completed);
+    assert_eq!(result.aborted_reason, Some(TimeoutKind::ShallowSoft));
+
+    // The first depth finishes, but the next one isn't started.
+    let later = now + Duration::from_secs(1000);
+    let deadlines = Deadlines {
+        hard: later,
+        soft: later,
+        start_next_depth: now,
+        panic_hard: later,
+        panic_soft: later,
+    };
+    let result = search.search(&position, None, Some(deadlines), None, true, &history);
+    assert!(result.completed);
+    assert_eq!(result.depth, 100);
+    assert_eq!(result.aborted_reason, Some(TimeoutKind::NextDepth));
+}
+
+#[test]
+fn test_timeout_stats_display() {
+    let timeouts = TimeoutStats {
+        next_depth: 1,
+        shallow_soft: 0,
+        soft: 2,
+        hard: 3,
+        stopped: 0,
+    };
+    assert_eq!(timeouts.to_string(), "ndto=1,sto=2,hto=3");
+    assert_eq!(timeouts.total(), 6);
+    let stopped = TimeoutStats {
+        stopped: 1,
+        ..TimeoutStats::default()
+    };
+    assert_eq!(stopped.to_string(), "stop=1");
+}
+
+#[test]
+fn test_probe_tt() {
+    let (position, history) = start_position();
+
+    let hyperparameters = small_tables();
+    let mut search = Search::new(&hyperparameters, &Arc::new(DefaultEvaluator::default()));
+    assert_eq!(search.probe_tt(&position), None);
+
+    let result = search.search(&position, Some(400), None, None, true, &history);
+    let child = position.make_move(result.pv[0]).unwrap();
+    let entry = search.probe_tt(&child).unwrap();
+    assert!(entry.depth < result.depth);
+    assert!(entry.mov.is_some());
+    // Probing doesn't change the entry.
+    assert_eq!(search.probe_tt(&child), Some(entry));
+}
+
+#[test]
+fn test_search_excluding_moves() {
+    let hyperparameters = small_tables();
+    let mut search = Search::new(&hyperparameters, &Arc::new(DefaultEvaluator::default()));
+
+    let (position, history) = start_position();
+    let best = search.search(&position, Some(300), None, None, true, &history);
+    let second = search
+        .search_excluding_moves(
+            &position,
+            &[best.pv[0]],
+            Some(300),
+            None,
+            None,
+            true,
+            &history,
+        )
+        .unwrap();
+    assert_ne!(second.pv[0], best.pv[0]);
+    assert!(second.score <= best.score);
+    assert!(second.top_moves.iter().all(|m| m.mov != best.pv[0]));
+
+    let all_moves: Vec<Move> = movegen::pseudomoves(&position).collect();
+    assert!(search
+        .search_excluding_moves(&position, &all_moves, Some(300), None, None, true, &history)
+        .is_err());
+
+    // Other captures of the wazir are still found.
+    let position = Position::from_str(
+        "\
+regular
+4
+Af
+FW.A.D.D
+AfFA.DDA
+..A.A.A.
+.....wA.
+...a..ad
+..d..nN.
+a.a...a.
+add....a
+",
+    )
+    .unwrap();
+    let history = history_for(&position);
+    let excluded = Move::from_str("Ab4xwd6").unwrap();
+    let result = search
+        .search_excluding_moves(
+            &position,
+            &[excluded],
+            Some(300),
+            None,
+            None,
+            true,
+            &history,
+        )
+        .unwrap();
+    assert_ne!(result.pv[0], excluded);
+    assert_eq!(result.pv[0].captured, Some(Piece::Wazir));
+    assert!(result.score > Score::DRAW);
+}
+
+#[test]
+fn test_branching_factor() {
+    let hyperparameters = small_tables();
+    let mut search = Search::new(&hyperparameters, &Arc::new(DefaultEvaluator::default()));
+    let (position, history) = start_position();
+
+    let result = search.search(&position, Some(400), None, None, true, &history);
+    assert_eq!(result.depth, 400);
+    let branching_factor = result.branching_factor.unwrap();
+    assert!(branching_factor > 1.0);
+    assert!((branching_factor.powi(4) - result.nodes as f64).abs() < 1e-6 * result.nodes as f64);
+
+    // A forced wazir capture isn't searched.
+    let position = Position::from_str(
+        "\
+regular
+4
+Af
+FW.A.D.D
+AfFA.DDA
+..A.A.A.
+.....wA.
+...a..ad
+..d..nN.
+a.a...a.
+add....a
+",
+    )
+    .unwrap();
+    let history = history_for(&position);
+    let result = search.search(&position, Some(400), None, None, true, &history);
+    assert_eq!(result.branching_factor, None);
+}
+
+#[test]
+fn test_stop_flag() {
+    let hyperparameters = small_tables();
+    let mut search = Search::new(&hyperparameters, &Arc::new(DefaultEvaluator::default()));
+    let (position, history) = start_position();
+
+    let stop = Arc::new(AtomicBool::new(true));
+    search.set_stop_flag(Some(Arc::clone(&stop)));
+    let result = search.search(&position, None, None, None, false, &history);
+    assert_eq!(result.aborted_reason, Some(TimeoutKind::Stopped));
+    assert!(!result.pv.is_empty());
+
+    let other: Search<DefaultEvaluator> = Search::with_ttable(
+        &hyperparameters,
+        &Arc::new(DefaultEvaluator::default()),
+        search.ttable(),
+    );
+    assert!(Arc::ptr_eq(other.ttable(), search.ttable()));
+
+    stop.store(false, Ordering::Relaxed);
+    let result = search.search(&position, Some(200), None, None, false, &history);
+    assert_eq!(result.aborted_reason, None);
+    assert_eq!(result.depth, 200);
+}
+
+#[test]
+fn test_null_verify() {
+    let search_with = |position: &Position, history: &History, depth, null_verify_depth| {
+        let hyperparameters = Hyperparameters {
+            null_verify_depth,
+            ..small_tables()
+        };
+        let mut search: Search<DefaultEvaluator> =
+            Search::new(&hyperparameters, &Arc::new(DefaultEvaluator::default()));
+        search.search(position, Some(depth), None, None, true, history)
+    };
+
+    let (position, history) = start_position();
+    let naive = search_with(&position, &history, 600, None);
+    // Above the search depth, verification never triggers.
+    let unverified = search_with(&position, &history, 600, Some(1000));
+    assert_eq!(unverified.score, naive.score);
+    assert_eq!(unverified.nodes, naive.nodes);
+
+    // Verifying every null move cutoff costs extra nodes.
+    let verified = search_with(&position, &history, 600, Some(300));
+    assert_eq!(verified.depth, 600);
+    assert!(!verified.pv.moves.is_empty());
+    assert!(verified.nodes > naive.nodes);
+
+    // Zugzwang: after N@f6 attacks g8 and h7, every blue move is a suicide, but passing would be
+    // safe. The null move cutoff hides the win unless it is verified.
+    let position =
+        Position::from_fen("regular 40 AAAAAAAAAAAAAAAADDDDDDDDFFFFNN 8/5W2/8/8/8/8/8/7w").unwrap();
+    let history = history_for(&position);
+    let zugzwang = Move::from_str("N@f6").unwrap();
+    let naive = search_with(&position, &history, 400, None);
+    assert_ne!(naive.pv[0], zugzwang);
+    assert!(!naive.score.is_winning());
+    let verified = search_with(&position, &history, 400, Some(300));
+    assert_eq!(verified.pv[0], zugzwang);
+    assert!(verified.score.is_winning());
+}
+
+#[test]
+fn test_memory_budget() {
+    let budget = 17 << 20;
+    let hyperparameters = Hyperparameters::from_memory_budget(budget);
+    assert!(hyperparameters.ttable_size > 15 << 20);
+    assert!(hyperparameters.pvtable_size < 1 << 20);
+    assert_eq!(hyperparameters.eval_cache_size, 0);
+
+    for eval_cache_size in [0, 4 << 20] {
+        let hyperparameters = Hyperparameters {
+            eval_cache_size,
+            ..Hyperparameters::default()
+        }
+        .with_memory_budget(budget);
+        assert_eq!(hyperparameters.eval_cache_size > 0, eval_cache_size > 0);
+        let search = Search::new(&hyperparameters, &Arc::new(DefaultEvaluator::default()));
+        assert!(search.memory_bytes() <= budget);
+        assert!(search.memory_bytes() > budget / 4);
+    }
+
+    // Helpers have their own PV tables and eval caches, so they come out of the same budget.
+    let hyperparameters = Hyperparameters {
+        eval_cache_size: 4 << 20,
+        search_threads: 4,
+        ..Hyperparameters::default()
+    }
+    .with_memory_budget(budget);
+    let search = Search::new(&hyperparameters, &Arc::new(DefaultEvaluator::default()));
+    assert!(search.memory_bytes() <= budget);
+    assert!(search.memory_bytes() > budget / 4);
+}
+
+#[test]
+fn test_cumulative_stats() {
+    let (position, history) = start_position();
+    let mut search = Search::new(&small_tables(), &Arc::new(DefaultEvaluator::default()));
+    assert_eq!(search.cumulative_stats(), CumulativeStats::default());
+
+    let result1 = search.search(&position, Some(200), None, None, true, &history);
+    let result2 = search.search(&position, Some(300), None, None, true, &history);
+    let stats = search.cumulative_stats();
+    assert_eq!(stats.searches, 2);
+    assert_eq!(stats.nodes, result1.nodes + result2.nodes);
+    assert!(stats.nodes_per_second().is_some());
+
+    search.reset_cumulative_stats();
+    assert_eq!(search.cumulative_stats(), CumulativeStats::default());
+    assert_eq!(search.cumulative_stats().nodes_per_second(), None);
+}
+
+#[test]
+fn test_draw_blend_plies() {
+    // Red is far ahead in material, with no quick win.
+    let position = Position::from_str(
+        "\
+regular
+20
+AAAAAAADDDDFN
+WNAADADA
+AFFAADDA
+........
+........
+........
+........
+..f.....
+.w..a...
+",
+    )
+    .unwrap();
+    let evaluator = Arc::new(DefaultEvaluator::default());
+    let score = |draw_blend_plies: Option<Ply>, ply_draw: Ply| {
+        let position = position.with_ply_draw(ply_draw).unwrap();
+        let hyperparameters = Hyperparameters {
+            draw_blend_plies,
+            ..small_tables()
+        };
+        let mut search = Search::new(&hyperparameters, &evaluator);
+        let history = history_for(&position);
+        search
+            .search(&position, Some(200), None, None, true, &history)
+            .score
+    };
+
+    // Without blending, the eval doesn't change until the limit is within reach.
+    let far = score(None, PLY_DRAW);
+    assert!(far > Score::DRAW);
+    assert_eq!(score(None, 30), far);
+
+    // With blending, it goes down gradually as the limit approaches.
+    assert_eq!(score(Some(20), PLY_DRAW), far);
+    let blended: Vec<Score> = [36, 30, 25]
+        .into_iter()
+        .map(|ply_draw| score(Some(20), ply_draw))
+        .collect();
+    assert!(far > blended[0]);
+    assert!(blended.windows(2).all(|w| w[0] > w[1]));
+    assert!(blended[2] > Score::DRAW);
+}
+
+#[test]
+fn test_draw_blend_progress() {
+    // Red is winning with the move limit 16 plies away.
+    let position =
+        Position::from_fen("regular 40/56 AAAAAAADDFFFN 3N2A1/1A1A4/7W/8/1D1ADA2/6ad/1AD3ad/F4adw")
+            .unwrap();
+    let evaluator = Arc::new(DefaultEvaluator::default());
+    // Both sides play at a fixed depth until the game ends.
+    let play = |draw_blend_plies: Option<Ply>| {
+        let hyperparameters = Hyperparameters {
+            draw_blend_plies,
+            ..small_tables()
+        };
+        let mut search = Search::new(&hyperparameters, &evaluator);
+        let mut position = position;
+        let mut history = history_for(&position);
+        while position.stage() == Stage::Regular {
+            let result = search.search(&position, Some(300), None, None, true, &history);
+            position = position.make_move(result.pv[0]).unwrap();
+            history.push_position_irreversible(&position);
+        }
+        position
+    };
+
+    // Without blending, red stalls until the limit draws the game.
+    let stalled = play(None);
+    assert_eq!(stalled.stage(), Stage::End(Outcome::Draw));
+    assert_eq!(stalled.ply(), stalled.ply_draw());
+
+    // With blending, red makes progress and wins.
+    let blended = play(Some(20));
+    assert_eq!(blended.stage(), Stage::End(Outcome::RedWin));
+    assert!(blended.ply() < blended.ply_draw());
+}
+
+#[test]
+fn test_top_moves_after_interrupted_depth() {
+    let hyperparameters = small_tables();
+    let mut search = Search::new(&hyperparameters, &Arc::new(DefaultEvaluator::default()));
+    let (position, history) = start_position();
+    // Keeps deepening until the soft deadline interrupts a depth after its first move.
+    let now = Instant::now();
+    let soft = now + Duration::from_millis(200);
+    let later = now + Duration::from_secs(100);
+    let deadlines = Deadlines {
+        hard: later,
+        soft,
+        start_next_depth: later,
+        panic_hard: later,
+        panic_soft: soft,
+    };
+    let threshold = Eval::MAX / 2;
+    let result = search.search(
+        &position,
+        None,
+        Some(deadlines),
+        Some(threshold),
+        false,
+        &history,
+    );
+    assert!(!result.completed);
+    assert!(result.root_moves_considered < result.num_root_moves);
+    // Every move is within the threshold at the last completed depth.
+    let complete = search.search(
+        &position,
+        Some(ONE_PLY),
+        None,
+        Some(threshold),
+        false,
+        &history,
+    );
+    assert_eq!(result.top_moves.len(), complete.top_moves.len());
+}
+
+#[test]
+fn test_search_eval_cache() {
+    let (position, history) = start_position();
+    let evaluator = Arc::new(DefaultEvaluator::default());
+    let search_with = |eval_cache_size| {
+        let hyperparameters = Hyperparameters {
+            eval_cache_size,
+            ..small_tables()
+        };
+        let mut search = Search::new(&hyperparameters, &evaluator);
+        search.search(&position, Some(400), None, None, false, &history)
+    };
+    let uncached = search_with(0);
+    assert_eq!(uncached.eval_cache_stats, EvalCacheStats::default());
+    let cached = search_with(1 << 16);
+    assert!(cached.eval_cache_stats.hits > 0);
+    // Cached evals are the same, so the search is too.
+    assert_eq!(cached.score, uncached.score);
+    assert_eq!(cached.pv.to_string(), uncached.pv.to_string());
+    assert_eq!(cached.nodes, uncached.nodes);
+}
+
+#[test]
+fn test_aspiration_window() {
+    let (position, history) = start_position();
+    let evaluator = Arc::new(DefaultEvaluator::default());
+    let search_with = |aspiration_window| {
+        let hyperparameters = Hyperparameters {
+            aspiration_window,
+            aspiration_min_depth: 2 * ONE_PLY,
+            ..small_tables()
+        };
+        let mut search = Search::new(&hyperparameters, &evaluator);
+        search.search(&position, Some(600), None, None, false, &history)
+    };
+    let full = search_with(None);
+    assert_eq!(full.aspiration_researches, 0);
+    // A window this narrow fails at almost every depth, and the re-searches still finish.
+    let narrow = search_with(Some(1e-9));
+    assert!(narrow.aspiration_researches > 0);
+    assert_eq!(narrow.depth, full.depth);
+    assert!(narrow.completed);
+    assert!(!narrow.pv.moves.is_empty());
+}
+
+#[test]
+fn test_search_threads() {
+    let (position, history) = start_position();
+    let evaluator = Arc::new(DefaultEvaluator::default());
+    let hyperparameters = small_tables();
+    let mut single = Search::new(&hyperparameters, &evaluator);
+    let single_memory = single.memory_bytes();
+    let single = single.search(&position, Some(500), None, None, false, &history);
+    assert_eq!(single.helper_nodes, 0);
+
+    let hyperparameters = Hyperparameters {
+        search_threads: 3,
+        ..hyperparameters
+    };
+    let mut search = Search::new(&hyperparameters, &evaluator);
+    assert!(search.memory_bytes() > single_memory);
+    for _ in 0..2 {
+        let result = search.search(&position, Some(500), None, None, false, &history);
+        assert_eq!(result.depth, 500);
+        assert!(result.completed);
+        assert!(!result.pv.moves.is_empty());
+    }
+
+    // Helpers without a depth limit stop when the main search does.
+    search.set_stop_flag(Some(Arc::new(AtomicBool::new(true))));
+    let result = search.search(&position, None, None, None, false, &history);
+    assert_eq!(result.aborted_reason, Some(TimeoutKind::Stopped));
+    assert!(!result.pv.is_empty());
+}
+
+#[test]
+fn test_score_all_blue_setups() {
+    let hyperparameters = small_tables();
+    let mut search = Search::new(&hyperparameters, &Arc::new(DefaultEvaluator::default()));
+    let scores = search.score_all_blue_setups(book::red_setup(), 2 * ONE_PLY);
+    let candidates = book::blue_setup_moves();
+    assert_eq!(scores.len(), candidates.len());
+    for mov in candidates {
+        assert!(scores.iter().any(|&(blue, _)| blue == mov));
+    }
+    assert!(scores.windows(2).all(|w| w[0].1 >= w[1].1));
+}
@@ -1,5 +1,5 @@
 use std::str::FromStr;
-use wazir_drop::{Direction, Square};
+use wazir_drop::{enums::SimpleEnumExt, Coord, Direction, Square};
 
 #[test]
 fn test_display() {
@@ -31,3 +31,32 @@ fn test_add() {
     assert!(Square::A5.add(Direction::new(-1, -1)).is_none());
     assert!(Square::H5.add(Direction::new(-1, 2)).is_none());
 }
+
+#[test]
+fn test_offset() {
+    assert_eq!(Square::A5.offset(-1, 2), Some(Square::C4));
+    assert!(Square::A5.offset(-1, -1).is_none());
+    assert!(Square::H5.offset(-1, 2).is_none());
+    assert_eq!(Coord::new(0, 0).offset(7, 7), Some(Coord::new(7, 7)));
+    assert!(Coord::new(0, 0).offset(8, 0).is_none());
+    assert!(Coord::new(0, 0).offset(isize::MIN, 0).is_none());
+}
+
+#[test]
+fn test_coord_try_new() {
+    assert_eq!(Coord::try_new(7, 0), Some(Coord::new(7, 0)));
+    assert!(Coord::try_new(8, 0).is_none());
+    assert!(Coord::try_new(0, 8).is_none());
+}
+
+#[test]
+fn test_coord_iteration() {
+    assert!(Coord::all().map(Square::from).eq(Square::all()));
+    assert_eq!(
+        Coord::row(2).map(Coord::x).collect::<Vec<_>>(),
+        (0..Coord::WIDTH).collect::<Vec<_>>()
+    );
+    assert!(Coord::row(2).all(|coord| coord.y() == 2));
+    assert_eq!(Coord::column(3).count(), Coord::HEIGHT);
+    assert!(Coord::column(3).all(|coord| coord.x() == 3));
+}
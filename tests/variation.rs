@@ -1,6 +1,7 @@
 use std::str::FromStr;
 use wazir_drop::{
-    constants::MAX_VARIATION_LENGTH, ExtendableVariation, LongVariation, Move, Variation,
+    constants::MAX_VARIATION_LENGTH, AnyMove, ExtendableVariation, LongVariation, Move, Position,
+    Variation,
 };
 
 #[test]
@@ -17,3 +18,32 @@ fn test_long_variation() {
     assert!(variation.truncated);
     assert_eq!(variation.len(), MAX_VARIATION_LENGTH);
 }
+
+#[test]
+fn test_truncate_illegal() {
+    let setup: Vec<AnyMove> = ["WNAADADAFFAADDAA", "wnaadadaffaaddaa"]
+        .iter()
+        .map(|s| AnyMove::from_str(s).unwrap())
+        .collect();
+    let position = Position::replay(&setup).unwrap();
+
+    let variation = |moves: &[&str]| {
+        moves
+            .iter()
+            .rev()
+            .fold(LongVariation::empty(), |variation, s| {
+                variation.add_front(Move::from_str(s).unwrap())
+            })
+    };
+
+    let mut pv = variation(&["Aa3-c5", "ag3-e5"]);
+    pv.truncate_illegal(&position);
+    assert_eq!(pv.to_string(), "Aa3-c5 ag3-e5");
+    assert!(!pv.truncated);
+
+    // The second move is as if from a colliding transposition table entry.
+    let mut pv = variation(&["Aa3-c5", "Aa4-c6", "ag3-e5"]);
+    pv.truncate_illegal(&position);
+    assert_eq!(pv.to_string(), "Aa3-c5 (trunc)");
+    assert!(pv.truncated);
+}
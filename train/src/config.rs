@@ -5,4 +5,6 @@ use serde::Deserialize;
 pub enum FeaturesConfig {
     PS,
     WPS,
+    /// `WPS` plus `WazirSafetyFeatures`.
+    WPSSafety,
 }
@@ -1,14 +1,23 @@
 use crate::config::FeaturesConfig;
-use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
-use serde::{Deserialize, Serialize};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::{
     error::Error,
     fs::File,
-    io::{BufReader, BufWriter},
+    io::{BufReader, BufWriter, Read, Seek, Write},
     path::{Path, PathBuf},
 };
 use tch::{Device, Kind, Tensor};
 
+/// Starts a versioned dataset file.
+/// Can't be the start of an unversioned file: it would decode as a huge number of features.
+const DATASET_MAGIC: [u8; 4] = [0xFF, b'W', b'D', b'S'];
+
+/// 0: unversioned, no header.
+/// 1: added `Sample::policy`.
+/// 2: added `Sample::book_opening`.
+const DATASET_VERSION: u32 = 2;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Sample {
     /// [to move, other]
@@ -17,6 +26,61 @@ pub struct Sample {
     pub deep_value: f32,
     /// +1 = win, -1 = loss
     pub game_points: i32,
+    /// Root move scores, for policy learning.
+    pub policy: Option<Policy>,
+    /// The game started from a book opening rather than random setups.
+    pub book_opening: bool,
+}
+
+/// Search results at the root of a self-play search.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Policy {
+    /// [to move, other] features of the root position.
+    pub features: [Vec<u16>; 2],
+    /// Every root move with an eval score, in text notation with the eval, best first. Moves
+    /// that force a win or a loss are left out.
+    pub moves: Vec<(String, i32)>,
+}
+
+/// `Sample` in dataset version 0.
+#[derive(Deserialize)]
+struct SampleV0 {
+    features: [Vec<u16>; 2],
+    deep_value: f32,
+    game_points: i32,
+}
+
+impl From<SampleV0> for Sample {
+    fn from(sample: SampleV0) -> Self {
+        Self {
+            features: sample.features,
+            deep_value: sample.deep_value,
+            game_points: sample.game_points,
+            policy: None,
+            book_opening: false,
+        }
+    }
+}
+
+/// `Sample` in dataset version 1.
+#[derive(Deserialize)]
+struct SampleV1 {
+    features: [Vec<u16>; 2],
+    deep_value: f32,
+    game_points: i32,
+    policy: Option<Policy>,
+}
+
+impl From<SampleV1> for Sample {
+    fn from(sample: SampleV1) -> Self {
+        Self {
+            features: sample.features,
+            deep_value: sample.deep_value,
+            game_points: sample.game_points,
+            policy: sample.policy,
+            book_opening: false,
+        }
+    }
 }
 
 /// A batch of data.
@@ -33,11 +97,33 @@ pub struct Batch {
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct DatasetConfig {
-    file: PathBuf,
+    /// Read one after another, without loading them into memory. A single path is also
+    /// accepted, under the old name `file`.
+    #[serde(alias = "file", deserialize_with = "one_or_many_paths")]
+    files: Vec<PathBuf>,
     pub features: FeaturesConfig,
-    chunk_size: usize,
+    /// Number of samples held for shuffling. Each sample is drawn at random from the buffer,
+    /// which is then refilled from the files.
+    #[serde(alias = "chunk_size")]
+    shuffle_buffer_size: usize,
     batch_size: usize,
     outcome_weight: f32,
+    /// Makes the order reproducible. Random if not set.
+    seed: Option<u64>,
+}
+
+fn one_or_many_paths<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<PathBuf>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(PathBuf),
+        Many(Vec<PathBuf>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(path) => vec![path],
+        OneOrMany::Many(paths) => paths,
+    })
 }
 
 impl Batch {
@@ -83,58 +169,135 @@ impl Batch {
     }
 }
 
+/// Streams batches from the dataset files through a bounded shuffle buffer.
 pub struct DatasetIterator {
-    reader: BufReader<File>,
-    buffer: Vec<u8>,
+    files: Vec<PathBuf>,
+    next_file: usize,
+    reader: Option<DatasetReader>,
     outcome_weight: f32,
-    chunk_size: usize,
+    shuffle_buffer_size: usize,
     batch_size: usize,
     rng: StdRng,
-    current_chunk: Vec<Sample>,
-    current_chunk_index: usize,
+    shuffle_buffer: Vec<Sample>,
 }
 
 impl DatasetIterator {
-    pub fn new(config: &DatasetConfig) -> Result<Self, Box<dyn Error>> {
-        let reader = BufReader::new(File::open(&config.file)?);
+    /// With a seed, each `epoch` gets a different but reproducible order.
+    pub fn new(config: &DatasetConfig, epoch: u64) -> Result<Self, Box<dyn Error>> {
+        if config.files.is_empty() {
+            return Err("No dataset files".into());
+        }
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(epoch)),
+            None => StdRng::from_os_rng(),
+        };
         Ok(Self {
-            reader,
-            buffer: vec![0; 1 << 10],
+            files: config.files.clone(),
+            next_file: 0,
+            reader: None,
             outcome_weight: config.outcome_weight,
-            chunk_size: config.chunk_size,
+            shuffle_buffer_size: config.shuffle_buffer_size,
             batch_size: config.batch_size,
-            rng: StdRng::from_os_rng(),
-            current_chunk: Vec::with_capacity(config.chunk_size),
-            current_chunk_index: 0,
+            rng,
+            shuffle_buffer: Vec::with_capacity(config.shuffle_buffer_size),
         })
     }
 
     pub fn next_batch(&mut self) -> Result<Option<Batch>, Box<dyn Error>> {
-        if self.current_chunk_index == self.current_chunk.len() {
-            self.refill_chunk()?;
-            if self.current_chunk_index == self.current_chunk.len() {
-                return Ok(None);
-            }
+        let mut samples = Vec::with_capacity(self.batch_size);
+        while samples.len() < self.batch_size {
+            let Some(sample) = self.next_sample()? else {
+                break;
+            };
+            samples.push(sample);
+        }
+        if samples.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Batch::from_samples(&samples, self.outcome_weight)))
+    }
+
+    fn next_sample(&mut self) -> Result<Option<Sample>, Box<dyn Error>> {
+        while self.shuffle_buffer.len() < self.shuffle_buffer_size {
+            let Some(sample) = self.read_sample()? else {
+                break;
+            };
+            self.shuffle_buffer.push(sample);
         }
-        let next_chunk_index =
-            (self.current_chunk_index + self.batch_size).min(self.current_chunk.len());
-        let samples = &self.current_chunk[self.current_chunk_index..next_chunk_index];
-        self.current_chunk_index = next_chunk_index;
-        Ok(Some(Batch::from_samples(samples, self.outcome_weight)))
+        if self.shuffle_buffer.is_empty() {
+            return Ok(None);
+        }
+        let index = self.rng.random_range(0..self.shuffle_buffer.len());
+        Ok(Some(self.shuffle_buffer.swap_remove(index)))
     }
 
-    fn refill_chunk(&mut self) -> Result<(), Box<dyn Error>> {
-        self.current_chunk.clear();
-        self.current_chunk_index = 0;
-        while self.current_chunk.len() < self.chunk_size {
-            match postcard::from_io((&mut self.reader, &mut self.buffer)) {
-                Ok((sample, _)) => self.current_chunk.push(sample),
-                Err(postcard::Error::DeserializeUnexpectedEnd) => break,
-                Err(e) => return Err(e.into()),
+    /// The next sample in file order.
+    fn read_sample(&mut self) -> Result<Option<Sample>, Box<dyn Error>> {
+        loop {
+            let reader = match &mut self.reader {
+                Some(reader) => reader,
+                None => {
+                    let Some(path) = self.files.get(self.next_file) else {
+                        return Ok(None);
+                    };
+                    self.next_file += 1;
+                    self.reader.insert(DatasetReader::open(path)?)
+                }
+            };
+            match reader.read_sample()? {
+                Some(sample) => return Ok(Some(sample)),
+                None => self.reader = None,
             }
         }
-        self.current_chunk.shuffle(&mut self.rng);
-        Ok(())
+    }
+}
+
+/// Reads samples from a single dataset file.
+struct DatasetReader {
+    reader: BufReader<File>,
+    version: u32,
+    buffer: Vec<u8>,
+}
+
+impl DatasetReader {
+    fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let version = Self::read_version(&mut reader)?;
+        Ok(Self {
+            reader,
+            version,
+            buffer: vec![0; 1 << 10],
+        })
+    }
+
+    fn read_version(reader: &mut BufReader<File>) -> Result<u32, Box<dyn Error>> {
+        let mut magic = [0; 4];
+        if reader.read_exact(&mut magic).is_err() || magic != DATASET_MAGIC {
+            reader.rewind()?;
+            return Ok(0);
+        }
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version > DATASET_VERSION {
+            return Err(format!("Unsupported dataset version {version}").into());
+        }
+        Ok(version)
+    }
+
+    /// `None` at the end of the file.
+    fn read_sample(&mut self) -> Result<Option<Sample>, Box<dyn Error>> {
+        let input = (&mut self.reader, self.buffer.as_mut_slice());
+        let sample: Result<Sample, postcard::Error> = match self.version {
+            0 => postcard::from_io::<SampleV0, _>(input).map(|(sample, _)| sample.into()),
+            1 => postcard::from_io::<SampleV1, _>(input).map(|(sample, _)| sample.into()),
+            _ => postcard::from_io(input).map(|(sample, _)| sample),
+        };
+        match sample {
+            Ok(sample) => Ok(Some(sample)),
+            Err(postcard::Error::DeserializeUnexpectedEnd) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
     }
 }
 
@@ -144,7 +307,9 @@ pub struct DatasetWriter {
 
 impl DatasetWriter {
     pub fn new(filename: &Path) -> Result<Self, Box<dyn Error>> {
-        let writer = BufWriter::new(File::create(filename)?);
+        let mut writer = BufWriter::new(File::create(filename)?);
+        writer.write_all(&DATASET_MAGIC)?;
+        writer.write_all(&DATASET_VERSION.to_le_bytes())?;
         Ok(Self { writer })
     }
 
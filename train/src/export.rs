@@ -31,6 +31,8 @@ pub fn run(config: &Config) -> Result<(), Box<dyn Error>> {
     match config.features {
         FeaturesConfig::PS => run_with_features(PSFeatures, config),
         FeaturesConfig::WPS => run_with_features(WPSFeatures, config),
+        // The engine's evaluators only read `PS` and `WPS` weights.
+        FeaturesConfig::WPSSafety => Err("WPSSafety features can't be exported".into()),
     }
 }
 
@@ -5,7 +5,7 @@ use crate::{
     model::EvalModel,
     nnue::{self, NnueModel},
 };
-use extra::PSFeatures;
+use extra::{PSFeatures, WazirSafetyFeatures};
 use serde::Deserialize;
 use std::{error::Error, path::PathBuf, time::Instant};
 use tch::{
@@ -42,6 +42,9 @@ pub fn run(config: &Config) -> Result<(), Box<dyn Error>> {
     match config.dataset.features {
         FeaturesConfig::PS => run_with_features(PSFeatures, config),
         FeaturesConfig::WPS => run_with_features(WPSFeatures, config),
+        FeaturesConfig::WPSSafety => {
+            run_with_features(WazirSafetyFeatures::new(WPSFeatures), config)
+        }
     }
 }
 
@@ -81,7 +84,7 @@ fn run_with_model<M: EvalModel>(
         let start_time = Instant::now();
         let mut last_log_time = start_time;
 
-        let mut dataset_iterator = DatasetIterator::new(&config.dataset)?;
+        let mut dataset_iterator = DatasetIterator::new(&config.dataset, epoch.into())?;
         loop {
             let batch = dataset_iterator.next_batch()?;
             if batch.is_none() || last_log_time.elapsed().as_secs_f64() >= config.log_period_seconds
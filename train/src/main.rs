@@ -9,9 +9,8 @@ mod self_play;
 mod validate;
 
 use clap::Parser;
-use log::LevelFilter;
+use extra::logging::{self, VerbosityArgs};
 use serde::Deserialize;
-use simplelog::{ColorChoice, CombinedLogger, TermLogger, TerminalMode, WriteLogger};
 use std::{
     error::Error,
     fs::{self, File},
@@ -22,6 +21,8 @@ use std::{
 #[derive(Parser, Debug)]
 struct Args {
     config_file: PathBuf,
+    #[command(flatten)]
+    verbosity: VerbosityArgs,
 }
 
 #[derive(Deserialize)]
@@ -63,15 +64,7 @@ fn run() -> Result<(), Box<dyn Error>> {
     }
     let log_file = File::create(log_path)?;
 
-    CombinedLogger::init(vec![
-        WriteLogger::new(LevelFilter::Info, simplelog::Config::default(), log_file),
-        TermLogger::new(
-            LevelFilter::Info,
-            simplelog::Config::default(),
-            TerminalMode::Stderr,
-            ColorChoice::Auto,
-        ),
-    ])?;
+    logging::init_logger(args.verbosity.verbosity(), log_file)?;
 
     for command in &config.command {
         match command {
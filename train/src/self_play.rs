@@ -1,9 +1,9 @@
 use crate::{
     config::FeaturesConfig,
-    data::{DatasetWriter, Sample},
+    data::{DatasetWriter, Policy, Sample},
 };
-use extra::{PSFeatures, moverand};
-use rand::{SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use extra::{PSFeatures, WazirSafetyFeatures, moverand};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
 use serde::Deserialize;
 use std::{
     error::Error,
@@ -14,8 +14,9 @@ use std::{
 use threadpool::ThreadPool;
 use wazir_drop::{
     DefaultEvaluator, Evaluator, Features, History, LongVariation, Move, Outcome, Position, Score,
-    ScoreExpanded, ScoredMove, Search, Stage, WPSFeatures,
-    constants::{Depth, Eval, Hyperparameters},
+    ScoreExpanded, ScoredMove, Search, SetupMove, Stage, Symmetry, WPSFeatures, book,
+    constants::{Depth, Eval, Hyperparameters, Ply},
+    movegen,
 };
 
 #[derive(Clone, Debug, Deserialize)]
@@ -32,12 +33,42 @@ pub struct Config {
     temperature: f64,
     temperature_cutoff: f64,
     features: FeaturesConfig,
+    /// Record the scores of all root moves in samples. Slower, because every root move needs an
+    /// exact score rather than only those within `temperature_cutoff`.
+    #[serde(default)]
+    record_policy: bool,
+    /// Deepen the extra search until the score changes by less than this, up to `extra_depth`.
+    #[serde(default)]
+    stable_margin: Option<Eval>,
+    /// Ply at which games are drawn, for shorter games.
+    #[serde(default)]
+    ply_draw: Option<Ply>,
+    /// Fraction of games that start from a book opening rather than random setups.
+    #[serde(default)]
+    book_opening_fraction: f64,
+    /// Skip samples where the shallow and deep scores differ by more than this, in units of the
+    /// evaluator scale.
+    #[serde(default)]
+    max_score_disagreement: Option<f64>,
+    /// Skip samples where the side to move in the PV position is in check.
+    #[serde(default)]
+    skip_in_check: bool,
 }
 
 pub fn run(config: &Config) -> Result<(), Box<dyn Error>> {
+    if !(0.0..=1.0).contains(&config.book_opening_fraction) {
+        return Err("book_opening_fraction must be between 0 and 1".into());
+    }
+    if config
+        .max_score_disagreement
+        .is_some_and(|disagreement| disagreement.is_nan() || disagreement < 0.0)
+    {
+        return Err("max_score_disagreement must be non-negative".into());
+    }
     match config.features {
         FeaturesConfig::PS => run_games(config, PSFeatures)?,
         FeaturesConfig::WPS => run_games(config, WPSFeatures)?,
+        FeaturesConfig::WPSSafety => run_games(config, WazirSafetyFeatures::new(WPSFeatures))?,
     }
     Ok(())
 }
@@ -45,6 +76,7 @@ pub fn run(config: &Config) -> Result<(), Box<dyn Error>> {
 fn run_games<F: Features>(config: &Config, features: F) -> Result<(), Box<dyn Error>> {
     let writer = Arc::new(Mutex::new(DatasetWriter::new(&config.output)?));
     let evaluator = Arc::new(DefaultEvaluator::default());
+    let openings = Arc::new(book::openings());
     let thread_pool = ThreadPool::new(config.num_cpus);
     let stats = Arc::new(Mutex::new(Stats::new()));
     let start_time = Instant::now();
@@ -65,9 +97,10 @@ fn run_games<F: Features>(config: &Config, features: F) -> Result<(), Box<dyn Er
             let config = config.clone();
             let writer = writer.clone();
             let evaluator = evaluator.clone();
+            let openings = openings.clone();
             let stats = stats.clone();
-            thread_pool.execute(
-                move || match play_game(&config, &writer, &evaluator, features) {
+            thread_pool.execute(move || {
+                match play_game(&config, &writer, &evaluator, &openings, features) {
                     Ok(s) => {
                         let mut stats = stats.lock().unwrap();
                         stats.add(&s);
@@ -76,8 +109,8 @@ fn run_games<F: Features>(config: &Config, features: F) -> Result<(), Box<dyn Er
                         log::error!("Error playing game: {e}");
                         panic!("Error playing game: {e}");
                     }
-                },
-            );
+                }
+            });
         }
         thread_pool.join();
         {
@@ -85,7 +118,9 @@ fn run_games<F: Features>(config: &Config, features: F) -> Result<(), Box<dyn Er
             log::info!(
                 "games={games} / {num_games} draws={draws_percentage:.2}% moves/game = {moves_per_game:.2}\n \
                 entropy/move = {entropy_per_move:.6} samples={samples} games/s={games_per_second:.2}\n  \
-                pv_truncated={pv_truncated} invalid_pv={invalid_pv} ",
+                pv_truncated={pv_truncated} invalid_pv={invalid_pv} book_games={book_games}\n  \
+                skipped: game_decided={game_decided} repeated_pv_position={repeated_pv_position} \
+                in_check={in_check} score_disagreement={score_disagreement}",
                 games = stats.games,
                 num_games = config.num_games,
                 draws_percentage = stats.draws as f64 / stats.games as f64 * 100.0,
@@ -95,6 +130,11 @@ fn run_games<F: Features>(config: &Config, features: F) -> Result<(), Box<dyn Er
                 games_per_second = stats.games as f64 / start_time.elapsed().as_secs_f64(),
                 pv_truncated = stats.pv_truncated,
                 invalid_pv = stats.invalid_pv,
+                book_games = stats.book_games,
+                game_decided = stats.game_decided,
+                repeated_pv_position = stats.repeated_pv_position,
+                in_check = stats.in_check,
+                score_disagreement = stats.score_disagreement,
             );
         }
     }
@@ -105,10 +145,22 @@ fn play_game<F: Features>(
     config: &Config,
     writer: &Mutex<DatasetWriter>,
     evaluator: &Arc<DefaultEvaluator>,
+    openings: &[(SetupMove, SetupMove)],
     features: F,
 ) -> Result<Stats, Box<dyn Error>> {
     let mut rng = StdRng::from_os_rng();
-    let mut position = Position::initial();
+    let book_opening = rng.random_bool(config.book_opening_fraction).then(|| {
+        let &(red, blue) = openings.choose(&mut rng).unwrap();
+        let symmetry = *[Symmetry::Identity, Symmetry::FlipX]
+            .choose(&mut rng)
+            .unwrap();
+        [red, blue].map(|mov| symmetry.apply_to_setup(mov))
+    });
+    let mut position = match config.ply_draw {
+        Some(ply_draw) => Position::initial_with_ply_draw(ply_draw)
+            .map_err(|_| format!("Invalid ply_draw {ply_draw}"))?,
+        None => Position::initial(),
+    };
     let mut history = History::new_from_position(&position);
 
     let hyperparameters = Hyperparameters {
@@ -124,6 +176,7 @@ fn play_game<F: Features>(
     struct Entry {
         pv_position: Position,
         deep_score: Score,
+        policy: Option<(Position, Vec<(Move, Score)>)>,
     }
     let mut entries: Vec<Entry> = Vec::new();
 
@@ -132,47 +185,80 @@ fn play_game<F: Features>(
     let outcome = loop {
         match position.stage() {
             Stage::Setup => {
-                let mov = moverand::random_setup(position.to_move(), &mut rng);
+                let mov = match book_opening {
+                    Some(setups) => setups[position.to_move().index()],
+                    None => moverand::random_setup(position.to_move(), &mut rng),
+                };
                 position = position.make_setup_move(mov).unwrap();
                 history.push_position_irreversible(&position);
             }
             Stage::Regular => {
+                let cutoff = (config.temperature_cutoff * evaluator.scale()) as Eval;
+                // The policy needs every root move scored, not only those within the cutoff.
+                let threshold = if config.record_policy {
+                    Eval::MAX
+                } else {
+                    cutoff
+                };
                 let result = search.search(
                     &position,
                     Some(config.depth),
                     None, /* deadline */
-                    Some((config.temperature_cutoff * evaluator.scale()) as Eval),
+                    Some(threshold),
                     false, /* is_score_important */
                     &history,
                 );
                 assert!(!result.top_moves.is_empty());
                 match calc_deep_score(
+                    config,
                     &position,
                     &history,
                     result.score,
                     &result.pv,
                     &mut search,
-                    config.extra_depth,
+                    evaluator.scale(),
                     &mut prev_pv_position_hash,
                 ) {
                     Ok((pv_position, deep_score)) => {
+                        let policy = config.record_policy.then(|| {
+                            let moves = result.top_moves.iter().map(|m| (m.mov, m.score));
+                            (position, moves.collect())
+                        });
                         entries.push(Entry {
                             pv_position,
                             deep_score,
+                            policy,
                         });
                         stats.samples += 1;
                     }
-                    Err(DeepScoreImpossible::RepeatedPVPosition) => {}
-                    Err(DeepScoreImpossible::GameDecided) => {}
+                    Err(DeepScoreImpossible::RepeatedPVPosition) => {
+                        stats.repeated_pv_position += 1;
+                    }
+                    Err(DeepScoreImpossible::GameDecided) => {
+                        stats.game_decided += 1;
+                    }
                     Err(DeepScoreImpossible::PVTruncated) => {
                         stats.pv_truncated += 1;
                     }
                     Err(DeepScoreImpossible::InvalidPV) => {
                         stats.invalid_pv += 1;
                     }
+                    Err(DeepScoreImpossible::InCheck) => {
+                        stats.in_check += 1;
+                    }
+                    Err(DeepScoreImpossible::ScoreDisagreement) => {
+                        stats.score_disagreement += 1;
+                    }
                 }
+                let min_score = result.score.offset(-cutoff);
+                let candidates: Vec<ScoredMove> = result
+                    .top_moves
+                    .iter()
+                    .copied()
+                    .filter(|m| m.score >= min_score)
+                    .collect();
                 let (entropy, mov) = select_move(
-                    &result.top_moves,
+                    &candidates,
                     &mut rng,
                     config.temperature * evaluator.scale(),
                 );
@@ -185,28 +271,38 @@ fn play_game<F: Features>(
         }
     };
     stats.games += 1;
+    if book_opening.is_some() {
+        stats.book_games += 1;
+    }
     if outcome == Outcome::Draw {
         stats.draws += 1;
     }
     let mut writer = writer.lock().unwrap();
     for entry in entries {
         let to_move = entry.pv_position.to_move();
-        let f = [to_move, to_move.opposite()].map(|color| {
-            features
-                .all(&entry.pv_position, color)
-                .map(|x| u16::try_from(x).unwrap())
-                .collect()
-        });
+        let f = position_features(features, &entry.pv_position);
         let deep_value = match entry.deep_score.into() {
             ScoreExpanded::Win(_) => f32::INFINITY,
             ScoreExpanded::Eval(eval) => eval as f32 / evaluator.scale() as f32,
             ScoreExpanded::Loss(_) => -f32::INFINITY,
         };
         let game_points = outcome.points(to_move);
+        let policy = entry.policy.map(|(root_position, moves)| Policy {
+            features: position_features(features, &root_position),
+            moves: moves
+                .iter()
+                .filter_map(|&(mov, score)| match score.into() {
+                    ScoreExpanded::Eval(eval) => Some((mov.to_string(), eval)),
+                    _ => None,
+                })
+                .collect(),
+        });
         let sample = Sample {
             features: f,
             deep_value,
             game_points,
+            policy,
+            book_opening: book_opening.is_some(),
         };
         writer.write(&sample)?;
     }
@@ -214,26 +310,41 @@ fn play_game<F: Features>(
     Ok(stats)
 }
 
+/// [to move, other]
+fn position_features<F: Features>(features: F, position: &Position) -> [Vec<u16>; 2] {
+    let to_move = position.to_move();
+    [to_move, to_move.opposite()].map(|color| {
+        features
+            .all(position, color)
+            .map(|x| u16::try_from(x).unwrap())
+            .collect()
+    })
+}
+
 enum DeepScoreImpossible {
     GameDecided,
     PVTruncated,
     InvalidPV,
     RepeatedPVPosition,
+    InCheck,
+    ScoreDisagreement,
 }
 
 /// Returns the PV position and the deep score.
+#[allow(clippy::too_many_arguments)]
 fn calc_deep_score(
+    config: &Config,
     position: &Position,
     history: &History,
     score: Score,
     pv: &LongVariation,
     search: &mut Search<DefaultEvaluator>,
-    extra_depth: Depth,
+    scale: f64,
     prev_pv_position_hash: &mut u64,
 ) -> Result<(Position, Score), DeepScoreImpossible> {
-    if !matches!(score.into(), ScoreExpanded::Eval(_)) {
+    let ScoreExpanded::Eval(shallow_eval) = score.into() else {
         return Err(DeepScoreImpossible::GameDecided);
-    }
+    };
     if pv.truncated {
         return Err(DeepScoreImpossible::PVTruncated);
     }
@@ -251,14 +362,43 @@ fn calc_deep_score(
         return Err(DeepScoreImpossible::RepeatedPVPosition);
     }
     *prev_pv_position_hash = hash;
-    let result = search.search(
-        &pv_position,
-        Some(extra_depth),
-        None, /* deadline */
-        None, /* multi_move_threshold */
-        true, /* is_score_important */
-        &pv_history,
-    );
+    if config.skip_in_check && movegen::in_check(&pv_position, pv_position.to_move()) {
+        return Err(DeepScoreImpossible::InCheck);
+    }
+    let result = match config.stable_margin {
+        Some(margin) => search.search_until_stable(
+            &pv_position,
+            margin,
+            config.extra_depth,
+            None, /* deadline */
+            &pv_history,
+        ),
+        None => search.search(
+            &pv_position,
+            Some(config.extra_depth),
+            None, /* deadline */
+            None, /* multi_move_threshold */
+            true, /* is_score_important */
+            &pv_history,
+        ),
+    };
+    if let Some(max_disagreement) = config.max_score_disagreement {
+        // The shallow score is from the point of view of the side to move at the root.
+        let deep_score = if pv_position.to_move() == position.to_move() {
+            result.score
+        } else {
+            -result.score
+        };
+        let agrees = match deep_score.into() {
+            ScoreExpanded::Eval(deep_eval) => {
+                f64::from((deep_eval - shallow_eval).abs()) <= max_disagreement * scale
+            }
+            _ => false,
+        };
+        if !agrees {
+            return Err(DeepScoreImpossible::ScoreDisagreement);
+        }
+    }
     Ok((pv_position, result.score))
 }
 
@@ -300,6 +440,11 @@ struct Stats {
     samples: u64,
     pv_truncated: u64,
     invalid_pv: u64,
+    book_games: u64,
+    game_decided: u64,
+    repeated_pv_position: u64,
+    in_check: u64,
+    score_disagreement: u64,
 }
 
 impl Stats {
@@ -312,6 +457,11 @@ impl Stats {
             samples: 0,
             pv_truncated: 0,
             invalid_pv: 0,
+            book_games: 0,
+            game_decided: 0,
+            repeated_pv_position: 0,
+            in_check: 0,
+            score_disagreement: 0,
         }
     }
 
@@ -323,5 +473,10 @@ impl Stats {
         self.samples += stats.samples;
         self.pv_truncated += stats.pv_truncated;
         self.invalid_pv += stats.invalid_pv;
+        self.book_games += stats.book_games;
+        self.game_decided += stats.game_decided;
+        self.repeated_pv_position += stats.repeated_pv_position;
+        self.in_check += stats.in_check;
+        self.score_disagreement += stats.score_disagreement;
     }
 }
@@ -5,7 +5,7 @@ use crate::{
     model::EvalModel,
     nnue::{self, NnueModel},
 };
-use extra::PSFeatures;
+use extra::{PSFeatures, WazirSafetyFeatures};
 use plotters::{
     backend::SVGBackend,
     chart::ChartBuilder,
@@ -44,6 +44,9 @@ pub fn run(config_dir: &Path, config: &Config) -> Result<(), Box<dyn Error>> {
     match config.dataset.features {
         FeaturesConfig::PS => run_with_features(PSFeatures, config_dir, config),
         FeaturesConfig::WPS => run_with_features(WPSFeatures, config_dir, config),
+        FeaturesConfig::WPSSafety => {
+            run_with_features(WazirSafetyFeatures::new(WPSFeatures), config_dir, config)
+        }
     }
 }
 
@@ -85,7 +88,7 @@ fn run_with_model<M: EvalModel>(
     let mut total_loss: f64 = 0.0;
     let mut total_min_loss: f64 = 0.0;
     let start_time = Instant::now();
-    let mut dataset_iterator = DatasetIterator::new(&config.dataset)?;
+    let mut dataset_iterator = DatasetIterator::new(&config.dataset, 0)?;
     while let Some(batch) = dataset_iterator.next_batch()? {
         let batch = batch.to_device(device);
         let values = model.forward(&batch.features, &batch.offsets);
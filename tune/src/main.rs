@@ -1,9 +1,8 @@
 use clap::Parser;
-use log::LevelFilter;
+use extra::logging::{self, VerbosityArgs};
 use rand::{Rng, SeedableRng, rngs::StdRng};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde::Deserialize;
-use simplelog::{ColorChoice, CombinedLogger, TermLogger, TerminalMode, WriteLogger};
 use std::{
     array,
     error::Error,
@@ -14,13 +13,15 @@ use std::{
     time::{Duration, Instant},
 };
 use wazir_drop::{
-    AnyMove, Color, DefaultEvaluator, MainPlayerFactory, PlayerFactory, constants::Hyperparameters,
-    enums::EnumMap,
+    AnyMove, Color, DefaultEvaluator, Evaluator, MainPlayerFactory, PlayerFactory, Position,
+    clock::TimeControl, constants::Hyperparameters, enums::EnumMap,
 };
 
 #[derive(Parser, Debug)]
 struct Args {
     config: PathBuf,
+    #[command(flatten)]
+    verbosity: VerbosityArgs,
 }
 
 #[derive(Debug, Deserialize)]
@@ -74,15 +75,7 @@ fn run() -> Result<(), Box<dyn Error>> {
     let config_dir = args.config.parent().unwrap();
     let log_path = config_dir.join(&config.log);
     let log_file = File::create(log_path)?;
-    CombinedLogger::init(vec![
-        WriteLogger::new(LevelFilter::Info, simplelog::Config::default(), log_file),
-        TermLogger::new(
-            LevelFilter::Info,
-            simplelog::Config::default(),
-            TerminalMode::Stderr,
-            ColorChoice::Auto,
-        ),
-    ])?;
+    logging::init_logger(args.verbosity.verbosity(), log_file)?;
 
     rayon::ThreadPoolBuilder::new()
         .num_threads(config.cpus)
@@ -126,7 +119,11 @@ fn run_tune(config: &Config) {
         );
         log_parameters(config, &parameters);
     }
-    log::info!("Results");
+    log::info!(
+        "Results (evaluator {name} scale {scale}, see Hyperparameters::rescale)",
+        name = evaluator.name(),
+        scale = evaluator.scale(),
+    );
     for (i, &param) in parameters.iter().enumerate() {
         let c = &config.parameter[i];
         let value = unnormalize(c, param);
@@ -274,23 +271,36 @@ fn run_round(
     let player_plus = MainPlayerFactory::new(&hyper_plus, evaluator);
     let hyper_minus = to_hyperparameters(config, &sub_parameters(parameters, &round_config.delta));
     let player_minus = MainPlayerFactory::new(&hyper_minus, evaluator);
-    let time_limits = EnumMap::from_fn(|_| Some(Duration::from_millis(config.time_limit_ms)));
+    let time_control = TimeControl::fixed(Duration::from_millis(config.time_limit_ms));
+    let time_controls = EnumMap::from_fn(|_| Some(time_control));
 
     let player_factories = EnumMap::from_fn(|color| match color {
         Color::Red => &player_plus as &dyn PlayerFactory,
         Color::Blue => &player_minus as &dyn PlayerFactory,
     });
-    let points0 = referee::run_game("", player_factories, &round_config.opening, time_limits)
-        .outcome
-        .points(Color::Red);
+    let points0 = referee::run_game(
+        "",
+        player_factories,
+        &Position::initial(),
+        &round_config.opening,
+        time_controls,
+    )
+    .outcome
+    .points(Color::Red);
 
     let player_factories = EnumMap::from_fn(|color| match color {
         Color::Red => &player_minus as &dyn PlayerFactory,
         Color::Blue => &player_plus as &dyn PlayerFactory,
     });
-    let points1 = referee::run_game("", player_factories, &round_config.opening, time_limits)
-        .outcome
-        .points(Color::Blue);
+    let points1 = referee::run_game(
+        "",
+        player_factories,
+        &Position::initial(),
+        &round_config.opening,
+        time_controls,
+    )
+    .outcome
+    .points(Color::Blue);
 
     let points = (points0 + points1) as f64;
     array::from_fn(|i| points / (2.0 * round_config.delta[i]))